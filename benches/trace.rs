@@ -3,7 +3,6 @@ use opentelemetry::{
     api::{Key, Provider, Span, Tracer},
     sdk,
 };
-
 fn criterion_benchmark(c: &mut Criterion) {
     trace_benchmark_group(c, "start-end-span", |tracer| {
         tracer.start("foo", None).end()
@@ -64,9 +63,29 @@ fn criterion_benchmark(c: &mut Criterion) {
         );
         span.end();
     });
+
+    trace_benchmark_group(
+        c,
+        "start-end-span-32-attrs-16-events",
+        many_attributes_and_events,
+    );
+}
+
+fn many_attributes_and_events(tracer: &sdk::Tracer) {
+    let span = tracer.start("foo", None);
+    for i in 0..32 {
+        span.set_attribute(Key::new(format!("key{}", i)).string("hello"));
+    }
+    for _ in 0..16 {
+        span.add_event(
+            "event".to_string(),
+            vec![Key::new("key").string("hello"), Key::new("count").i64(1)],
+        );
+    }
+    span.end();
 }
 
-fn trace_benchmark_group<F: Fn(&sdk::Tracer) -> ()>(c: &mut Criterion, name: &str, f: F) {
+fn trace_benchmark_group<F: Fn(&sdk::Tracer)>(c: &mut Criterion, name: &str, f: F) {
     let mut group = c.benchmark_group(name);
 
     group.bench_function("always-sample", |b| {
@@ -92,6 +111,17 @@ fn trace_benchmark_group<F: Fn(&sdk::Tracer) -> ()>(c: &mut Criterion, name: &st
         b.iter(|| f(&never_sample));
     });
 
+    group.bench_function("one-percent-sample", |b| {
+        let one_percent_sample = sdk::Provider::builder()
+            .with_config(sdk::Config {
+                default_sampler: Box::new(sdk::Sampler::Probability(0.01)),
+                ..Default::default()
+            })
+            .build()
+            .get_tracer("one-percent-sample");
+        b.iter(|| f(&one_percent_sample));
+    });
+
     group.finish();
 }
 
@@ -57,9 +57,9 @@ fn init_tracer() -> thrift::Result<()> {
         })
         .init()?;
 
-    // Configure your async library of choice. E.g. `async_std::spawn` or similar function can be
-    // used here in place of `tokio::spawn`, etc.
-    let batch = sdk::BatchSpanProcessor::builder(exporter, tokio::spawn, tokio::time::interval)
+    // Run the batch worker on tokio, enabled by the `rt-tokio` feature. With the `rt-async-std`
+    // feature, `async_std_builder` can be used instead.
+    let batch = sdk::BatchSpanProcessor::tokio_builder(exporter)
         .with_scheduled_delay(Duration::from_millis(100))
         .build();
 
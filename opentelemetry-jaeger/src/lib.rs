@@ -118,7 +118,7 @@ static DEFAULT_AGENT_ENDPOINT: &str = "127.0.0.1:6831";
 #[derive(Debug)]
 pub struct Exporter {
     process: jaeger::Process,
-    uploader: Mutex<uploader::BatchUploader>,
+    uploader: Arc<Mutex<uploader::BatchUploader>>,
 }
 
 /// Jaeger process configuration
@@ -151,18 +151,33 @@ impl Exporter {
             .with_agent_endpoint(DEFAULT_AGENT_ENDPOINT.parse().unwrap())
             .init()
     }
+
+    fn jaeger_batch(&self, batch: Vec<Arc<trace::SpanData>>) -> jaeger::Batch {
+        let jaeger_spans = batch.into_iter().map(Into::into).collect();
+        jaeger::Batch::new(self.process.clone(), jaeger_spans)
+    }
+}
+
+/// Upload a batch, uploads of concurrent exports are serialized.
+fn upload(uploader: &Mutex<uploader::BatchUploader>, batch: jaeger::Batch) -> trace::ExportResult {
+    match uploader.lock() {
+        Ok(mut uploader) => uploader.upload(batch),
+        Err(_) => trace::ExportResult::FailedNotRetryable,
+    }
 }
 
 impl trace::SpanExporter for Exporter {
     /// Export spans to Jaeger
     fn export(&self, batch: Vec<Arc<trace::SpanData>>) -> trace::ExportResult {
-        match self.uploader.lock() {
-            Ok(mut uploader) => {
-                let jaeger_spans = batch.into_iter().map(Into::into).collect();
-                uploader.upload(jaeger::Batch::new(self.process.clone(), jaeger_spans))
-            }
-            Err(_) => trace::ExportResult::FailedNotRetryable,
-        }
+        upload(&self.uploader, self.jaeger_batch(batch))
+    }
+
+    /// Export spans to Jaeger from a separate thread, so that the upload can
+    /// be timed out.
+    fn export_async(&self, batch: Vec<Arc<trace::SpanData>>) -> trace::ExportFuture<'_> {
+        let uploader = self.uploader.clone();
+        let batch = self.jaeger_batch(batch);
+        trace::spawn_export(move || upload(&uploader, batch))
     }
 
     /// Ignored for now.
@@ -253,7 +268,7 @@ impl<T: net::ToSocketAddrs> Builder<T> {
 
         Ok(Exporter {
             process: process.into(),
-            uploader: Mutex::new(uploader),
+            uploader: Arc::new(Mutex::new(uploader)),
         })
     }
 
@@ -320,6 +335,13 @@ impl Into<jaeger::Log> for api::Event {
             .map(Into::into)
             .collect::<Vec<_>>();
         fields.push(api::Key::new("name").string(self.name).into());
+        if self.dropped_attributes_count != 0 {
+            fields.push(
+                api::Key::new("otel.dropped_attributes_count")
+                    .i64(i64::from(self.dropped_attributes_count))
+                    .into(),
+            );
+        }
 
         jaeger::Log::new(timestamp, fields)
     }
@@ -382,7 +404,7 @@ fn links_to_references(links: &sdk::EvictedQueue<api::Link>) -> Option<Vec<jaege
 }
 
 fn build_tags(span_data: &Arc<trace::SpanData>) -> Option<Vec<jaeger::Tag>> {
-    let mut tags = Vec::with_capacity(span_data.attributes.len() + span_data.resource.len() + 4);
+    let mut tags = Vec::with_capacity(span_data.attributes.len() + span_data.resource.len() + 7);
     let mut user_specified_error = false;
     for (key, value) in span_data.attributes.iter() {
         tags.push(api::KeyValue::new(key.clone(), value.clone()).into());
@@ -413,9 +435,31 @@ fn build_tags(span_data: &Arc<trace::SpanData>) -> Option<Vec<jaeger::Tag>> {
             .into(),
     );
 
+    // Report data dropped because of the span limits
+    for (key, count) in dropped_counts(span_data) {
+        tags.push(api::Key::new(key).i64(i64::from(count)).into());
+    }
+
     Some(tags)
 }
 
+/// Non-zero dropped attribute, event and link counts of a span.
+fn dropped_counts(span_data: &trace::SpanData) -> impl Iterator<Item = (&'static str, u32)> {
+    vec![
+        (
+            "otel.dropped_attributes_count",
+            span_data.dropped_attributes_count(),
+        ),
+        (
+            "otel.dropped_events_count",
+            span_data.dropped_events_count(),
+        ),
+        ("otel.dropped_links_count", span_data.dropped_links_count()),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count != 0)
+}
+
 fn events_to_logs(events: &sdk::EvictedQueue<api::Event>) -> Option<Vec<jaeger::Log>> {
     if events.is_empty() {
         None
@@ -56,7 +56,7 @@ static DEFAULT_COLLECTOR_ENDPOINT: &str = "127.0.0.1:9411";
 #[derive(Debug)]
 pub struct Exporter {
     config: ExporterConfig,
-    uploader: uploader::Uploader,
+    uploader: Arc<uploader::Uploader>,
 }
 
 /// Zipkin-specific configuration used to initialize the `Exporter`.
@@ -151,22 +151,35 @@ impl Exporter {
     pub fn from_config(config: ExporterConfig) -> Self {
         Exporter {
             config: config.clone(),
-            uploader: uploader::Uploader::new(
+            uploader: Arc::new(uploader::Uploader::new(
                 config.collector_endpoint,
                 uploader::UploaderFormat::HTTP,
-            ),
+            )),
         }
     }
+
+    fn zipkin_spans(&self, batch: Vec<Arc<trace::SpanData>>) -> span::ListOfSpans {
+        span::ListOfSpans(
+            batch
+                .into_iter()
+                .map(|span| into_zipkin_span(&self.config, span))
+                .collect(),
+        )
+    }
 }
 
 impl trace::SpanExporter for Exporter {
     /// Export spans to Zipkin collector.
     fn export(&self, batch: Vec<Arc<trace::SpanData>>) -> trace::ExportResult {
-        let zipkin_spans: Vec<span::Span> = batch
-            .into_iter()
-            .map(|span| into_zipkin_span(&self.config, span))
-            .collect();
-        self.uploader.upload(span::ListOfSpans(zipkin_spans))
+        self.uploader.upload(self.zipkin_spans(batch))
+    }
+
+    /// Export spans to Zipkin collector from a separate thread, so that the
+    /// upload can be timed out.
+    fn export_async(&self, batch: Vec<Arc<trace::SpanData>>) -> trace::ExportFuture<'_> {
+        let uploader = self.uploader.clone();
+        let spans = self.zipkin_spans(batch);
+        trace::spawn_export(move || uploader.upload(spans))
     }
 
     fn shutdown(&self) {}
@@ -245,11 +258,31 @@ fn into_zipkin_span(config: &ExporterConfig, span_data: Arc<trace::SpanData>) ->
                         .resource
                         .iter()
                         .map(|(k, v)| api::KeyValue::new(k.clone(), v.clone())),
-                ),
+                )
+                .chain(dropped_counts(&span_data)),
         ))
         .build()
 }
 
+/// Tags for non-zero dropped attribute, event and link counts of a span.
+fn dropped_counts(span_data: &trace::SpanData) -> Vec<api::KeyValue> {
+    vec![
+        (
+            "otel.dropped_attributes_count",
+            span_data.dropped_attributes_count(),
+        ),
+        (
+            "otel.dropped_events_count",
+            span_data.dropped_events_count(),
+        ),
+        ("otel.dropped_links_count", span_data.dropped_links_count()),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count != 0)
+    .map(|(key, count)| api::Key::new(key).i64(i64::from(count)))
+    .collect()
+}
+
 fn map_from_kvs<T>(kvs: T) -> HashMap<String, String>
 where
     T: IntoIterator<Item = api::KeyValue>,
@@ -260,3 +293,32 @@ where
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::dropped_counts;
+    use opentelemetry::api::{self, Provider, Span, Tracer};
+    use opentelemetry::exporter::trace::in_memory::InMemorySpanExporter;
+    use opentelemetry::sdk;
+
+    #[test]
+    fn reports_dropped_counts_like_jaeger() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(sdk::Config {
+                max_events_per_span: 1,
+                ..Default::default()
+            })
+            .build();
+        let span = provider.get_tracer("test").start("span", None);
+        span.add_event("first".to_string(), Vec::new());
+        span.add_event("second".to_string(), Vec::new());
+        drop(span);
+
+        assert_eq!(
+            dropped_counts(&exporter.get_finished_spans()[0]),
+            vec![api::Key::new("otel.dropped_events_count").i64(1)]
+        );
+    }
+}
@@ -4,3 +4,4 @@ set -eu
 
 cargo test --all "$@"
 cargo test --all "$@" --features="default serialize base64_format"
+cargo test --all "$@" --all-features
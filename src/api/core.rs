@@ -172,7 +172,7 @@ impl KeyValue {
 }
 
 /// Units denote underlying data units tracked by `Meter`s.
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
 pub struct Unit(String);
 
 impl Unit {
@@ -12,7 +12,9 @@
 //! in which case they support positive and negative increments.
 //! `monotonic = false` counters are useful to report changes in an
 //! accounting scheme, such as the number of bytes allocated and
-//! deallocated.
+//! deallocated, although an `UpDownCounter` states this intent more clearly.
+//!
+//! Negative increments of monotonic counters are invalid, SDKs may drop them.
 use crate::api::metrics;
 
 /// An interface for recording values where the sum is of primary interest.
@@ -11,7 +11,7 @@
 //! `Measure`s are defined as `with_absolute(true)` by default,
 //! meaning that negative values are invalid. `absolute = true`
 //! measures are typically used to record absolute values such as
-//! durations and sizes.
+//! durations and sizes, SDKs may drop negative values they record.
 //!
 //! When passing `MetricOptions`, measures can be declared as
 //! `with_absolute(false)` to indicate support for positive and negative values.
@@ -8,7 +8,10 @@
 //! "metric instrument" to refer to a program object, allocated through the
 //! API, used for recording metrics. There are three distinct instruments
 //! in the Metrics API, commonly known as `Counter`s, `Gauge`s, and
-//! `Measure`s.
+//! `Measure`s. Sums that may also decrease are recorded by `UpDownCounter`s,
+//! and values that are only available on demand can be reported
+//! asynchronously by `Observer`s, whose callbacks run when metrics are
+//! collected.
 //!
 //! Monitoring and alerting are the common use-case for the data provided
 //! through metric instruments, after various collection and aggregation
@@ -61,11 +64,16 @@ pub mod counter;
 pub mod gauge;
 pub mod measure;
 pub mod noop;
+pub mod observer;
+pub mod provider;
+pub mod up_down_counter;
 pub mod value;
 
 use counter::Counter;
 use gauge::Gauge;
 use measure::Measure;
+use observer::{BatchObserver, Observer, ObserverResult};
+use up_down_counter::UpDownCounter;
 use value::MeasurementValue;
 
 /// The implementation-level interface to Set/Add/Record individual
@@ -106,12 +114,23 @@ pub struct MetricOptions {
     /// - for `Counter`, `true` implies that the metric is an up-down
     ///   `Counter`
     ///
-    /// - for `Gauge`, `true` implies that the metric is a
-    ///   non-descending `Gauge`
+    /// - for `Gauge` and `Observer`, `true` implies that the metric is
+    ///   non-descending
     ///
     /// - for `Measure`, `true` implies that the metric supports
     ///   positive and negative values
     pub alternate: bool,
+
+    /// Monotonic overrides the default monotonicity of a `Counter`, a `Gauge`
+    /// or an `Observer` when set, taking precedence over `alternate`. It is
+    /// ignored by other instruments.
+    pub monotonic: Option<bool>,
+
+    /// Boundaries are the upper bounds of the histogram buckets of a
+    /// `Measure`, in increasing order. They must be finite. If unset, the
+    /// SDK chooses the boundaries. SDKs that do not aggregate measures into
+    /// histograms ignore them.
+    pub boundaries: Option<Vec<f64>>,
 }
 
 impl MetricOptions {
@@ -133,10 +152,23 @@ impl MetricOptions {
         MetricOptions { keys, ..self }
     }
 
-    /// Set monotonic for the given set of metric options.
-    pub fn with_monotonic(self, _monotonic: bool) -> Self {
-        // TODO figure out counter vs gauge issue here.
-        unimplemented!()
+    /// Set explicit histogram bucket boundaries for the current set of metric
+    /// options, see `linear_boundaries` and `exponential_boundaries`.
+    pub fn with_boundaries(self, boundaries: Vec<f64>) -> Self {
+        MetricOptions {
+            boundaries: Some(boundaries),
+            ..self
+        }
+    }
+
+    /// Set monotonic for the given set of metric options. Counters are
+    /// monotonic by default, `false` makes them up-down counters. Gauges and
+    /// observers are not, `true` makes them non-descending.
+    pub fn with_monotonic(self, monotonic: bool) -> Self {
+        MetricOptions {
+            monotonic: Some(monotonic),
+            ..self
+        }
     }
 
     /// Set absolute for the given set of metric options.
@@ -148,6 +180,37 @@ impl MetricOptions {
     }
 }
 
+/// Returns `count` histogram boundaries, starting at `start` and spaced
+/// `width` apart.
+///
+/// ```
+/// use opentelemetry::api::metrics::linear_boundaries;
+///
+/// assert_eq!(linear_boundaries(10.0, 5.0, 3), vec![10.0, 15.0, 20.0]);
+/// ```
+pub fn linear_boundaries(start: f64, width: f64, count: usize) -> Vec<f64> {
+    (0..count).map(|i| start + width * i as f64).collect()
+}
+
+/// Returns `count` histogram boundaries, starting at `start` and each
+/// `factor` times the previous one.
+///
+/// ```
+/// use opentelemetry::api::metrics::exponential_boundaries;
+///
+/// assert_eq!(exponential_boundaries(1.0, 10.0, 3), vec![1.0, 10.0, 100.0]);
+/// ```
+pub fn exponential_boundaries(start: f64, factor: f64, count: usize) -> Vec<f64> {
+    let mut boundaries = Vec::with_capacity(count);
+    let mut boundary = start;
+    for _ in 0..count {
+        boundaries.push(boundary);
+        boundary *= factor;
+    }
+
+    boundaries
+}
+
 /// Used to record `MeasurementValue`s for a given `Instrument` for use in
 /// batch recording by a `Meter`.
 #[allow(missing_debug_implementations)]
@@ -208,6 +271,10 @@ pub trait Meter {
     type I64Counter: Counter<i64, Self::LabelSet>;
     /// The `F64Counter` data type for this meter.
     type F64Counter: Counter<f64, Self::LabelSet>;
+    /// The `I64UpDownCounter` data type for this meter.
+    type I64UpDownCounter: UpDownCounter<i64, Self::LabelSet>;
+    /// The `F64UpDownCounter` data type for this meter.
+    type F64UpDownCounter: UpDownCounter<f64, Self::LabelSet>;
     /// The `I64Gauge` data type for this meter.
     type I64Gauge: Gauge<i64, Self::LabelSet>;
     /// The `F64Gauge` data type for this meter.
@@ -216,6 +283,12 @@ pub trait Meter {
     type I64Measure: Measure<i64, Self::LabelSet>;
     /// The `F64Measure` data type for this meter.
     type F64Measure: Measure<f64, Self::LabelSet>;
+    /// The `I64Observer` data type for this meter.
+    type I64Observer: Observer<i64, Self::LabelSet>;
+    /// The `F64Observer` data type for this meter.
+    type F64Observer: Observer<f64, Self::LabelSet>;
+    /// The `BatchObserver` data type for this meter.
+    type BatchObserver: BatchObserver<Self::LabelSet>;
 
     /// Returns a reference to a set of labels that cannot be read by the application.
     fn labels(&self, key_values: Vec<api::KeyValue>) -> Self::LabelSet;
@@ -226,6 +299,20 @@ pub trait Meter {
     /// Creates a new `f64` counter with a given name and customized with passed options.
     fn new_f64_counter<S: Into<String>>(&self, name: S, opts: MetricOptions) -> Self::F64Counter;
 
+    /// Creates a new `i64` up-down counter with a given name and customized with passed options.
+    fn new_i64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: MetricOptions,
+    ) -> Self::I64UpDownCounter;
+
+    /// Creates a new `f64` up-down counter with a given name and customized with passed options.
+    fn new_f64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: MetricOptions,
+    ) -> Self::F64UpDownCounter;
+
     /// Creates a new `i64` gauge with a given name and customized with passed options.
     fn new_i64_gauge<S: Into<String>>(&self, name: S, opts: MetricOptions) -> Self::I64Gauge;
 
@@ -238,6 +325,34 @@ pub trait Meter {
     /// Creates a new `f64` measure with a given name and customized with passed options.
     fn new_f64_measure<S: Into<String>>(&self, name: S, opts: MetricOptions) -> Self::F64Measure;
 
+    /// Registers a new `i64` observer with a given name and customized with
+    /// passed options. The callback is run each time metrics are collected.
+    fn register_i64_observer<S, F>(
+        &self,
+        name: S,
+        opts: MetricOptions,
+        callback: F,
+    ) -> Self::I64Observer
+    where
+        S: Into<String>,
+        F: Fn(ObserverResult<'_, i64, Self::LabelSet>) + Send + Sync + 'static;
+
+    /// Registers a new `f64` observer with a given name and customized with
+    /// passed options. The callback is run each time metrics are collected.
+    fn register_f64_observer<S, F>(
+        &self,
+        name: S,
+        opts: MetricOptions,
+        callback: F,
+    ) -> Self::F64Observer
+    where
+        S: Into<String>,
+        F: Fn(ObserverResult<'_, f64, Self::LabelSet>) + Send + Sync + 'static;
+
+    /// Creates a new batch observer, whose instruments are observed by the
+    /// callbacks registered with `BatchObserver::register_callback`.
+    fn new_batch_observer(&self) -> Self::BatchObserver;
+
     /// Atomically records a batch of measurements.
     fn record_batch<M: IntoIterator<Item = Measurement<Self::LabelSet>>>(
         &self,
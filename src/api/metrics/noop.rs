@@ -7,6 +7,19 @@ use crate::api;
 use std::marker;
 use std::sync::Arc;
 
+/// A no-op instance of a `MeterProvider`.
+#[derive(Clone, Debug, Default)]
+pub struct NoopMeterProvider {}
+
+impl api::MeterProvider for NoopMeterProvider {
+    type Meter = NoopMeter;
+
+    /// Returns a new `NoopMeter` instance.
+    fn versioned_meter(&self, _name: &'static str, _version: Option<&'static str>) -> NoopMeter {
+        NoopMeter {}
+    }
+}
+
 /// A no-op instance of a `Meter`.
 #[derive(Clone, Debug)]
 pub struct NoopMeter {}
@@ -15,10 +28,15 @@ impl api::Meter for NoopMeter {
     type LabelSet = NoopLabelSet;
     type I64Counter = NoopCounter<i64>;
     type F64Counter = NoopCounter<f64>;
+    type I64UpDownCounter = NoopUpDownCounter<i64>;
+    type F64UpDownCounter = NoopUpDownCounter<f64>;
     type I64Gauge = NoopGauge<i64>;
     type F64Gauge = NoopGauge<f64>;
     type I64Measure = NoopMeasure<i64>;
     type F64Measure = NoopMeasure<f64>;
+    type I64Observer = NoopObserver<i64>;
+    type F64Observer = NoopObserver<f64>;
+    type BatchObserver = NoopBatchObserver;
 
     /// Returns a no-op `NoopLabelSet`.
     fn labels(&self, _key_values: Vec<api::KeyValue>) -> Self::LabelSet {
@@ -47,6 +65,28 @@ impl api::Meter for NoopMeter {
         }
     }
 
+    /// Returns a no-op `I64UpDownCounter` instance.
+    fn new_i64_up_down_counter<S: Into<String>>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+    ) -> Self::I64UpDownCounter {
+        NoopUpDownCounter {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns a no-op `F64UpDownCounter` instance.
+    fn new_f64_up_down_counter<S: Into<String>>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+    ) -> Self::F64UpDownCounter {
+        NoopUpDownCounter {
+            _marker: marker::PhantomData,
+        }
+    }
+
     /// Returns a no-op `I64Gauge` instance.
     fn new_i64_gauge<S: Into<String>>(
         &self,
@@ -91,6 +131,43 @@ impl api::Meter for NoopMeter {
         }
     }
 
+    /// Returns a no-op `I64Observer` instance, the callback is never run.
+    fn register_i64_observer<S, F>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+        _callback: F,
+    ) -> Self::I64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, i64, NoopLabelSet>) + Send + Sync + 'static,
+    {
+        NoopObserver {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns a no-op `F64Observer` instance, the callback is never run.
+    fn register_f64_observer<S, F>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+        _callback: F,
+    ) -> Self::F64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, f64, NoopLabelSet>) + Send + Sync + 'static,
+    {
+        NoopObserver {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns a no-op `BatchObserver` instance.
+    fn new_batch_observer(&self) -> Self::BatchObserver {
+        NoopBatchObserver {}
+    }
+
     /// Ignores batch recordings
     fn record_batch<M: IntoIterator<Item = api::Measurement<NoopLabelSet>>>(
         &self,
@@ -158,6 +235,59 @@ impl<T> api::Instrument<NoopLabelSet> for NoopCounter<T> {
     }
 }
 
+/// A no-op instance of an `UpDownCounter`.
+#[derive(Clone, Debug)]
+pub struct NoopUpDownCounter<T> {
+    _marker: marker::PhantomData<T>,
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::UpDownCounter<T, NoopLabelSet>
+    for NoopUpDownCounter<T>
+{
+    type Handle = NoopUpDownCounterHandle<T>;
+
+    /// Returns a no-op `Measurement`.
+    fn measurement(&self, value: T) -> api::Measurement<NoopLabelSet> {
+        let handle: NoopHandle<T> = NoopHandle {
+            _marker: marker::PhantomData,
+        };
+
+        api::Measurement::new(Arc::new(handle), value.into())
+    }
+
+    /// Returns a `NoopUpDownCounterHandle`
+    fn acquire_handle(&self, _labels: &NoopLabelSet) -> Self::Handle {
+        NoopUpDownCounterHandle {
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> api::Instrument<NoopLabelSet> for NoopUpDownCounter<T> {
+    /// Ignores all recorded measurement values.
+    fn record_one(&self, _value: api::MeasurementValue, _labels: &NoopLabelSet) {
+        // Ignored
+    }
+}
+
+/// A no-op instance of an `UpDownCounterHandle`.
+#[derive(Clone, Debug)]
+pub struct NoopUpDownCounterHandle<T> {
+    _marker: marker::PhantomData<T>,
+}
+
+impl<T> api::InstrumentHandle for NoopUpDownCounterHandle<T> {
+    /// Ignores all measurement values.
+    fn record_one(&self, _value: api::MeasurementValue) {
+        // Ignored
+    }
+}
+
+impl<T> api::UpDownCounterHandle<T> for NoopUpDownCounterHandle<T> where
+    T: Into<api::MeasurementValue>
+{
+}
+
 /// A no-op instance of a `Gauge`.
 #[derive(Clone, Debug)]
 pub struct NoopGauge<T> {
@@ -266,3 +396,66 @@ impl<T> api::Instrument<NoopLabelSet> for NoopMeasure<T> {
         // Ignored
     }
 }
+
+/// A no-op instance of an `Observer`.
+#[derive(Clone, Debug)]
+pub struct NoopObserver<T> {
+    _marker: marker::PhantomData<T>,
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::Observer<T, NoopLabelSet> for NoopObserver<T> {
+    /// Returns a no-op `Measurement`.
+    fn observation(&self, value: T) -> api::Measurement<NoopLabelSet> {
+        let handle: NoopHandle<T> = NoopHandle {
+            _marker: marker::PhantomData,
+        };
+
+        api::Measurement::new(Arc::new(handle), value.into())
+    }
+}
+
+impl<T> api::Instrument<NoopLabelSet> for NoopObserver<T> {
+    /// Ignores all observed values and labels.
+    fn record_one(&self, _value: api::MeasurementValue, _labels: &NoopLabelSet) {
+        // Ignored
+    }
+}
+
+/// A no-op instance of a `BatchObserver`.
+#[derive(Clone, Debug)]
+pub struct NoopBatchObserver {}
+
+impl api::BatchObserver<NoopLabelSet> for NoopBatchObserver {
+    type I64Observer = NoopObserver<i64>;
+    type F64Observer = NoopObserver<f64>;
+
+    /// Returns a no-op `I64Observer` instance.
+    fn new_i64_observer<S: Into<String>>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+    ) -> Self::I64Observer {
+        NoopObserver {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns a no-op `F64Observer` instance.
+    fn new_f64_observer<S: Into<String>>(
+        &self,
+        _name: S,
+        _opts: api::MetricOptions,
+    ) -> Self::F64Observer {
+        NoopObserver {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Ignores the callback, it is never run.
+    fn register_callback<F>(&self, _callback: F)
+    where
+        F: Fn(api::BatchObserverResult<NoopLabelSet>) + Send + Sync + 'static,
+    {
+        // Ignored
+    }
+}
@@ -0,0 +1,123 @@
+//! # Metrics Observer Interface
+//!
+//! `Observer`s are asynchronous instruments. Instead of recording values as
+//! they change, an observer is registered with a callback that the SDK runs
+//! each time metrics are collected. The callback reports the current value
+//! for any number of label sets with `observe(value, label_set)`. Choose
+//! this kind of metric when the value is readily available on demand, e.g.
+//! the depth of a queue, the size of a pool or a ratio computed from other
+//! statistics.
+//!
+//! Like `Gauge`s, observers are defined as `monotonic = false` by default,
+//! meaning that successive observations may rise or fall. Observers created
+//! with the `alternate` option are monotonic, successive observations are
+//! expected to rise and report a cumulative sum, like the total number of
+//! bytes read from a socket.
+//!
+//! A `BatchObserver` runs a single callback to observe several instruments
+//! at once, which is useful when the values are computed together. Its
+//! instruments are created first, so that the callback can capture them.
+use crate::api::metrics;
+use std::marker;
+
+/// An asynchronous instrument, observed by a callback at collection time.
+pub trait Observer<T, LS>: metrics::Instrument<LS>
+where
+    T: Into<metrics::value::MeasurementValue>,
+    LS: metrics::LabelSet,
+{
+    /// Creates a `Measurement` to be reported by a `BatchObserver` callback.
+    fn observation(&self, value: T) -> metrics::Measurement<LS>;
+}
+
+/// Creates instruments and observes them with callbacks that update several
+/// instruments at once.
+pub trait BatchObserver<LS: metrics::LabelSet> {
+    /// The `i64` observer type of this batch observer.
+    type I64Observer: Observer<i64, LS>;
+    /// The `f64` observer type of this batch observer.
+    type F64Observer: Observer<f64, LS>;
+
+    /// Creates a new `i64` observer with a given name and customized with
+    /// passed options, observed by the callbacks of this batch observer.
+    fn new_i64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: metrics::MetricOptions,
+    ) -> Self::I64Observer;
+
+    /// Creates a new `f64` observer with a given name and customized with
+    /// passed options, observed by the callbacks of this batch observer.
+    fn new_f64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: metrics::MetricOptions,
+    ) -> Self::F64Observer;
+
+    /// Registers a callback run each time metrics are collected, reporting
+    /// observations of the instruments of this batch observer.
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(BatchObserverResult<LS>) + Send + Sync + 'static;
+}
+
+/// Passed to the callback of an `Observer` to report its current values.
+#[allow(missing_debug_implementations)]
+pub struct ObserverResult<'a, T, LS> {
+    instrument: &'a dyn metrics::Instrument<LS>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<'a, T, LS> ObserverResult<'a, T, LS>
+where
+    T: Into<metrics::value::MeasurementValue>,
+    LS: metrics::LabelSet,
+{
+    /// Create a new result reporting observations to `instrument`.
+    pub fn new(instrument: &'a dyn metrics::Instrument<LS>) -> Self {
+        ObserverResult {
+            instrument,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Reports the current value for a label set.
+    pub fn observe(&self, value: T, label_set: &LS) {
+        self.instrument.record_one(value.into(), label_set)
+    }
+}
+
+/// Passed to the callback of a `BatchObserver` to report the current values
+/// of its instruments.
+#[allow(missing_debug_implementations)]
+pub struct BatchObserverResult<LS> {
+    _marker: marker::PhantomData<fn(&LS)>,
+}
+
+impl<LS: metrics::LabelSet> BatchObserverResult<LS> {
+    /// Create a new result.
+    pub fn new() -> Self {
+        BatchObserverResult {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Reports the current values of several instruments for a label set.
+    /// Observations are created with `Observer::observation`.
+    pub fn observe<M: IntoIterator<Item = metrics::Measurement<LS>>>(
+        &self,
+        label_set: &LS,
+        observations: M,
+    ) {
+        for observation in observations.into_iter() {
+            let instrument = observation.instrument();
+            instrument.record_one(observation.into_value(), label_set);
+        }
+    }
+}
+
+impl<LS: metrics::LabelSet> Default for BatchObserverResult<LS> {
+    fn default() -> Self {
+        BatchObserverResult::new()
+    }
+}
@@ -0,0 +1,33 @@
+//! # OpenTelemetry Meter Provider Interface
+//!
+//! ### Obtaining a Meter
+//!
+//! New `Meter` instances can be created via a `MeterProvider` and its `meter`
+//! or `versioned_meter` methods:
+//!
+//! - `name` (required): This name must identify the instrumentation library
+//!   (e.g. `io.opentelemetry.contrib.mongodb`) and *not* the instrumented
+//!   library. An SDK may use it as the namespace of the instruments created
+//!   by the meter, so an empty name is valid and creates instruments without
+//!   a namespace.
+//! - `version` (optional): The version of the instrumentation library.
+//!
+//! Implementations might require the user to specify configuration properties at
+//! `MeterProvider` creation time, or rely on external configuration.
+use crate::api;
+use std::fmt;
+
+/// An interface to create `Meter` instances.
+pub trait MeterProvider: fmt::Debug + 'static {
+    /// The `Meter` type that this `MeterProvider` will return.
+    type Meter: api::Meter;
+
+    /// Creates a named meter instance of `Self::Meter`.
+    fn meter(&self, name: &'static str) -> Self::Meter {
+        self.versioned_meter(name, None)
+    }
+
+    /// Creates a named meter instance of `Self::Meter` for a version of the
+    /// instrumentation library.
+    fn versioned_meter(&self, name: &'static str, version: Option<&'static str>) -> Self::Meter;
+}
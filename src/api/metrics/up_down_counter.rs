@@ -0,0 +1,50 @@
+//! # Metrics UpDownCounter Interface
+//!
+//! UpDownCounters support `add(value, label_set)` with positive and negative
+//! increments. Choose this kind of metric when the sum is of primary
+//! interest but may decrease, such as the number of items in a queue or the
+//! number of bytes allocated and deallocated.
+//!
+//! `UpDownCounter`s are always `monotonic = false`, their sum cannot be
+//! interpreted as a rate. Use a `Counter` for values that only increase.
+use crate::api::metrics;
+
+/// An interface for recording increments and decrements where the sum is of
+/// primary interest.
+pub trait UpDownCounter<T, LS>: metrics::Instrument<LS>
+where
+    T: Into<metrics::value::MeasurementValue>,
+    LS: metrics::LabelSet,
+{
+    /// The handle type for the implementing `UpDownCounter`.
+    type Handle: UpDownCounterHandle<T>;
+
+    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
+    fn measurement(&self, value: T) -> metrics::Measurement<LS>;
+
+    /// Creates a handle for this up-down counter. The labels should contain
+    /// the keys and values for each key specified in the `LabelSet`.
+    ///
+    /// If the labels do not contain a value for the key specified in the
+    /// `LabelSet`, then the missing value will be treated as unspecified.
+    fn acquire_handle(&self, labels: &LS) -> Self::Handle;
+
+    /// Adds the value, which may be negative, to the `UpDownCounter`'s sum.
+    fn add(&self, value: T, label_set: &LS) {
+        self.record_one(value.into(), label_set)
+    }
+}
+
+/// `UpDownCounterHandle` is a handle for `UpDownCounter` instances.
+///
+/// It allows for repeated `add` calls for a pre-determined `LabelSet`.
+pub trait UpDownCounterHandle<T>: metrics::InstrumentHandle
+where
+    T: Into<metrics::value::MeasurementValue>,
+{
+    /// Add works by calling the underlying `record_one` method
+    /// available because this trait also implements `InstrumentHandle`.
+    fn add(&self, value: T) {
+        self.record_one(value.into())
+    }
+}
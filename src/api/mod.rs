@@ -21,9 +21,14 @@ pub mod trace;
 pub use self::core::{Key, KeyValue, Unit, Value};
 pub use metrics::{
     counter::{Counter, CounterHandle},
+    exponential_boundaries,
     gauge::{Gauge, GaugeHandle},
+    linear_boundaries,
     measure::{Measure, MeasureHandle},
-    noop::NoopMeter,
+    noop::{NoopMeter, NoopMeterProvider},
+    observer::{BatchObserver, BatchObserverResult, Observer, ObserverResult},
+    provider::MeterProvider,
+    up_down_counter::{UpDownCounter, UpDownCounterHandle},
     value::MeasurementValue,
     Instrument, InstrumentHandle, LabelSet, Measurement, Meter, MetricOptions,
 };
@@ -40,7 +45,7 @@ pub use trace::{
     sampler::{Sampler, SamplingDecision, SamplingResult},
     span::{Span, SpanKind, StatusCode},
     span_context::{SpanContext, SpanId, TraceId, TRACE_FLAGS_UNUSED, TRACE_FLAG_SAMPLED},
-    span_processor::SpanProcessor,
+    span_processor::{FlushResult, SpanProcessor},
     trace_context_propagator::TraceContextPropagator,
     tracer::{SpanBuilder, Tracer, TracerGenerics},
 };
@@ -16,6 +16,8 @@ pub struct Event {
     pub timestamp: SystemTime,
     /// Event attributes
     pub attributes: Vec<api::KeyValue>,
+    /// The number of attributes dropped because of the attribute limit
+    pub dropped_attributes_count: u32,
 }
 
 impl Event {
@@ -25,6 +27,7 @@ impl Event {
             name,
             timestamp,
             attributes,
+            dropped_attributes_count: 0,
         }
     }
 
@@ -34,6 +37,7 @@ impl Event {
             name,
             timestamp: SystemTime::now(),
             attributes: Vec::new(),
+            dropped_attributes_count: 0,
         }
     }
 }
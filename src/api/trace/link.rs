@@ -9,7 +9,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq)]
 pub struct Link {
     span_context: api::SpanContext,
-    attributes: Vec<api::KeyValue>,
+    pub(crate) attributes: Vec<api::KeyValue>,
+    pub(crate) dropped_attributes_count: u32,
 }
 
 impl Link {
@@ -18,6 +19,7 @@ impl Link {
         Link {
             span_context,
             attributes,
+            dropped_attributes_count: 0,
         }
     }
 
@@ -30,4 +32,9 @@ impl Link {
     pub fn attributes(&self) -> &Vec<api::KeyValue> {
         &self.attributes
     }
+
+    /// The number of attributes dropped because of the attribute limit
+    pub fn dropped_attributes_count(&self) -> u32 {
+        self.dropped_attributes_count
+    }
 }
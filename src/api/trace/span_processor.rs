@@ -37,14 +37,51 @@
 use crate::exporter;
 use std::sync::Arc;
 
+/// Describes the result of flushing or shutting down a `SpanProcessor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushResult {
+    /// All pending spans were passed to the exporter and successfully exported.
+    Success,
+    /// All pending spans were passed to the exporter, but some of them failed
+    /// to export.
+    ExportFailed,
+    /// The timeout elapsed before all pending spans were exported.
+    Timeout,
+    /// The processor had already been shut down, nothing was exported.
+    AlreadyShutdown,
+}
+
 /// `SpanProcessor`s allow finished spans to be processed.
 pub trait SpanProcessor: Send + Sync + std::fmt::Debug {
-    /// `on_start` method is invoked when a `Span` is started.
-    fn on_start(&self, span: Arc<exporter::trace::SpanData>);
+    /// `on_start` method is invoked when a `Span` is started. Processors can
+    /// modify the span data, e.g. to add attributes, and the changes are part
+    /// of the span passed to `on_end`. Processors are called in the order
+    /// they were registered.
+    fn on_start(&self, span: &mut exporter::trace::SpanData);
     /// `on_end` method is invoked when a `Span` is ended.
     fn on_end(&self, span: Arc<exporter::trace::SpanData>);
+    /// Exports all ended spans that have not been exported yet. Returns once
+    /// the exporter has acknowledged them, or the processor's timeout elapsed.
+    ///
+    /// This is useful for short-lived processes that must not exit before
+    /// their spans have been exported. Processors that do not buffer spans
+    /// have nothing to flush and can rely on the default implementation.
+    fn force_flush(&self) -> FlushResult {
+        FlushResult::Success
+    }
     /// Shutdown is invoked when SDK shuts down. Use this call to cleanup any
     /// processor data. No calls to `on_start` and `on_end` method is invoked
     /// after `shutdown` call is made.
-    fn shutdown(&self);
+    ///
+    /// Pending spans are exported before the processor shuts down, the result
+    /// reports whether this finished in time.
+    fn shutdown(&self) -> FlushResult;
+    /// Starts shutting down the processor without waiting for its pending
+    /// spans to be exported. This is invoked when the `Provider` is dropped.
+    ///
+    /// Processors exporting in the background should only signal their
+    /// worker to shut down. The default implementation calls `shutdown`.
+    fn begin_shutdown(&self) {
+        let _ = self.shutdown();
+    }
 }
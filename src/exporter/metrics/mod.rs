@@ -1,2 +1,82 @@
 //! Metric exporters
+//!
+//! Metrics are aggregated by an `Accumulator` in the SDK, which produces a
+//! [`Record`] per instrument and label set when it is collected. Push based
+//! exporters like [`statsd`] implement [`MetricsExporter`] to receive these
+//! records, pull based exporters like [`prometheus`] collect them when they
+//! are scraped.
+//!
+//! [`Record`]: struct.Record.html
+//! [`MetricsExporter`]: trait.MetricsExporter.html
+//! [`prometheus`]: prometheus/index.html
+//! [`statsd`]: statsd/index.html
+use crate::sdk;
+use crate::sdk::metrics::{Aggregation, Descriptor, LabelSet};
+use std::fmt;
+use std::sync::Arc;
+
 pub mod prometheus;
+pub mod statsd;
+
+pub use super::ExportResult;
+
+/// `MetricsExporter` defines the interface that protocol-specific exporters
+/// must implement to receive collected metrics.
+pub trait MetricsExporter: Send + Sync + fmt::Debug {
+    /// Exports the records of one collection. Records are cumulative, they
+    /// contain everything aggregated since the instruments were created.
+    ///
+    /// This function must not block indefinitely, there must be a reasonable
+    /// upper limit after which the call must time out with an error result.
+    fn export(&self, records: Vec<Record>) -> ExportResult;
+
+    /// Shuts down the exporter. Called when SDK is shut down. This is an
+    /// opportunity for exporter to do any cleanup required.
+    fn shutdown(&self);
+}
+
+/// The aggregate of one instrument and label set at collection time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    descriptor: Arc<Descriptor>,
+    labels: LabelSet,
+    aggregation: Aggregation,
+    resource: Arc<sdk::Resource>,
+}
+
+impl Record {
+    /// Create a new record.
+    pub fn new(
+        descriptor: Arc<Descriptor>,
+        labels: LabelSet,
+        aggregation: Aggregation,
+        resource: Arc<sdk::Resource>,
+    ) -> Self {
+        Record {
+            descriptor,
+            labels,
+            aggregation,
+            resource,
+        }
+    }
+
+    /// The instrument that was aggregated.
+    pub fn descriptor(&self) -> &Descriptor {
+        &self.descriptor
+    }
+
+    /// The labels of the aggregated measurements.
+    pub fn labels(&self) -> &LabelSet {
+        &self.labels
+    }
+
+    /// The aggregate of the measurements.
+    pub fn aggregation(&self) -> &Aggregation {
+        &self.aggregation
+    }
+
+    /// The resource describing the entity producing the metrics.
+    pub fn resource(&self) -> &sdk::Resource {
+        &self.resource
+    }
+}
@@ -0,0 +1,281 @@
+//! # Exposition Formats
+//!
+//! Encodes gathered metric families in the [OpenMetrics] format and in the
+//! Prometheus text format. Unlike the `TextEncoder` of the Prometheus
+//! library, both support summaries.
+//!
+//! [OpenMetrics]: https://openmetrics.io
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use prometheus::{Encoder, Error};
+use std::io::Write;
+
+/// The content type of the OpenMetrics text format.
+pub const OPENMETRICS_FORMAT: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// The content type of the Prometheus text format.
+pub(crate) const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Encodes metric families in the OpenMetrics text format.
+#[derive(Debug, Default)]
+pub struct OpenMetricsEncoder;
+
+impl OpenMetricsEncoder {
+    /// Create a new OpenMetrics encoder.
+    pub fn new() -> Self {
+        OpenMetricsEncoder
+    }
+}
+
+impl Encoder for OpenMetricsEncoder {
+    fn encode<W: Write>(&self, families: &[MetricFamily], writer: &mut W) -> Result<(), Error> {
+        encode(families, Format::OpenMetrics, writer)
+    }
+
+    fn format_type(&self) -> &str {
+        OPENMETRICS_FORMAT
+    }
+}
+
+/// The exposition formats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Format {
+    Text,
+    OpenMetrics,
+}
+
+impl Format {
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Format::Text => TEXT_FORMAT,
+            Format::OpenMetrics => OPENMETRICS_FORMAT,
+        }
+    }
+}
+
+/// Write `families` to `writer` in the given format.
+pub(crate) fn encode<W: Write>(
+    families: &[MetricFamily],
+    format: Format,
+    writer: &mut W,
+) -> Result<(), Error> {
+    for family in families {
+        let metric_type = family.get_field_type();
+        let mut name = family.get_name();
+        // OpenMetrics counter samples have a `_total` suffix that is not part
+        // of the family name.
+        if format == Format::OpenMetrics && metric_type == MetricType::COUNTER {
+            name = name.strip_suffix("_total").unwrap_or(name);
+        }
+
+        if !family.get_help().is_empty() {
+            let help = escape(family.get_help(), format == Format::OpenMetrics);
+            writeln!(writer, "# HELP {} {}", name, help)?;
+        }
+        let type_name = match (metric_type, format) {
+            (MetricType::COUNTER, _) => "counter",
+            (MetricType::GAUGE, _) => "gauge",
+            (MetricType::SUMMARY, _) => "summary",
+            (MetricType::HISTOGRAM, _) => "histogram",
+            (MetricType::UNTYPED, Format::Text) => "untyped",
+            (MetricType::UNTYPED, Format::OpenMetrics) => "unknown",
+        };
+        writeln!(writer, "# TYPE {} {}", name, type_name)?;
+
+        for metric in family.get_metric() {
+            match metric_type {
+                MetricType::COUNTER => {
+                    let value = metric.get_counter().get_value();
+                    let suffix = match format {
+                        Format::Text => "",
+                        Format::OpenMetrics => "_total",
+                    };
+                    write_sample(writer, format, name, suffix, metric, None, value)?;
+                }
+                MetricType::GAUGE => {
+                    let value = metric.get_gauge().get_value();
+                    write_sample(writer, format, name, "", metric, None, value)?;
+                }
+                MetricType::UNTYPED => {
+                    let value = metric.get_untyped().get_value();
+                    write_sample(writer, format, name, "", metric, None, value)?;
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        let label = ("quantile", quantile.get_quantile());
+                        let value = quantile.get_value();
+                        write_sample(writer, format, name, "", metric, Some(label), value)?;
+                    }
+                    let (sum, count) = (summary.get_sample_sum(), summary.get_sample_count());
+                    write_sample(writer, format, name, "_sum", metric, None, sum)?;
+                    write_sample(writer, format, name, "_count", metric, None, count as f64)?;
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let count = histogram.get_sample_count();
+                    let mut has_inf = false;
+                    for bucket in histogram.get_bucket() {
+                        let upper_bound = bucket.get_upper_bound();
+                        has_inf |= upper_bound == f64::INFINITY;
+                        let cumulative = bucket.get_cumulative_count() as f64;
+                        let label = Some(("le", upper_bound));
+                        write_sample(writer, format, name, "_bucket", metric, label, cumulative)?;
+                    }
+                    if !has_inf {
+                        let label = Some(("le", f64::INFINITY));
+                        write_sample(writer, format, name, "_bucket", metric, label, count as f64)?;
+                    }
+                    let sum = histogram.get_sample_sum();
+                    write_sample(writer, format, name, "_sum", metric, None, sum)?;
+                    write_sample(writer, format, name, "_count", metric, None, count as f64)?;
+                }
+            }
+        }
+    }
+
+    if format == Format::OpenMetrics {
+        writeln!(writer, "# EOF")?;
+    }
+
+    Ok(())
+}
+
+fn write_sample<W: Write>(
+    writer: &mut W,
+    format: Format,
+    name: &str,
+    suffix: &str,
+    metric: &Metric,
+    extra_label: Option<(&str, f64)>,
+    value: f64,
+) -> Result<(), Error> {
+    write!(writer, "{}{}", name, suffix)?;
+
+    let mut separator = "{";
+    for label in metric.get_label() {
+        let value = escape(label.get_value(), true);
+        write!(writer, "{}{}=\"{}\"", separator, label.get_name(), value)?;
+        separator = ",";
+    }
+    if let Some((label, label_value)) = extra_label {
+        let label_value = format_value(label_value);
+        write!(writer, "{}{}=\"{}\"", separator, label, label_value)?;
+        separator = ",";
+    }
+    if separator == "," {
+        write!(writer, "}}")?;
+    }
+
+    write!(writer, " {}", format_value(value))?;
+    let timestamp = metric.get_timestamp_ms();
+    if timestamp != 0 {
+        match format {
+            Format::Text => write!(writer, " {}", timestamp)?,
+            Format::OpenMetrics => write!(writer, " {}", timestamp as f64 / 1000.0)?,
+        }
+    }
+
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape backslashes and line feeds, and double quotes if `quotes` is set.
+fn escape(value: &str, quotes: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' if quotes => escaped.push_str("\\\""),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Format, OpenMetricsEncoder};
+    use prometheus::proto::{
+        Counter, LabelPair, Metric, MetricFamily, MetricType, Quantile, Summary,
+    };
+    use prometheus::Encoder;
+
+    fn families() -> Vec<MetricFamily> {
+        let mut label = LabelPair::default();
+        label.set_name("path".to_string());
+        label.set_value("/a\"b".to_string());
+
+        let mut counter = Counter::default();
+        counter.set_value(3.0);
+        let mut metric = Metric::default();
+        metric.mut_label().push(label);
+        metric.set_counter(counter);
+        let mut requests = MetricFamily::default();
+        requests.set_name("requests".to_string());
+        requests.set_help("Requests\nserved".to_string());
+        requests.set_field_type(MetricType::COUNTER);
+        requests.mut_metric().push(metric);
+
+        let mut summary = Summary::default();
+        summary.set_sample_count(2);
+        summary.set_sample_sum(f64::INFINITY);
+        let mut quantile = Quantile::default();
+        quantile.set_quantile(1.0);
+        quantile.set_value(f64::INFINITY);
+        summary.mut_quantile().push(quantile);
+        let mut metric = Metric::default();
+        metric.set_summary(summary);
+        let mut latency = MetricFamily::default();
+        latency.set_name("latency".to_string());
+        latency.set_field_type(MetricType::SUMMARY);
+        latency.mut_metric().push(metric);
+
+        vec![requests, latency]
+    }
+
+    #[test]
+    fn encodes_text_and_openmetrics() {
+        let mut text = Vec::new();
+        encode(&families(), Format::Text, &mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "# HELP requests Requests\\nserved\n\
+             # TYPE requests counter\n\
+             requests{path=\"/a\\\"b\"} 3\n\
+             # TYPE latency summary\n\
+             latency{quantile=\"1\"} +Inf\n\
+             latency_sum +Inf\n\
+             latency_count 2\n"
+        );
+
+        let mut openmetrics = Vec::new();
+        OpenMetricsEncoder::new()
+            .encode(&families(), &mut openmetrics)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(openmetrics).unwrap(),
+            "# HELP requests Requests\\nserved\n\
+             # TYPE requests counter\n\
+             requests_total{path=\"/a\\\"b\"} 3\n\
+             # TYPE latency summary\n\
+             latency{quantile=\"1\"} +Inf\n\
+             latency_sum +Inf\n\
+             latency_count 2\n\
+             # EOF\n"
+        );
+    }
+}
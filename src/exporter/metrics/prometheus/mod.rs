@@ -1,264 +1,520 @@
 //! # OpenTelemetry Prometheus Exporter
 //!
-//! This exporter currently delegates to the [Prometheus library]
-//! library which implements the [Prometheus API].
+//! The [`PrometheusExporter`] registers a [Prometheus library] `Collector`
+//! with a `Registry`. Each time the registry is gathered, it collects the
+//! records of an `Accumulator` and converts them to metric families of the
+//! [Prometheus API]:
 //!
+//! - sums of monotonic counters become counters, sums of up-down counters
+//!   and of non-monotonic counters become gauges,
+//! - last values become gauges, last values of monotonic observers become
+//!   counters,
+//! - min, max, sum and count aggregates become summaries with the minimum
+//!   and maximum as the 0 and 1 quantiles,
+//! - histograms become histograms, exponential histograms become histograms
+//!   with one bucket per exponential bucket.
+//!
+//! Metric names are the namespace of the instrument, its name and its unit,
+//! joined by `_`.
+//!
+//! Each exporter registers with its own `Registry` by default, pass
+//! `prometheus::default_registry().clone()` to `Builder::with_registry` to
+//! expose the metrics through the process-wide registry instead.
+//!
+//! The registry can be encoded with the `TextEncoder` or the
+//! `OpenMetricsEncoder`, served over HTTP by the [`server`] module with the
+//! `prometheus-server` feature, or pushed to a Pushgateway by the [`push`]
+//! module with the `prometheus-push` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::api::{Counter, Meter, MetricOptions};
+//! use opentelemetry::exporter::metrics::prometheus::{Encoder, PrometheusExporter, TextEncoder};
+//!
+//! let exporter = PrometheusExporter::builder().try_init().unwrap();
+//! let meter = exporter.meter("example");
+//!
+//! let counter = meter.new_i64_counter("requests", MetricOptions::default());
+//! counter.add(1, &meter.labels(Vec::new()));
+//!
+//! let mut buffer = Vec::new();
+//! TextEncoder::new()
+//!     .encode(&exporter.registry().gather(), &mut buffer)
+//!     .unwrap();
+//! assert!(String::from_utf8(buffer).unwrap().contains("example_requests 1"));
+//! ```
+//!
+//! [`PrometheusExporter`]: struct.PrometheusExporter.html
+//! [`push`]: push/index.html
+//! [`server`]: server/index.html
 //! [Prometheus library]: https://github.com/tikv/rust-prometheus
 //! [Prometheus API]: https://prometheus.io
-use crate::api;
-use crate::sdk;
-use api::Key;
-pub use prometheus::{
-    default_registry, gather, Counter, CounterVec, Encoder, Gauge, GaugeVec, Histogram,
-    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
-    TextEncoder,
-};
-use std::collections::HashMap;
-use std::sync::Arc;
-
-/// Convert from `sdk::LabelSet` to `prometheus`' label format.
-fn convert_label_set(label_set: &sdk::LabelSet) -> HashMap<&str, &str> {
-    label_set
-        .iter()
-        .map(|(key, value)| (key.as_ref(), value.as_ref()))
-        .collect()
-}
+mod encoder;
+#[cfg(feature = "prometheus-push")]
+pub mod push;
+#[cfg(feature = "prometheus-server")]
+pub mod server;
 
-/// Convert from list of `Key`s to prometheus' label format.
-pub(crate) fn convert_labels(labels: &[Key]) -> Vec<&str> {
-    labels.iter().map(|k| k.as_str()).collect()
-}
-
-/// Prometheus IntCounterHandle
+use crate::exporter::metrics::Record;
+use crate::sdk;
+use crate::sdk::metrics::{Accumulator, Aggregation, Descriptor, InstrumentKind};
+pub use encoder::{OpenMetricsEncoder, OPENMETRICS_FORMAT};
+use prometheus::core::{Collector, Desc};
+use prometheus::proto;
+pub use prometheus::{default_registry, gather, Encoder, Error, Registry, TextEncoder};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Exposes the metrics of an `Accumulator` through a Prometheus `Registry`.
 #[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct IntCounterHandle(prometheus::IntCounter);
-
-impl api::Counter<i64, sdk::LabelSet> for prometheus::IntCounterVec {
-    /// Prometheus' `CounterHandle`
-    type Handle = IntCounterHandle;
+pub struct PrometheusExporter {
+    accumulator: Accumulator,
+    registry: Registry,
+}
 
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: i64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+impl PrometheusExporter {
+    /// Create a new exporter builder.
+    pub fn builder() -> Builder {
+        Builder::default()
     }
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        IntCounterHandle(self.with(&convert_label_set(labels)))
+    /// The accumulator collected by this exporter.
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
     }
-}
 
-impl api::Instrument<sdk::LabelSet> for prometheus::IntCounterVec {
-    /// Record a single counter measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.with(&convert_label_set(label_set))
-            .inc_by(value.into_i64())
+    /// The registry this exporter is registered with.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
     }
-}
 
-impl api::InstrumentHandle for IntCounterHandle {
-    /// Record a single counter measurement value for preset values
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.inc_by(value.into_i64())
+    /// Create a new `Meter` with the given namespace, recording into the
+    /// accumulator of this exporter.
+    pub fn meter<S: Into<String>>(&self, namespace: S) -> sdk::Meter {
+        sdk::Meter::with_accumulator(namespace, self.accumulator.clone())
     }
-}
-
-impl api::CounterHandle<i64> for IntCounterHandle {}
-
-/// Prometheus CounterHandle
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct CounterHandle(prometheus::Counter);
 
-impl api::Counter<f64, sdk::LabelSet> for prometheus::CounterVec {
-    type Handle = CounterHandle;
-
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: f64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+    /// Create a builder for a pusher sending the registry of this exporter
+    /// to the Pushgateway at `url` under the job name `job`.
+    #[cfg(feature = "prometheus-push")]
+    pub fn pusher<U: Into<String>, J: Into<String>>(&self, url: U, job: J) -> push::Builder {
+        push::Builder::new(self.registry.clone(), url, job)
     }
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        CounterHandle(self.with(&convert_label_set(labels)))
+    /// Create a builder for an HTTP endpoint serving the registry of this
+    /// exporter.
+    #[cfg(feature = "prometheus-server")]
+    pub fn server(&self) -> server::Builder {
+        server::Builder::new(self.registry.clone())
     }
 }
 
-impl api::Instrument<sdk::LabelSet> for prometheus::CounterVec {
-    /// record a single counter measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.with(&convert_label_set(label_set))
-            .inc_by(value.into_f64())
+impl fmt::Debug for PrometheusExporter {
+    /// Formats the exporter omitting its registry.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrometheusExporter")
+            .field("accumulator", &self.accumulator)
+            .finish()
     }
 }
 
-impl api::InstrumentHandle for CounterHandle {
-    /// record a single counter measurement value for precomputed labels
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.inc_by(value.into_f64())
-    }
+/// Builder for `PrometheusExporter` configuration.
+#[derive(Default)]
+pub struct Builder {
+    accumulator: Option<Accumulator>,
+    registry: Option<Registry>,
 }
 
-impl api::CounterHandle<f64> for CounterHandle {}
-
-// GAUGE COMPAT
-
-/// Prometheus IntGaugeHandle
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct IntGaugeHandle(prometheus::IntGauge);
-
-impl api::Gauge<i64, sdk::LabelSet> for prometheus::IntGaugeVec {
-    type Handle = IntGaugeHandle;
-
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: i64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+impl fmt::Debug for Builder {
+    /// Formats the builder omitting its registry.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("accumulator", &self.accumulator)
+            .finish()
     }
+}
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        IntGaugeHandle(self.with(&convert_label_set(labels)))
+impl Builder {
+    /// The accumulator to collect, a new default `Accumulator` is used if
+    /// none is given.
+    pub fn with_accumulator(self, accumulator: Accumulator) -> Self {
+        Builder {
+            accumulator: Some(accumulator),
+            ..self
+        }
     }
-}
 
-impl api::Instrument<sdk::LabelSet> for prometheus::IntGaugeVec {
-    /// record a single gauge measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.with(&convert_label_set(label_set))
-            .set(value.into_i64())
+    /// The registry to register with, a new `Registry` is used if none is
+    /// given.
+    pub fn with_registry(self, registry: Registry) -> Self {
+        Builder {
+            registry: Some(registry),
+            ..self
+        }
     }
-}
 
-impl api::InstrumentHandle for IntGaugeHandle {
-    /// record a single gauge measurement value for precomputed labels
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.set(value.into_i64())
+    /// Create the exporter and register it with the registry.
+    ///
+    /// The exporter does not describe its metrics up front, so registering
+    /// a second exporter with the same registry fails with
+    /// `Error::AlreadyReg`.
+    pub fn try_init(self) -> Result<PrometheusExporter, Error> {
+        let accumulator = self.accumulator.unwrap_or_default();
+        let registry = self.registry.unwrap_or_default();
+        registry.register(Box::new(AccumulatorCollector(accumulator.clone())))?;
+
+        Ok(PrometheusExporter {
+            accumulator,
+            registry,
+        })
     }
 }
 
-impl api::GaugeHandle<i64> for IntGaugeHandle {}
-
-/// Prometheus GaugeHandle
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct GaugeHandle(prometheus::Gauge);
-
-impl api::Gauge<f64, sdk::LabelSet> for prometheus::GaugeVec {
-    type Handle = GaugeHandle;
+/// Collects an accumulator and converts its records when the registry is
+/// gathered.
+struct AccumulatorCollector(Accumulator);
 
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: f64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+impl Collector for AccumulatorCollector {
+    /// Metrics are only known at collection time.
+    fn desc(&self) -> Vec<&Desc> {
+        Vec::new()
     }
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        GaugeHandle(self.with(&convert_label_set(labels)))
+    /// Collect the accumulator and convert its records.
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        metric_families(self.0.collect())
     }
 }
 
-impl api::Instrument<sdk::LabelSet> for prometheus::GaugeVec {
-    /// record a single gauge measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.with(&convert_label_set(label_set))
-            .set(value.into_f64())
+/// Group records into metric families by metric name.
+fn metric_families(records: Vec<Record>) -> Vec<proto::MetricFamily> {
+    let mut families = BTreeMap::<String, proto::MetricFamily>::new();
+    for record in records {
+        let descriptor = record.descriptor();
+        let (field_type, metric) = convert(&record);
+        let name = metric_name(descriptor);
+        let family = families
+            .entry(name.clone())
+            .or_insert_with(|| metric_family(name, descriptor, field_type));
+        family.mut_metric().push(metric);
     }
-}
 
-impl api::InstrumentHandle for GaugeHandle {
-    /// record a single gauge measurement value for precomputed labels
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.set(value.into_f64())
-    }
+    families.into_iter().map(|(_, family)| family).collect()
 }
 
-impl api::GaugeHandle<f64> for GaugeHandle {}
-
-// MEASURE COMPAT
-
-/// Prometheus IntMeasureHandle
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct IntMeasureHandle(prometheus::Histogram);
-
-/// Prometheus Histograms do not have i64 variant, `IntMeasure` will convert i64 to float when it
-/// records values.
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct IntMeasure(prometheus::HistogramVec);
-
-impl IntMeasure {
-    pub(crate) fn new(histogram: prometheus::HistogramVec) -> Self {
-        IntMeasure(histogram)
-    }
+/// An empty metric family for an instrument.
+fn metric_family(
+    name: String,
+    descriptor: &Descriptor,
+    field_type: proto::MetricType,
+) -> proto::MetricFamily {
+    let mut family = proto::MetricFamily::default();
+    // Prometheus cannot have empty help strings
+    family.set_help(if descriptor.description().is_empty() {
+        format!("{} metric", name)
+    } else {
+        descriptor.description().to_string()
+    });
+    family.set_name(name);
+    family.set_field_type(field_type);
+    family
 }
 
-impl api::Measure<i64, sdk::LabelSet> for IntMeasure {
-    type Handle = IntMeasureHandle;
-
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: i64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+/// The namespace, name and unit of the instrument, joined by `_` and with
+/// characters Prometheus does not allow replaced by `_`.
+fn metric_name(descriptor: &Descriptor) -> String {
+    let mut name = String::new();
+    for part in &[
+        descriptor.namespace(),
+        descriptor.name(),
+        descriptor.unit().as_str(),
+    ] {
+        if part.is_empty() {
+            continue;
+        }
+        if !name.is_empty() {
+            name.push('_');
+        }
+        name.push_str(part);
     }
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        IntMeasureHandle(self.0.with(&convert_label_set(labels)))
-    }
+    sanitize(&name)
 }
 
-impl api::Instrument<sdk::LabelSet> for IntMeasure {
-    /// record a single measure measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.0
-            .with(&convert_label_set(label_set))
-            .observe(value.into_i64() as f64)
-    }
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(index, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if index > 0 => c,
+            _ => '_',
+        })
+        .collect()
 }
 
-impl api::InstrumentHandle for IntMeasureHandle {
-    /// record a single measure measurement value for precomputed labels
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.observe(value.into_i64() as f64)
+/// Convert a record to a Prometheus metric and its type.
+fn convert(record: &Record) -> (proto::MetricType, proto::Metric) {
+    let mut metric = proto::Metric::default();
+    for (key, value) in record.labels().iter() {
+        let mut label = proto::LabelPair::default();
+        label.set_name(sanitize(key));
+        label.set_value(value.to_string());
+        metric.mut_label().push(label);
     }
-}
-
-impl api::MeasureHandle<i64> for IntMeasureHandle {}
-
-/// Prometheus MeasureHandle
-#[derive(Clone)]
-#[allow(missing_debug_implementations)]
-pub struct MeasureHandle(prometheus::Histogram);
 
-impl api::Measure<f64, sdk::LabelSet> for prometheus::HistogramVec {
-    type Handle = MeasureHandle;
+    let descriptor = record.descriptor();
+    let field_type = match record.aggregation() {
+        Aggregation::Sum(sum) => {
+            if descriptor.instrument_kind() == InstrumentKind::Counter && descriptor.monotonic() {
+                let mut counter = proto::Counter::default();
+                counter.set_value(sum.to_f64());
+                metric.set_counter(counter);
+                proto::MetricType::COUNTER
+            } else {
+                let mut gauge = proto::Gauge::default();
+                gauge.set_value(sum.to_f64());
+                metric.set_gauge(gauge);
+                proto::MetricType::GAUGE
+            }
+        }
+        Aggregation::LastValue { value, .. }
+            if descriptor.instrument_kind() == InstrumentKind::Observer
+                && descriptor.monotonic() =>
+        {
+            let mut counter = proto::Counter::default();
+            counter.set_value(value.to_f64());
+            metric.set_counter(counter);
+            proto::MetricType::COUNTER
+        }
+        Aggregation::LastValue { value, .. } => {
+            let mut gauge = proto::Gauge::default();
+            gauge.set_value(value.to_f64());
+            metric.set_gauge(gauge);
+            proto::MetricType::GAUGE
+        }
+        Aggregation::MinMaxSumCount {
+            min,
+            max,
+            sum,
+            count,
+        } => {
+            let mut summary = proto::Summary::default();
+            summary.set_sample_count(*count);
+            summary.set_sample_sum(sum.to_f64());
+            for (quantile, value) in &[(0.0, min), (1.0, max)] {
+                let mut q = proto::Quantile::default();
+                q.set_quantile(*quantile);
+                q.set_value(value.to_f64());
+                summary.mut_quantile().push(q);
+            }
+            metric.set_summary(summary);
+            proto::MetricType::SUMMARY
+        }
+        Aggregation::Histogram {
+            boundaries,
+            counts,
+            sum,
+            count,
+        } => {
+            let mut histogram = proto::Histogram::default();
+            histogram.set_sample_count(*count);
+            histogram.set_sample_sum(sum.to_f64());
+            // Prometheus buckets are cumulative, the `+Inf` bucket is implied
+            // by the sample count.
+            let mut cumulative = 0;
+            for (boundary, bucket_count) in boundaries.iter().zip(counts.iter()) {
+                cumulative += bucket_count;
+                let mut bucket = proto::Bucket::default();
+                bucket.set_upper_bound(*boundary);
+                bucket.set_cumulative_count(cumulative);
+                histogram.mut_bucket().push(bucket);
+            }
+            metric.set_histogram(histogram);
+            proto::MetricType::HISTOGRAM
+        }
+        Aggregation::ExponentialHistogram(exponential) => {
+            let mut histogram = proto::Histogram::default();
+            histogram.set_sample_count(exponential.count());
+            histogram.set_sample_sum(exponential.sum().to_f64());
+            // Buckets of negative values are ordered by decreasing magnitude
+            // and bounded by their smallest magnitude.
+            let negative = exponential
+                .negative()
+                .iter()
+                .rev()
+                .map(|(index, count)| (-exponential.lower_boundary(index), count));
+            let zero = Some((0.0, exponential.zero_count()))
+                .filter(|(_, count)| *count > 0 || !exponential.negative().counts().is_empty());
+            let positive = exponential
+                .positive()
+                .iter()
+                .map(|(index, count)| (exponential.lower_boundary(index + 1), count));
+            let mut cumulative = 0;
+            for (upper_bound, bucket_count) in negative.chain(zero).chain(positive) {
+                cumulative += bucket_count;
+                let mut bucket = proto::Bucket::default();
+                bucket.set_upper_bound(upper_bound);
+                bucket.set_cumulative_count(cumulative);
+                histogram.mut_bucket().push(bucket);
+            }
+            metric.set_histogram(histogram);
+            proto::MetricType::HISTOGRAM
+        }
+    };
+
+    (field_type, metric)
+}
 
-    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
-    fn measurement(&self, value: f64) -> api::Measurement<sdk::LabelSet> {
-        api::Measurement::new(Arc::new(self.clone()), api::MeasurementValue::from(value))
+#[cfg(test)]
+mod tests {
+    use super::{Error, PrometheusExporter};
+    use crate::api::{Counter, Gauge, Key, Measure, Meter, MetricOptions, Unit, UpDownCounter};
+    use crate::sdk;
+    use prometheus::{Encoder, Registry, TextEncoder};
+
+    #[test]
+    fn reports_registry_conflicts() {
+        let registry = Registry::new();
+        let exporter = PrometheusExporter::builder()
+            .with_registry(registry.clone())
+            .try_init()
+            .unwrap();
+        match PrometheusExporter::builder()
+            .with_registry(registry)
+            .try_init()
+        {
+            Err(Error::AlreadyReg) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Exporters with their own registries do not conflict
+        let other = PrometheusExporter::builder().try_init().unwrap();
+        exporter
+            .meter("test")
+            .new_i64_counter("requests", MetricOptions::default())
+            .add(1, &exporter.meter("test").labels(Vec::new()));
+        assert_eq!(exporter.registry().gather().len(), 1);
+        assert!(other.registry().gather().is_empty());
     }
 
-    /// Creates a handle for this instrument.
-    fn acquire_handle(&self, labels: &sdk::LabelSet) -> Self::Handle {
-        MeasureHandle(self.with(&convert_label_set(labels)))
+    #[test]
+    fn exports_text_format() {
+        let registry = Registry::new();
+        let exporter = PrometheusExporter::builder()
+            .with_accumulator(
+                sdk::metrics::Accumulator::builder()
+                    .with_selector(sdk::metrics::Selector::Histogram(vec![0.5, 1.0]))
+                    .build(),
+            )
+            .with_registry(registry.clone())
+            .try_init()
+            .unwrap();
+        let meter = exporter.meter("test");
+
+        let labels = meter.labels(vec![Key::new("method").string("GET")]);
+        meter
+            .new_i64_counter(
+                "requests",
+                MetricOptions::default().with_description("Requests served"),
+            )
+            .add(3, &labels);
+        meter
+            .new_f64_gauge("temperature", MetricOptions::default())
+            .set(21.5, &labels);
+        meter
+            .new_i64_up_down_counter("queued", MetricOptions::default())
+            .add(-2, &labels);
+        meter
+            .new_f64_counter("balance", MetricOptions::default().with_monotonic(false))
+            .add(-1.5, &labels);
+        meter
+            .new_f64_measure(
+                "latency",
+                MetricOptions::default().with_unit(Unit::new("seconds")),
+            )
+            .record(0.7, &labels);
+        let observed = labels.clone();
+        meter.register_i64_observer(
+            "bytes_read",
+            MetricOptions {
+                alternate: true,
+                ..MetricOptions::default()
+            },
+            move |result| result.observe(1024, &observed),
+        );
+        let observed = labels.clone();
+        meter.register_i64_observer(
+            "bytes_written",
+            MetricOptions::default().with_monotonic(true),
+            move |result| result.observe(512, &observed),
+        );
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        for line in &[
+            "# HELP test_requests Requests served",
+            "# TYPE test_requests counter",
+            "test_requests{method=\"GET\"} 3",
+            "# TYPE test_temperature gauge",
+            "test_temperature{method=\"GET\"} 21.5",
+            "# TYPE test_queued gauge",
+            "test_queued{method=\"GET\"} -2",
+            "# TYPE test_balance gauge",
+            "test_balance{method=\"GET\"} -1.5",
+            "# TYPE test_latency_seconds histogram",
+            "test_latency_seconds_bucket{method=\"GET\",le=\"0.5\"} 0",
+            "test_latency_seconds_bucket{method=\"GET\",le=\"1\"} 1",
+            "test_latency_seconds_bucket{method=\"GET\",le=\"+Inf\"} 1",
+            "test_latency_seconds_sum{method=\"GET\"} 0.7",
+            "test_latency_seconds_count{method=\"GET\"} 1",
+            "# TYPE test_bytes_read counter",
+            "test_bytes_read{method=\"GET\"} 1024",
+            "# TYPE test_bytes_written counter",
+            "test_bytes_written{method=\"GET\"} 512",
+        ] {
+            assert!(text.contains(line), "missing `{}` in:\n{}", line, text);
+        }
     }
-}
 
-impl api::Instrument<sdk::LabelSet> for prometheus::HistogramVec {
-    /// record a single measure measurement value
-    fn record_one(&self, value: api::MeasurementValue, label_set: &sdk::LabelSet) {
-        self.with(&convert_label_set(label_set))
-            .observe(value.into_f64())
+    #[test]
+    fn exports_exponential_histograms() {
+        let exporter = PrometheusExporter::builder()
+            .with_accumulator(
+                sdk::metrics::Accumulator::builder()
+                    .with_selector(sdk::metrics::Selector::Exponential(2))
+                    .build(),
+            )
+            .try_init()
+            .unwrap();
+        let meter = exporter.meter("test");
+        let labels = meter.labels(Vec::new());
+        let measure = meter.new_f64_measure("size", MetricOptions::default().with_absolute(false));
+        for value in &[-2.0, 0.0, 1.0, 4.0] {
+            measure.record(*value, &labels);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&exporter.registry().gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        for line in &[
+            "# TYPE test_size histogram",
+            "test_size_bucket{le=\"-1\"} 1",
+            "test_size_bucket{le=\"0\"} 2",
+            "test_size_bucket{le=\"1\"} 3",
+            "test_size_bucket{le=\"4\"} 4",
+            "test_size_bucket{le=\"+Inf\"} 4",
+            "test_size_sum 3",
+            "test_size_count 4",
+        ] {
+            assert!(text.contains(line), "missing `{}` in:\n{}", line, text);
+        }
     }
 }
-
-impl api::InstrumentHandle for MeasureHandle {
-    /// record a single measure measurement value for precomputed labels
-    fn record_one(&self, value: api::MeasurementValue) {
-        self.0.observe(value.into_f64())
-    }
-}
-
-impl api::MeasureHandle<f64> for MeasureHandle {}
@@ -0,0 +1,704 @@
+//! # Prometheus Pushgateway Exporter
+//!
+//! The [`Pusher`] sends the metric families of a `Registry` to a
+//! [Pushgateway] from a dedicated thread, for batch jobs that exit before
+//! Prometheus could scrape them. It is enabled with the `prometheus-push`
+//! feature.
+//!
+//! The registry is pushed every `interval` and once more when the pusher is
+//! shut down. Metrics are pushed to the group identified by the job name and
+//! the grouping labels, replacing the whole group with `PUT` or only the
+//! pushed metric families with `POST`. With `delete_on_exit`, the group is
+//! deleted on shutdown instead of being pushed a last time. Dropping the
+//! pusher only stops its periodic pushes.
+//!
+//! Requests are sent with [hyper] over `http` or `https` from the worker
+//! thread, which runs its own runtime. `push`, `delete` and `shutdown` block
+//! the calling thread until the worker answered, they can also be called
+//! from within an async runtime. Periodic pushes that fail are reported to
+//! `global::handle_error`. Basic authentication credentials are only sent
+//! over `https`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry::api::{Counter, Meter, MetricOptions};
+//! use opentelemetry::exporter::metrics::prometheus::PrometheusExporter;
+//! use opentelemetry::exporter::metrics::prometheus::push::PushMethod;
+//!
+//! let exporter = PrometheusExporter::builder().try_init().unwrap();
+//! let pusher = exporter
+//!     .pusher("http://localhost:9091", "backup")
+//!     .with_grouping("instance", "db-1")
+//!     .with_method(PushMethod::Post)
+//!     .try_init()
+//!     .unwrap();
+//!
+//! let meter = exporter.meter("backup");
+//! let counter = meter.new_i64_counter("files", MetricOptions::default());
+//! counter.add(42, &meter.labels(Vec::new()));
+//!
+//! pusher.shutdown().unwrap();
+//! ```
+//!
+//! [`Pusher`]: struct.Pusher.html
+//! [Pushgateway]: https://github.com/prometheus/pushgateway
+//! [hyper]: https://hyper.rs
+use super::encoder::{encode, Format};
+use crate::global;
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Uri};
+use hyper_tls::HttpsConnector;
+use prometheus::Registry;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+use tokio::runtime;
+
+/// How pushed metrics are merged with the metrics of their group.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PushMethod {
+    /// Replace all metrics of the group.
+    Put,
+    /// Replace only the metrics with the same names as the pushed ones.
+    Post,
+}
+
+impl PushMethod {
+    fn as_method(self) -> Method {
+        match self {
+            PushMethod::Put => Method::PUT,
+            PushMethod::Post => Method::POST,
+        }
+    }
+}
+
+/// Errors returned when pushing to a Pushgateway.
+#[derive(Debug)]
+pub enum PushError {
+    /// The Pushgateway URL is not an `http` or `https` URL.
+    InvalidUrl(String),
+    /// Basic authentication was configured for a URL that is not `https`,
+    /// the credentials would be sent in cleartext.
+    InsecureCredentials(String),
+    /// The TLS connector could not be created.
+    Tls(native_tls::Error),
+    /// The registry could not be encoded.
+    Encode(prometheus::Error),
+    /// The request could not be sent or its response could not be read.
+    Http(hyper::Error),
+    /// The worker thread or its runtime could not be created, the worker
+    /// stopped, or the request timed out.
+    Io(io::Error),
+    /// The Pushgateway answered with a status code other than 2xx.
+    Status(u16),
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::InvalidUrl(url) => write!(f, "`{}` is not an http or https URL", url),
+            PushError::InsecureCredentials(url) => write!(
+                f,
+                "basic authentication requires an https URL, got `{}`",
+                url
+            ),
+            PushError::Tls(err) => write!(f, "failed to create the TLS connector: {}", err),
+            PushError::Encode(err) => write!(f, "failed to encode metrics: {}", err),
+            PushError::Http(err) => write!(f, "failed to reach the pushgateway: {}", err),
+            PushError::Io(err) => write!(f, "failed to reach the pushgateway: {}", err),
+            PushError::Status(status) => {
+                write!(f, "the pushgateway answered with status {}", status)
+            }
+        }
+    }
+}
+
+impl Error for PushError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PushError::Tls(err) => Some(err),
+            PushError::Encode(err) => Some(err),
+            PushError::Http(err) => Some(err),
+            PushError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PushError {
+    fn from(err: io::Error) -> Self {
+        PushError::Io(err)
+    }
+}
+
+impl From<hyper::Error> for PushError {
+    fn from(err: hyper::Error) -> Self {
+        PushError::Http(err)
+    }
+}
+
+impl From<native_tls::Error> for PushError {
+    fn from(err: native_tls::Error) -> Self {
+        PushError::Tls(err)
+    }
+}
+
+impl From<prometheus::Error> for PushError {
+    fn from(err: prometheus::Error) -> Self {
+        PushError::Encode(err)
+    }
+}
+
+/// Builder for `Pusher` configuration.
+pub struct Builder {
+    registry: Registry,
+    url: String,
+    job: String,
+    grouping: Vec<(String, String)>,
+    method: PushMethod,
+    basic_auth: Option<(String, String)>,
+    interval: time::Duration,
+    timeout: time::Duration,
+    delete_on_exit: bool,
+}
+
+impl fmt::Debug for Builder {
+    /// Formats the builder omitting its registry and credentials.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("url", &self.url)
+            .field("job", &self.job)
+            .field("grouping", &self.grouping)
+            .field("method", &self.method)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("delete_on_exit", &self.delete_on_exit)
+            .finish()
+    }
+}
+
+impl Builder {
+    /// Create a new builder pushing the metric families of `registry` to
+    /// the Pushgateway at `url` under the job name `job`.
+    pub fn new<U: Into<String>, J: Into<String>>(registry: Registry, url: U, job: J) -> Self {
+        Builder {
+            registry,
+            url: url.into(),
+            job: job.into(),
+            grouping: Vec::new(),
+            method: PushMethod::Put,
+            basic_auth: None,
+            interval: time::Duration::from_secs(15),
+            timeout: time::Duration::from_secs(10),
+            delete_on_exit: false,
+        }
+    }
+
+    /// Add a grouping label identifying the group along with the job name.
+    pub fn with_grouping<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.grouping.push((key.into(), value.into()));
+        self
+    }
+
+    /// The method of the pushes, `PushMethod::Put` by default.
+    pub fn with_method(self, method: PushMethod) -> Self {
+        Builder { method, ..self }
+    }
+
+    /// Authenticate to the Pushgateway with HTTP basic authentication.
+    ///
+    /// The credentials are only sent over `https`, `try_init` fails with
+    /// `PushError::InsecureCredentials` for an `http` URL.
+    pub fn with_basic_auth<U: Into<String>, P: Into<String>>(
+        self,
+        username: U,
+        password: P,
+    ) -> Self {
+        Builder {
+            basic_auth: Some((username.into(), password.into())),
+            ..self
+        }
+    }
+
+    /// The delay between periodic pushes, 15 seconds by default.
+    pub fn with_interval(self, interval: time::Duration) -> Self {
+        Builder { interval, ..self }
+    }
+
+    /// The maximum duration of connecting, sending a request and reading its
+    /// response, 10 seconds by default.
+    pub fn with_timeout(self, timeout: time::Duration) -> Self {
+        Builder { timeout, ..self }
+    }
+
+    /// Delete the group on shutdown instead of pushing a last time.
+    pub fn with_delete_on_exit(self, delete_on_exit: bool) -> Self {
+        Builder {
+            delete_on_exit,
+            ..self
+        }
+    }
+
+    /// Create the pusher and spawn its worker thread.
+    pub fn try_init(self) -> Result<Pusher, PushError> {
+        let client = Client::new(self)?;
+        let (sender, messages) = mpsc::channel();
+        let worker = thread::Builder::new()
+            .name("opentelemetry-prometheus-push".to_string())
+            .spawn(move || client.run(messages))?;
+
+        Ok(Pusher {
+            worker: Some((sender, worker)),
+        })
+    }
+}
+
+/// The result of a request of the worker thread.
+type Reply = mpsc::Sender<Result<(), PushError>>;
+
+/// Messages sent to the worker thread.
+#[derive(Debug)]
+enum Message {
+    Push(Reply),
+    Delete(Reply),
+    /// Push or delete a last time, then stop.
+    Shutdown(Reply),
+    /// Stop without pushing.
+    Stop,
+}
+
+/// Pushes the metric families of a `Registry` to a Pushgateway.
+#[derive(Debug)]
+pub struct Pusher {
+    worker: Option<(mpsc::Sender<Message>, thread::JoinHandle<()>)>,
+}
+
+impl Pusher {
+    /// Push the registry now, independently of the periodic pushes.
+    pub fn push(&self) -> Result<(), PushError> {
+        self.request(Message::Push)
+    }
+
+    /// Delete the group from the Pushgateway.
+    pub fn delete(&self) -> Result<(), PushError> {
+        self.request(Message::Delete)
+    }
+
+    /// Stop the worker thread after it pushed a last time, or deleted the
+    /// group if `delete_on_exit` is set.
+    pub fn shutdown(mut self) -> Result<(), PushError> {
+        let result = self.request(Message::Shutdown);
+        if let Some((_, worker)) = self.worker.take() {
+            let _ = worker.join();
+        }
+
+        result
+    }
+
+    /// Send a request to the worker thread and wait for its result.
+    fn request<F: FnOnce(Reply) -> Message>(&self, message: F) -> Result<(), PushError> {
+        let stopped = || {
+            PushError::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "the push worker stopped",
+            ))
+        };
+        let (sender, _) = self.worker.as_ref().ok_or_else(stopped)?;
+        let (reply, result) = mpsc::channel();
+        sender.send(message(reply)).map_err(|_| stopped())?;
+        result.recv().map_err(|_| stopped())?
+    }
+}
+
+impl Drop for Pusher {
+    /// Stops the periodic pushes without waiting for a push in progress.
+    /// Call `shutdown` to push a last time.
+    fn drop(&mut self) {
+        if let Some((sender, _)) = self.worker.take() {
+            let _ = sender.send(Message::Stop);
+        }
+    }
+}
+
+/// An HTTP client for the group of a pusher, run by its worker thread.
+struct Client {
+    registry: Registry,
+    runtime: runtime::Runtime,
+    http: hyper::Client<HttpsConnector<HttpConnector>>,
+    uri: Uri,
+    method: PushMethod,
+    authorization: Option<HeaderValue>,
+    interval: time::Duration,
+    timeout: time::Duration,
+    delete_on_exit: bool,
+}
+
+impl fmt::Debug for Client {
+    /// Formats the client omitting its registry and credentials.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("uri", &self.uri)
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+impl Client {
+    fn new(builder: Builder) -> Result<Self, PushError> {
+        let invalid_url = || PushError::InvalidUrl(builder.url.clone());
+        let base = builder.url.parse::<Uri>().map_err(|_| invalid_url())?;
+        let https = match base.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => return Err(invalid_url()),
+        };
+        if builder.basic_auth.is_some() && !https {
+            return Err(PushError::InsecureCredentials(builder.url));
+        }
+
+        let mut path = format!(
+            "{}/metrics/{}",
+            base.path().trim_end_matches('/'),
+            path_segment("job", &builder.job)
+        );
+        for (key, value) in &builder.grouping {
+            path.push('/');
+            path.push_str(&path_segment(key, value));
+        }
+        let uri = Uri::builder()
+            .scheme(base.scheme_str().unwrap_or_default())
+            .authority(base.authority().map_or("", |authority| authority.as_str()))
+            .path_and_query(path.as_str())
+            .build()
+            .map_err(|_| invalid_url())?;
+
+        let authorization = match &builder.basic_auth {
+            Some((username, password)) => {
+                let credentials = base64::encode(format!("{}:{}", username, password));
+                let value = HeaderValue::from_str(&format!("Basic {}", credentials))
+                    .map_err(|_| invalid_url())?;
+                Some(value)
+            }
+            None => None,
+        };
+
+        // Pushes are infrequent, connections are not kept alive between them.
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        let connector = HttpsConnector::from((http, native_tls::TlsConnector::new()?.into()));
+        let http = hyper::Client::builder()
+            .pool_max_idle_per_host(0)
+            .build(connector);
+
+        let runtime = runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()?;
+
+        Ok(Client {
+            registry: builder.registry,
+            runtime,
+            http,
+            uri,
+            method: builder.method,
+            authorization,
+            interval: builder.interval,
+            timeout: builder.timeout,
+            delete_on_exit: builder.delete_on_exit,
+        })
+    }
+
+    /// Push periodically and answer the requests of the pusher until it is
+    /// shut down or dropped.
+    fn run(mut self, messages: mpsc::Receiver<Message>) {
+        let mut next_push = time::Instant::now() + self.interval;
+        loop {
+            let now = time::Instant::now();
+            match messages.recv_timeout(next_push.saturating_duration_since(now)) {
+                // Periodic pushes that fail are retried at the next interval.
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    next_push = time::Instant::now() + self.interval;
+                    if let Err(err) = self.push() {
+                        global::handle_error(err);
+                    }
+                }
+                Ok(Message::Push(reply)) => {
+                    let _ = reply.send(self.push());
+                }
+                Ok(Message::Delete(reply)) => {
+                    let _ = reply.send(self.delete());
+                }
+                Ok(Message::Shutdown(reply)) => {
+                    let result = if self.delete_on_exit {
+                        self.delete()
+                    } else {
+                        self.push()
+                    };
+                    let _ = reply.send(result);
+                    return;
+                }
+                Ok(Message::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn push(&mut self) -> Result<(), PushError> {
+        let mut body = Vec::new();
+        encode(&self.registry.gather(), Format::Text, &mut body)?;
+        self.send(self.method.as_method(), body)
+    }
+
+    fn delete(&mut self) -> Result<(), PushError> {
+        self.send(Method::DELETE, Vec::new())
+    }
+
+    fn send(&mut self, method: Method, body: Vec<u8>) -> Result<(), PushError> {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = self.uri.clone();
+        if !body.is_empty() {
+            request.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static(Format::Text.content_type()),
+            );
+            *request.body_mut() = Body::from(body);
+        }
+        if let Some(authorization) = &self.authorization {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, authorization.clone());
+        }
+
+        // The timer of the timeout is registered with the runtime, so it is
+        // created within it.
+        let (http, timeout) = (&self.http, self.timeout);
+        let request = async { tokio::time::timeout(timeout, http.request(request)).await };
+        let response = self
+            .runtime
+            .block_on(request)
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "push timed out"))??;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(PushError::Status(status.as_u16()))
+        }
+    }
+}
+
+/// The `key/value` path segment of a grouping label, with the value base64
+/// encoded unless it only contains unreserved characters.
+fn path_segment(key: &str, value: &str) -> String {
+    let unreserved = |c: char| c.is_ascii_alphanumeric() || "-._~".contains(c);
+    if !value.is_empty() && value.chars().all(unreserved) {
+        format!("{}/{}", key, value)
+    } else if value.is_empty() {
+        format!("{}@base64/=", key)
+    } else {
+        format!(
+            "{}@base64/{}",
+            key,
+            base64::encode_config(value, base64::URL_SAFE)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PushError, PushMethod};
+    use crate::api::{Counter, Meter, MetricOptions};
+    use crate::exporter::metrics::prometheus::PrometheusExporter;
+    use crate::global;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::iter;
+    use std::net::TcpListener;
+    use std::sync::{mpsc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct Request {
+        line: String,
+        headers: Vec<String>,
+        body: String,
+    }
+
+    impl Request {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers.iter().find_map(|header| {
+                let mut pair = header.splitn(2, ':');
+                match (pair.next(), pair.next()) {
+                    (Some(key), Some(value)) if key.eq_ignore_ascii_case(name) => {
+                        Some(value.trim())
+                    }
+                    _ => None,
+                }
+            })
+        }
+    }
+
+    /// Answer each request with the next status of `statuses`, then with 200.
+    /// Requests are reported before they are answered.
+    fn stub(statuses: Vec<u16>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            let statuses = statuses.into_iter().chain(iter::repeat(200));
+            for (stream, status) in listener.incoming().zip(statuses) {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    headers.push(header.trim().to_string());
+                }
+                let mut request = Request {
+                    line: line.trim().to_string(),
+                    headers,
+                    body: String::new(),
+                };
+                let length = request
+                    .header("Content-Length")
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.body = String::from_utf8(body).unwrap();
+
+                let _ = sender.send(request);
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn pushes_periodically_and_on_shutdown() {
+        let exporter = PrometheusExporter::builder().try_init().unwrap();
+        let meter = exporter.meter("job");
+        let counter = meter.new_i64_counter("files", MetricOptions::default());
+        let labels = meter.labels(Vec::new());
+        counter.add(42, &labels);
+
+        let (url, requests) = stub(Vec::new());
+        let pusher = exporter
+            .pusher(url, "backup")
+            .with_grouping("instance", "db/1")
+            .with_grouping("zone", "")
+            .with_method(PushMethod::Post)
+            .with_interval(Duration::from_millis(10))
+            .try_init()
+            .unwrap();
+
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            request.line,
+            "POST /metrics/job/backup/instance@base64/ZGIvMQ==/zone@base64/= HTTP/1.1"
+        );
+        assert_eq!(request.header("Authorization"), None);
+        assert!(request.body.contains("job_files 42\n"));
+
+        // The push on shutdown is the last request, after any periodic push
+        counter.add(1, &labels);
+        pusher.shutdown().unwrap();
+        let request = requests.try_iter().last().unwrap();
+        assert!(request.line.starts_with("POST "));
+        assert!(request.body.contains("job_files 43\n"));
+    }
+
+    #[test]
+    fn deletes_on_exit_and_reports_errors() {
+        let exporter = PrometheusExporter::builder().try_init().unwrap();
+        let (url, requests) = stub(vec![500, 202]);
+        let pusher = exporter
+            .pusher(url.clone(), "backup job")
+            .with_interval(Duration::from_secs(3600))
+            .with_delete_on_exit(true)
+            .try_init()
+            .unwrap();
+
+        match pusher.push() {
+            Err(PushError::Status(500)) => {}
+            result => panic!("unexpected push result {:?}", result),
+        }
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            request.line,
+            "PUT /metrics/job@base64/YmFja3VwIGpvYg== HTTP/1.1"
+        );
+
+        pusher.shutdown().unwrap();
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            request.line,
+            "DELETE /metrics/job@base64/YmFja3VwIGpvYg== HTTP/1.1"
+        );
+        assert!(request.body.is_empty());
+
+        match exporter.pusher("ftp://localhost", "backup").try_init() {
+            Err(PushError::InvalidUrl(_)) => {}
+            result => panic!("unexpected pusher {:?}", result),
+        }
+        match exporter
+            .pusher(url, "backup")
+            .with_basic_auth("user", "secret")
+            .try_init()
+        {
+            Err(PushError::InsecureCredentials(_)) => {}
+            result => panic!("unexpected pusher {:?}", result),
+        }
+        assert!(exporter
+            .pusher("https://localhost:9091", "backup")
+            .with_basic_auth("user", "secret")
+            .try_init()
+            .is_ok());
+    }
+
+    #[test]
+    fn reports_failed_periodic_pushes_and_pushes_within_runtimes() {
+        let exporter = PrometheusExporter::builder().try_init().unwrap();
+        let (url, _requests) = stub(vec![500]);
+        let (reported, failures) = mpsc::channel();
+        let reported = Mutex::new(reported);
+        let handler = move |err: &(dyn std::error::Error + 'static)| {
+            if err.to_string() == "the pushgateway answered with status 500" {
+                let _ = reported.lock().unwrap().send(());
+            }
+        };
+
+        global::with_error_handler(handler, || {
+            let pusher = exporter
+                .pusher(url, "backup")
+                .with_interval(Duration::from_millis(10))
+                .try_init()
+                .unwrap();
+            failures.recv_timeout(Duration::from_secs(5)).unwrap();
+
+            // Requests run on the worker's runtime, not on the caller's
+            let mut runtime = tokio::runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async { pusher.push() }).unwrap();
+            runtime.block_on(async { pusher.shutdown() }).unwrap();
+        });
+    }
+}
@@ -0,0 +1,362 @@
+//! # Prometheus Scrape Endpoint
+//!
+//! An HTTP server exposing the metric families of a `Registry` for
+//! Prometheus to scrape, enabled with the `prometheus-server` feature.
+//!
+//! Each scrape negotiates the exposition format from the `Accept` header,
+//! serving [OpenMetrics] to clients that ask for it and the Prometheus text
+//! format otherwise, and compresses the response with gzip if the client
+//! accepts it. An optional health endpoint answers `OK` as long as the
+//! server is running.
+//!
+//! The server is built on [hyper] and must be bound within a Tokio runtime.
+//! Binding returns a `ServerHandle` and a future that serves requests until
+//! the handle is shut down, spawn the future on the runtime of the
+//! application.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry::exporter::metrics::prometheus::PrometheusExporter;
+//!
+//! # async fn run() {
+//! let exporter = PrometheusExporter::builder().try_init().unwrap();
+//! let (handle, server) = exporter
+//!     .server()
+//!     .with_health_path("/health")
+//!     .bind(&([0, 0, 0, 0], 9464).into())
+//!     .unwrap();
+//! let server = tokio::spawn(server);
+//!
+//! // ...
+//!
+//! handle.shutdown();
+//! server.await.unwrap().unwrap();
+//! # }
+//! ```
+//!
+//! [OpenMetrics]: https://openmetrics.io
+//! [hyper]: https://hyper.rs
+use super::encoder::{encode, Format};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::channel::oneshot;
+use futures::future::{self, Future};
+use hyper::header::{HeaderValue, ACCEPT, ACCEPT_ENCODING, ALLOW, CONTENT_ENCODING, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use prometheus::Registry;
+use std::convert::Infallible;
+use std::fmt;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Builder for the scrape endpoint of a `Registry`.
+pub struct Builder {
+    registry: Registry,
+    metrics_path: String,
+    health_path: Option<String>,
+    gzip: bool,
+}
+
+impl fmt::Debug for Builder {
+    /// Formats the builder omitting its registry.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("metrics_path", &self.metrics_path)
+            .field("health_path", &self.health_path)
+            .field("gzip", &self.gzip)
+            .finish()
+    }
+}
+
+impl Builder {
+    /// Create a new builder serving the metric families of `registry` at
+    /// `/metrics`.
+    pub fn new(registry: Registry) -> Self {
+        Builder {
+            registry,
+            metrics_path: "/metrics".to_string(),
+            health_path: None,
+            gzip: true,
+        }
+    }
+
+    /// The path the metrics are served at, `/metrics` by default.
+    pub fn with_metrics_path<S: Into<String>>(self, path: S) -> Self {
+        Builder {
+            metrics_path: path.into(),
+            ..self
+        }
+    }
+
+    /// The path of the health endpoint, there is none by default.
+    pub fn with_health_path<S: Into<String>>(self, path: S) -> Self {
+        Builder {
+            health_path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Whether to compress responses for clients accepting gzip, enabled by
+    /// default.
+    pub fn with_gzip(self, gzip: bool) -> Self {
+        Builder { gzip, ..self }
+    }
+
+    /// Bind the server to `addr`.
+    ///
+    /// Returns a handle to stop the server and the future serving requests,
+    /// which completes once the handle is shut down and in-flight requests
+    /// are answered. Must be called within a Tokio runtime.
+    pub fn bind(
+        self,
+        addr: &SocketAddr,
+    ) -> Result<
+        (
+            ServerHandle,
+            impl Future<Output = Result<(), hyper::Error>> + Send + 'static,
+        ),
+        hyper::Error,
+    > {
+        let config = Arc::new(self);
+        let make_service = make_service_fn(move |_| {
+            let config = config.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    future::ok::<_, Infallible>(config.respond(request))
+                }))
+            }
+        });
+
+        let server = hyper::Server::try_bind(addr)?.serve(make_service);
+        let local_addr = server.local_addr();
+        let (shutdown, receiver) = oneshot::channel();
+        let server = server.with_graceful_shutdown(async move {
+            // Dropping the handle leaves the server running.
+            if receiver.await.is_err() {
+                future::pending::<()>().await
+            }
+        });
+
+        Ok((
+            ServerHandle {
+                local_addr,
+                shutdown,
+            },
+            server,
+        ))
+    }
+
+    fn respond(&self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path();
+        let is_metrics = path == self.metrics_path;
+        if !is_metrics && self.health_path.as_deref() != Some(path) {
+            return status(StatusCode::NOT_FOUND);
+        }
+        if request.method() != Method::GET && request.method() != Method::HEAD {
+            let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+            response
+                .headers_mut()
+                .insert(ALLOW, HeaderValue::from_static("GET, HEAD"));
+            return response;
+        }
+        if !is_metrics {
+            return Response::new(Body::from("OK\n"));
+        }
+
+        let headers = request.headers();
+        let format = if accepts(headers.get(ACCEPT), "application/openmetrics-text") {
+            Format::OpenMetrics
+        } else {
+            Format::Text
+        };
+        let mut body = Vec::new();
+        if let Err(err) = encode(&self.registry.gather(), format, &mut body) {
+            let mut response = Response::new(Body::from(err.to_string()));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            return response;
+        }
+
+        let gzip = self.gzip && accepts(headers.get(ACCEPT_ENCODING), "gzip");
+        if gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            match encoder.write_all(&body).and_then(|_| encoder.finish()) {
+                Ok(compressed) => body = compressed,
+                Err(err) => {
+                    let mut response = Response::new(Body::from(err.to_string()));
+                    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                    return response;
+                }
+            }
+        }
+
+        let mut response = Response::new(Body::from(body));
+        let headers = response.headers_mut();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        );
+        if gzip {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        }
+        response
+    }
+}
+
+/// A handle to a running scrape endpoint.
+#[derive(Debug)]
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl ServerHandle {
+    /// The address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and complete the server future once
+    /// in-flight requests are answered.
+    pub fn shutdown(self) {
+        let _ = self.shutdown.send(());
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Whether the comma separated `header` lists `value` with a non-zero
+/// quality.
+fn accepts(header: Option<&HeaderValue>, value: &str) -> bool {
+    let header = match header.and_then(|header| header.to_str().ok()) {
+        Some(header) => header,
+        None => return false,
+    };
+
+    header.split(',').any(|item| {
+        let mut params = item.split(';').map(str::trim);
+        params
+            .next()
+            .map_or(false, |name| name.eq_ignore_ascii_case(value))
+            && params.all(|param| {
+                let mut pair = param.splitn(2, '=').map(str::trim);
+                match (pair.next(), pair.next()) {
+                    (Some(name), Some(quality)) if name.eq_ignore_ascii_case("q") => quality
+                        .parse::<f32>()
+                        .map_or(false, |quality| quality > 0.0),
+                    _ => true,
+                }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::accepts;
+    use crate::api::{Counter, Meter, MetricOptions};
+    use crate::exporter::metrics::prometheus::PrometheusExporter;
+    use flate2::read::GzDecoder;
+    use hyper::header::{HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
+    use hyper::{Body, Client, Method, Request, StatusCode};
+    use std::io::Read;
+
+    #[test]
+    fn parses_accept_headers() {
+        let accept = HeaderValue::from_static(
+            "text/plain;q=0.5, application/openmetrics-text; version=1.0.0; q=0.9",
+        );
+        assert!(accepts(Some(&accept), "application/openmetrics-text"));
+        assert!(accepts(Some(&accept), "text/plain"));
+        assert!(!accepts(Some(&accept), "gzip"));
+
+        let accept = HeaderValue::from_static("deflate, gzip;q=0");
+        assert!(!accepts(Some(&accept), "gzip"));
+        assert!(!accepts(None, "gzip"));
+    }
+
+    #[test]
+    fn serves_metrics() {
+        let mut runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let exporter = PrometheusExporter::builder().try_init().unwrap();
+            let meter = exporter.meter("server");
+            let counter = meter.new_i64_counter("requests", MetricOptions::default());
+            counter.add(3, &meter.labels(Vec::new()));
+
+            let (handle, server) = exporter
+                .server()
+                .with_health_path("/health")
+                .bind(&([127, 0, 0, 1], 0).into())
+                .unwrap();
+            let addr = handle.local_addr();
+            let server = tokio::spawn(server);
+
+            let client = Client::new();
+            let get = |path: &str, headers: &[(_, &'static str)]| {
+                let mut request = Request::get(format!("http://{}{}", addr, path));
+                for (name, value) in headers {
+                    request = request.header(name, *value);
+                }
+                client.request(request.body(Body::empty()).unwrap())
+            };
+
+            let response = get("/metrics", &[]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers()[CONTENT_TYPE],
+                "text/plain; version=0.0.4; charset=utf-8"
+            );
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.contains("# TYPE server_requests counter\nserver_requests 3\n"));
+
+            let response = get(
+                "/metrics",
+                &[
+                    (ACCEPT, "application/openmetrics-text; version=1.0.0"),
+                    (ACCEPT_ENCODING, "gzip"),
+                ],
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                response.headers()[CONTENT_TYPE],
+                "application/openmetrics-text; version=1.0.0; charset=utf-8"
+            );
+            assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let mut decoded = String::new();
+            GzDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert!(decoded.contains("server_requests_total 3\n"));
+            assert!(decoded.ends_with("# EOF\n"));
+
+            let response = get("/health", &[]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let response = get("/other", &[]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(format!("http://{}/metrics", addr))
+                .body(Body::empty())
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+            handle.shutdown();
+            server.await.unwrap().unwrap();
+        });
+    }
+}
@@ -0,0 +1,800 @@
+//! # StatsD Exporter
+//!
+//! The [`StatsdExporter`] sends collected records to a [StatsD] server or a
+//! [DogStatsD] agent, packing as many lines as fit into each datagram.
+//! Records are converted to StatsD metric types:
+//!
+//! - sums of monotonic counters and last values of monotonic observers
+//!   become counters (`c`), sent as the increase since the previous export,
+//! - other sums and last values become gauges (`g`),
+//! - min, max, sum and count aggregates, histograms and exponential
+//!   histograms send the count and sum of the measurements since the
+//!   previous export as counters, and their min and max as gauges, see
+//!   [`MeasureType`].
+//!
+//! Records whose labels are not sent as tags share the line of their metric
+//! name, counters send the sum of their increases and gauges the last value.
+//! Counters and measures can be sampled with a sample rate.
+//!
+//! Metric names are the namespace and name of the instrument, joined by `.`.
+//! With DogStatsD enabled, the labels of each record and the attributes of
+//! its resource are sent as tags, labels taking precedence.
+//!
+//! Datagrams are sent over UDP, or a Unix domain socket on Unix platforms.
+//! The exporter is not driven by the SDK, export the records of an
+//! `Accumulator` periodically.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry::api::{Counter, Meter, MetricOptions};
+//! use opentelemetry::exporter::metrics::statsd::StatsdExporter;
+//! use opentelemetry::exporter::metrics::MetricsExporter;
+//! use opentelemetry::sdk;
+//!
+//! let exporter = StatsdExporter::builder()
+//!     .with_udp_endpoint("127.0.0.1:8125")
+//!     .with_dogstatsd(true)
+//!     .try_init()
+//!     .unwrap();
+//!
+//! let accumulator = sdk::metrics::Accumulator::default();
+//! let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+//! let counter = meter.new_i64_counter("requests", MetricOptions::default());
+//! counter.add(1, &meter.labels(Vec::new()));
+//!
+//! exporter.export(accumulator.collect());
+//! ```
+//!
+//! [`StatsdExporter`]: struct.StatsdExporter.html
+//! [`MeasureType`]: enum.MeasureType.html
+//! [StatsD]: https://github.com/statsd/statsd
+//! [DogStatsD]: https://docs.datadoghq.com/developers/dogstatsd/
+use crate::exporter::metrics::{ExportResult, MetricsExporter, Record};
+use crate::sdk::metrics::{Aggregation, Descriptor, InstrumentKind, LabelSet};
+use std::collections::HashMap;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// The default maximum datagram size over UDP, an Ethernet MTU of 1500
+/// bytes minus the IPv6 and UDP headers.
+pub const DEFAULT_UDP_PACKET_SIZE: usize = 1432;
+
+/// The default maximum datagram size over Unix domain sockets.
+pub const DEFAULT_UNIX_PACKET_SIZE: usize = 8192;
+
+/// How measures are sent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MeasureType {
+    /// The count and sum of the measurements since the previous export as
+    /// counters named `<name>.count` and `<name>.sum`. Aggregations that keep
+    /// the smallest and largest measurement also send them as gauges named
+    /// `<name>.min` and `<name>.max`.
+    Summary,
+    /// The mean of the measurements since the previous export as a histogram
+    /// (`h`), aggregated by the agent of each host. The sample rate of the
+    /// line is divided by the count of the measurements, so that the server
+    /// counts each of them.
+    ///
+    /// This is lossy: the server sees each measurement as the mean, its
+    /// percentiles, minimum and maximum are not those of the measurements.
+    Histogram,
+    /// Like `Histogram`, as a DogStatsD distribution (`d`), aggregated
+    /// globally by Datadog.
+    Distribution,
+}
+
+/// Sends records to a StatsD server or a DogStatsD agent.
+#[derive(Debug)]
+pub struct StatsdExporter {
+    transport: Transport,
+    dogstatsd: bool,
+    measure_type: MeasureType,
+    sample_rate: f64,
+    max_packet_size: usize,
+    // The cumulative values of the previous export, by metric name and labels.
+    // Only the records of the latest export are kept.
+    previous: Mutex<HashMap<(String, LabelSet), Previous>>,
+}
+
+#[derive(Debug)]
+enum Previous {
+    Sum(f64),
+    Distribution { sum: f64, count: u64 },
+}
+
+impl StatsdExporter {
+    /// Create a new exporter builder.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Convert records to StatsD lines, replacing the previous values with
+    /// the values of `records`.
+    fn lines(&self, records: &[Record]) -> Vec<String> {
+        let mut previous = self.previous.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut current = HashMap::with_capacity(records.len());
+
+        let mut lines = Lines::default();
+        for record in records {
+            let descriptor = record.descriptor();
+            let name = metric_name(descriptor);
+            let tags = if self.dogstatsd {
+                tags(record)
+            } else {
+                String::new()
+            };
+            let key = (name.clone(), record.labels().clone());
+
+            let (sum, count, extremes) = match record.aggregation() {
+                Aggregation::Sum(value) | Aggregation::LastValue { value, .. }
+                    if is_counter(descriptor) =>
+                {
+                    let value = value.to_f64();
+                    // Cumulative values only decrease if the instrument was
+                    // recreated, all of the new value is an increase.
+                    let delta = match previous.remove(&key) {
+                        Some(Previous::Sum(previous)) if previous <= value => value - previous,
+                        _ => value,
+                    };
+                    current.insert(key, Previous::Sum(value));
+                    lines.merge(name, tags, LineType::Counter, delta);
+                    continue;
+                }
+                Aggregation::Sum(value) | Aggregation::LastValue { value, .. } => {
+                    lines.merge(name, tags, LineType::Gauge, value.to_f64());
+                    continue;
+                }
+                Aggregation::MinMaxSumCount {
+                    min,
+                    max,
+                    sum,
+                    count,
+                } => (sum.to_f64(), *count, Some((min.to_f64(), max.to_f64()))),
+                Aggregation::Histogram { sum, count, .. } => (sum.to_f64(), *count, None),
+                Aggregation::ExponentialHistogram(histogram) => (
+                    histogram.sum().to_f64(),
+                    histogram.count(),
+                    Some((histogram.min().to_f64(), histogram.max().to_f64())),
+                ),
+            };
+
+            let (delta_sum, delta_count) = match previous.remove(&key) {
+                Some(Previous::Distribution {
+                    sum: previous_sum,
+                    count: previous_count,
+                }) if previous_count <= count => (sum - previous_sum, count - previous_count),
+                _ => (sum, count),
+            };
+            current.insert(key, Previous::Distribution { sum, count });
+            if delta_count > 0 {
+                self.measure(&mut lines, name, tags, delta_sum, delta_count, extremes);
+            }
+        }
+
+        // Label sets missing from this export are forgotten, a label set
+        // reappearing later is sent like a new one.
+        *previous = current;
+
+        lines
+            .lines
+            .into_iter()
+            .filter_map(|line| self.format(line))
+            .collect()
+    }
+
+    /// Add the lines of `count` measurements since the previous export.
+    fn measure(
+        &self,
+        lines: &mut Lines,
+        name: String,
+        tags: String,
+        sum: f64,
+        count: u64,
+        extremes: Option<(f64, f64)>,
+    ) {
+        match self.measure_type {
+            MeasureType::Summary => {
+                let (count_name, sum_name) = (format!("{}.count", name), format!("{}.sum", name));
+                lines.merge(count_name, tags.clone(), LineType::Counter, count as f64);
+                lines.merge(sum_name, tags.clone(), LineType::Counter, sum);
+                if let Some((min, max)) = extremes {
+                    lines.merge(format!("{}.min", name), tags.clone(), LineType::Min, min);
+                    lines.merge(format!("{}.max", name), tags, LineType::Max, max);
+                }
+            }
+            MeasureType::Histogram | MeasureType::Distribution => lines.lines.push(Line::Mean {
+                name,
+                tags,
+                mean: sum / count as f64,
+                count,
+            }),
+        }
+    }
+
+    /// The StatsD line of `line`, unless it is empty or not sampled.
+    fn format(&self, line: Line) -> Option<String> {
+        match line {
+            Line::Merged {
+                name,
+                tags,
+                line_type: LineType::Counter,
+                value,
+            } => {
+                if value == 0.0 || !value.is_finite() || !self.sampled() {
+                    return None;
+                }
+                let rate = sample_rate(self.sample_rate);
+                Some(format!("{}:{}|c{}{}", name, value, rate, tags))
+            }
+            Line::Merged {
+                name, tags, value, ..
+            } => {
+                if !value.is_finite() {
+                    return None;
+                }
+                // Signed StatsD gauges are relative, reset the gauge first to
+                // set a negative value.
+                if value < 0.0 && !self.dogstatsd {
+                    Some(format!("{name}:0|g\n{name}:{}|g", value, name = name))
+                } else {
+                    Some(format!("{}:{}|g{}", name, value, tags))
+                }
+            }
+            Line::Mean {
+                name,
+                tags,
+                mean,
+                count,
+            } => {
+                if !mean.is_finite() || !self.sampled() {
+                    return None;
+                }
+                let measure_type = if self.measure_type == MeasureType::Distribution {
+                    "d"
+                } else {
+                    "h"
+                };
+                let rate = sample_rate(self.sample_rate / count as f64);
+                Some(format!(
+                    "{}:{}|{}{}{}",
+                    name, mean, measure_type, rate, tags
+                ))
+            }
+        }
+    }
+
+    /// Whether to send a sampled line of this export.
+    fn sampled(&self) -> bool {
+        self.sample_rate >= 1.0 || rand::random::<f64>() < self.sample_rate
+    }
+}
+
+/// The lines of an export, in the order of their first record.
+#[derive(Debug, Default)]
+struct Lines {
+    lines: Vec<Line>,
+    // The index of the merged lines, by name, tags and type
+    merged: HashMap<(String, String, LineType), usize>,
+}
+
+impl Lines {
+    /// Merge `value` into the line of `name` and `tags`. Records share a line
+    /// if their labels are not sent as tags.
+    fn merge(&mut self, name: String, tags: String, line_type: LineType, value: f64) {
+        let key = (name, tags, line_type);
+        if let Some(index) = self.merged.get(&key) {
+            if let Line::Merged { value: merged, .. } = &mut self.lines[*index] {
+                *merged = match line_type {
+                    LineType::Counter => *merged + value,
+                    LineType::Gauge => value,
+                    LineType::Min => merged.min(value),
+                    LineType::Max => merged.max(value),
+                };
+            }
+            return;
+        }
+
+        self.lines.push(Line::Merged {
+            name: key.0.clone(),
+            tags: key.1.clone(),
+            line_type,
+            value,
+        });
+        self.merged.insert(key, self.lines.len() - 1);
+    }
+}
+
+#[derive(Debug)]
+enum Line {
+    /// A counter or gauge, merged over the records sharing it.
+    Merged {
+        name: String,
+        tags: String,
+        line_type: LineType,
+        value: f64,
+    },
+    /// The mean of `count` measurements, see `MeasureType::Histogram`.
+    Mean {
+        name: String,
+        tags: String,
+        mean: f64,
+        count: u64,
+    },
+}
+
+/// How the values of records sharing a line are merged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum LineType {
+    /// A counter of the sum of the values
+    Counter,
+    /// A gauge of the last value
+    Gauge,
+    /// A gauge of the smallest value
+    Min,
+    /// A gauge of the largest value
+    Max,
+}
+
+impl MetricsExporter for StatsdExporter {
+    fn export(&self, records: Vec<Record>) -> ExportResult {
+        let lines = self.lines(&records);
+        let mut result = ExportResult::Success;
+        for packet in packets(lines, self.max_packet_size) {
+            if self.transport.send(packet.as_bytes()).is_err() {
+                result = ExportResult::FailedRetryable;
+            }
+        }
+
+        result
+    }
+
+    fn shutdown(&self) {}
+}
+
+/// Builder for `StatsdExporter` configuration.
+#[derive(Debug)]
+pub struct Builder {
+    endpoint: Endpoint,
+    dogstatsd: bool,
+    measure_type: MeasureType,
+    sample_rate: f64,
+    max_packet_size: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Endpoint {
+    Udp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            endpoint: Endpoint::Udp("127.0.0.1:8125".to_string()),
+            dogstatsd: false,
+            measure_type: MeasureType::Summary,
+            sample_rate: 1.0,
+            max_packet_size: None,
+        }
+    }
+}
+
+impl Builder {
+    /// The address of the server, `127.0.0.1:8125` by default.
+    pub fn with_udp_endpoint<S: Into<String>>(self, addr: S) -> Self {
+        Builder {
+            endpoint: Endpoint::Udp(addr.into()),
+            ..self
+        }
+    }
+
+    /// The path of the Unix domain socket of the server.
+    #[cfg(unix)]
+    pub fn with_unix_endpoint<P: Into<PathBuf>>(self, path: P) -> Self {
+        Builder {
+            endpoint: Endpoint::Unix(path.into()),
+            ..self
+        }
+    }
+
+    /// Whether to send DogStatsD tags, disabled by default.
+    pub fn with_dogstatsd(self, dogstatsd: bool) -> Self {
+        Builder { dogstatsd, ..self }
+    }
+
+    /// How measures are sent, `MeasureType::Summary` by default.
+    pub fn with_measure_type(self, measure_type: MeasureType) -> Self {
+        Builder {
+            measure_type,
+            ..self
+        }
+    }
+
+    /// The rate counters and measures are sampled at, 1 by default. Each of
+    /// their lines is sent with this probability, and the server scales its
+    /// value up by the inverse of the rate. Rates are clamped to `(0, 1]`.
+    pub fn with_sample_rate(self, sample_rate: f64) -> Self {
+        let sample_rate = if sample_rate <= 0.0 {
+            f64::MIN_POSITIVE
+        } else if sample_rate < 1.0 {
+            sample_rate
+        } else {
+            // Including NaN
+            1.0
+        };
+        Builder {
+            sample_rate,
+            ..self
+        }
+    }
+
+    /// The maximum size of datagrams, `DEFAULT_UDP_PACKET_SIZE` or
+    /// `DEFAULT_UNIX_PACKET_SIZE` by default. Lines longer than this are sent
+    /// in a datagram of their own.
+    pub fn with_max_packet_size(self, size: usize) -> Self {
+        Builder {
+            max_packet_size: Some(size),
+            ..self
+        }
+    }
+
+    /// Create the exporter and connect its socket.
+    pub fn try_init(self) -> io::Result<StatsdExporter> {
+        let (transport, default_packet_size) = match self.endpoint {
+            Endpoint::Udp(addr) => {
+                let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "address resolved to no address")
+                })?;
+                let local = if addr.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(local)?;
+                socket.connect(addr)?;
+                (Transport::Udp(socket), DEFAULT_UDP_PACKET_SIZE)
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(path)?;
+                (Transport::Unix(socket), DEFAULT_UNIX_PACKET_SIZE)
+            }
+        };
+
+        Ok(StatsdExporter {
+            transport,
+            dogstatsd: self.dogstatsd,
+            measure_type: self.measure_type,
+            sample_rate: self.sample_rate,
+            max_packet_size: self.max_packet_size.unwrap_or(default_packet_size),
+            previous: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+impl Transport {
+    fn send(&self, packet: &[u8]) -> io::Result<usize> {
+        match self {
+            Transport::Udp(socket) => socket.send(packet),
+            #[cfg(unix)]
+            Transport::Unix(socket) => socket.send(packet),
+        }
+    }
+}
+
+fn is_counter(descriptor: &Descriptor) -> bool {
+    match descriptor.instrument_kind() {
+        InstrumentKind::Counter | InstrumentKind::Observer => descriptor.monotonic(),
+        _ => false,
+    }
+}
+
+/// The namespace and name of the instrument, joined by `.`.
+fn metric_name(descriptor: &Descriptor) -> String {
+    let name = if descriptor.namespace().is_empty() {
+        descriptor.name().to_string()
+    } else {
+        format!("{}.{}", descriptor.namespace(), descriptor.name())
+    };
+
+    sanitize(&name, ":|@#,")
+}
+
+/// The DogStatsD tags of the labels and resource of a record.
+fn tags(record: &Record) -> String {
+    let mut tags = record
+        .labels()
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<Vec<_>>();
+    for (key, value) in record.resource().iter() {
+        let key = key.as_str();
+        if record.labels().get(key).is_none() {
+            tags.push((key.to_string(), value.clone().into()));
+        }
+    }
+    if tags.is_empty() {
+        return String::new();
+    }
+
+    let tags = tags
+        .iter()
+        .map(|(key, value)| format!("{}:{}", sanitize(key, ":|@#,"), sanitize(value, "|#,")))
+        .collect::<Vec<_>>();
+    format!("|#{}", tags.join(","))
+}
+
+/// The sample rate field of a line, empty for a rate of 1.
+fn sample_rate(rate: f64) -> String {
+    if rate < 1.0 {
+        format!("|@{}", rate)
+    } else {
+        String::new()
+    }
+}
+
+/// Replace whitespace and `reserved` characters with `_`.
+fn sanitize(value: &str, reserved: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || reserved.contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Join lines into packets of at most `max_packet_size` bytes.
+fn packets(lines: Vec<String>, max_packet_size: usize) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut packet = String::new();
+    for line in lines {
+        if !packet.is_empty() && packet.len() + 1 + line.len() > max_packet_size {
+            packets.push(std::mem::take(&mut packet));
+        }
+        if !packet.is_empty() {
+            packet.push('\n');
+        }
+        packet.push_str(&line);
+    }
+    if !packet.is_empty() {
+        packets.push(packet);
+    }
+
+    packets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{packets, MeasureType, StatsdExporter};
+    use crate::api::{Counter, Gauge, Key, Measure, Meter, MetricOptions, UpDownCounter};
+    use crate::exporter::metrics::{ExportResult, MetricsExporter};
+    use crate::sdk;
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    fn receive(socket: &UdpSocket) -> String {
+        let mut buffer = [0; 2048];
+        let length = socket.recv(&mut buffer).unwrap();
+        String::from_utf8(buffer[..length].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn sends_deltas_and_dogstatsd_tags() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = StatsdExporter::builder()
+            .with_udp_endpoint(server.local_addr().unwrap().to_string())
+            .with_dogstatsd(true)
+            .with_measure_type(MeasureType::Distribution)
+            .try_init()
+            .unwrap();
+
+        let accumulator = sdk::metrics::Accumulator::builder()
+            .with_resource(sdk::Resource::new(vec![
+                Key::new("host").string("web-1"),
+                Key::new("method").string("unused"),
+            ]))
+            .build();
+        let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+        let labels = meter.labels(vec![Key::new("method").string("GET")]);
+        let requests = meter.new_i64_counter("requests", MetricOptions::default());
+        let queued = meter.new_i64_up_down_counter("queued", MetricOptions::default());
+        let latency = meter.new_f64_measure("latency", MetricOptions::default());
+        requests.add(3, &labels);
+        queued.add(-2, &labels);
+        latency.record(1.0, &labels);
+        latency.record(2.0, &labels);
+
+        assert_eq!(
+            exporter.export(accumulator.collect()),
+            ExportResult::Success
+        );
+        assert_eq!(
+            receive(&server),
+            "app.requests:3|c|#method:GET,host:web-1\n\
+             app.queued:-2|g|#method:GET,host:web-1\n\
+             app.latency:1.5|d|@0.5|#method:GET,host:web-1"
+        );
+
+        requests.add(2, &labels);
+        latency.record(4.0, &labels);
+        exporter.export(accumulator.collect());
+        assert_eq!(
+            receive(&server),
+            "app.requests:2|c|#method:GET,host:web-1\n\
+             app.queued:-2|g|#method:GET,host:web-1\n\
+             app.latency:4|d|#method:GET,host:web-1"
+        );
+    }
+
+    #[test]
+    fn sums_deltas_of_label_sets_without_tags() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = StatsdExporter::builder()
+            .with_udp_endpoint(server.local_addr().unwrap().to_string())
+            .try_init()
+            .unwrap();
+
+        let accumulator = sdk::metrics::Accumulator::default();
+        let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+        let requests = meter.new_i64_counter("requests", MetricOptions::default());
+        requests.add(3, &meter.labels(vec![Key::new("method").string("GET")]));
+        requests.add(5, &meter.labels(vec![Key::new("method").string("POST")]));
+
+        exporter.export(accumulator.collect());
+        assert_eq!(receive(&server), "app.requests:8|c");
+
+        // Nothing changed, nothing is sent
+        exporter.export(accumulator.collect());
+        server
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        assert!(server.recv(&mut [0; 64]).is_err());
+
+        // Label sets missing from an export are forgotten
+        exporter.export(Vec::new());
+        assert!(exporter.previous.lock().unwrap().is_empty());
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        exporter.export(accumulator.collect());
+        assert_eq!(receive(&server), "app.requests:8|c");
+    }
+
+    #[test]
+    fn sends_measure_summaries_and_sample_rates() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = StatsdExporter::builder()
+            .with_udp_endpoint(server.local_addr().unwrap().to_string())
+            .try_init()
+            .unwrap();
+
+        let accumulator = sdk::metrics::Accumulator::builder()
+            .with_selector(sdk::metrics::Selector::Simple)
+            .build();
+        let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+        let labels = meter.labels(Vec::new());
+        let latency = meter.new_f64_measure("latency", MetricOptions::default());
+        latency.record(1.0, &labels);
+        latency.record(3.0, &labels);
+
+        exporter.export(accumulator.collect());
+        assert_eq!(
+            receive(&server),
+            "app.latency.count:2|c\n\
+             app.latency.sum:4|c\n\
+             app.latency.min:1|g\n\
+             app.latency.max:3|g"
+        );
+
+        // Sampled lines carry their rate, gauges are always sent
+        let exporter = StatsdExporter::builder()
+            .with_udp_endpoint(server.local_addr().unwrap().to_string())
+            .with_sample_rate(0.5)
+            .try_init()
+            .unwrap();
+        latency.record(2.0, &labels);
+        meter
+            .new_i64_gauge("queued", MetricOptions::default())
+            .set(4, &labels);
+        exporter.export(accumulator.collect());
+        let packet = receive(&server);
+        assert!(packet.contains("app.queued:4|g"));
+        for line in packet.lines().filter(|line| line.contains("|c")) {
+            assert!(line.ends_with("|c|@0.5"), "unexpected line {}", line);
+        }
+    }
+
+    #[test]
+    fn resets_negative_statsd_gauges() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = StatsdExporter::builder()
+            .with_udp_endpoint(server.local_addr().unwrap().to_string())
+            .try_init()
+            .unwrap();
+
+        let accumulator = sdk::metrics::Accumulator::default();
+        let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+        let labels = meter.labels(vec![Key::new("zone").string("a b")]);
+        meter
+            .new_f64_gauge("temperature", MetricOptions::default())
+            .set(-4.5, &labels);
+
+        exporter.export(accumulator.collect());
+        assert_eq!(
+            receive(&server),
+            "app.temperature:0|g\napp.temperature:-4.5|g"
+        );
+    }
+
+    #[test]
+    fn packs_lines_into_packets() {
+        let lines = vec![
+            "a:1|c".to_string(),
+            "b:2|c".to_string(),
+            "c:3|c".to_string(),
+        ];
+        assert_eq!(
+            packets(lines.clone(), 11),
+            vec!["a:1|c\nb:2|c".to_string(), "c:3|c".to_string()]
+        );
+        assert_eq!(packets(lines.clone(), 1).len(), 3);
+        assert_eq!(packets(lines, 1432).len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sends_over_unix_domain_sockets() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = std::env::temp_dir().join(format!("statsd-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let exporter = StatsdExporter::builder()
+            .with_unix_endpoint(&path)
+            .try_init()
+            .unwrap();
+
+        let accumulator = sdk::metrics::Accumulator::default();
+        let meter = sdk::Meter::with_accumulator("app", accumulator.clone());
+        meter
+            .new_i64_counter("requests", MetricOptions::default())
+            .add(1, &meter.labels(Vec::new()));
+        exporter.export(accumulator.collect());
+
+        let mut buffer = [0; 64];
+        let length = server.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..length], b"app.requests:1|c");
+        let _ = std::fs::remove_file(&path);
+    }
+}
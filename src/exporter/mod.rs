@@ -12,3 +12,14 @@
 pub mod metrics;
 #[cfg(feature = "trace")]
 pub mod trace;
+
+/// Describes the result of an export.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportResult {
+    /// Batch is successfully exported.
+    Success,
+    /// Batch export failed. Caller must not retry.
+    FailedNotRetryable,
+    /// Batch export failed transiently. Caller should record error and may retry.
+    FailedRetryable,
+}
@@ -0,0 +1,163 @@
+//! # In-Memory Span Exporter
+//!
+//! The in-memory [`SpanExporter`] keeps exported spans in memory, which makes
+//! it useful to assert on the spans produced by instrumented code in tests.
+//! The exporter is a cheap handle, clones share the same storage.
+//!
+//! When used with a [`BatchSpanProcessor`], spans are only visible after the
+//! processor exported them, e.g. after calling `force_flush` on the provider.
+//!
+//! [`SpanExporter`]: ../trait.SpanExporter.html
+//! [`BatchSpanProcessor`]: ../../../sdk/trace/span_processor/struct.BatchSpanProcessor.html
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::api::{Provider, Span, Tracer};
+//! use opentelemetry::exporter::trace::in_memory::InMemorySpanExporter;
+//! use opentelemetry::sdk;
+//!
+//! let exporter = InMemorySpanExporter::default();
+//! let provider = sdk::Provider::builder()
+//!     .with_simple_exporter(exporter.clone())
+//!     .build();
+//!
+//! provider.get_tracer("test").start("my-span", None).end();
+//!
+//! let spans = exporter.get_finished_spans();
+//! assert_eq!(spans.len(), 1);
+//! assert_eq!(spans[0].name, "my-span");
+//! ```
+use crate::api;
+use crate::exporter::trace;
+use std::any;
+use std::sync::{Arc, Mutex};
+
+/// A [`SpanExporter`] that stores exported spans in memory.
+///
+/// [`SpanExporter`]: ../trait.SpanExporter.html
+#[derive(Clone, Debug, Default)]
+pub struct InMemorySpanExporter {
+    spans: Arc<Mutex<Vec<Arc<trace::SpanData>>>>,
+}
+
+impl InMemorySpanExporter {
+    /// All spans exported so far, in the order they were exported.
+    pub fn get_finished_spans(&self) -> Vec<Arc<trace::SpanData>> {
+        self.find(|_| true)
+    }
+
+    /// Remove all exported spans.
+    pub fn reset(&self) {
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.clear();
+        }
+    }
+
+    /// Exported spans with the given name.
+    pub fn spans_by_name(&self, name: &str) -> Vec<Arc<trace::SpanData>> {
+        self.find(|span| span.name == name)
+    }
+
+    /// Exported spans belonging to the given trace.
+    pub fn spans_by_trace_id(&self, trace_id: api::TraceId) -> Vec<Arc<trace::SpanData>> {
+        self.find(|span| span.context.trace_id() == trace_id)
+    }
+
+    /// Exported spans whose parent is the given span.
+    pub fn children_of(&self, parent_span_id: api::SpanId) -> Vec<Arc<trace::SpanData>> {
+        self.find(|span| span.parent_span_id == parent_span_id)
+    }
+
+    /// Exported spans for which `predicate` returns `true`.
+    pub fn find<F>(&self, predicate: F) -> Vec<Arc<trace::SpanData>>
+    where
+        F: Fn(&trace::SpanData) -> bool,
+    {
+        self.spans
+            .lock()
+            .map(|spans| {
+                spans
+                    .iter()
+                    .filter(|span| predicate(span))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl trace::SpanExporter for InMemorySpanExporter {
+    /// Store spans in memory
+    fn export(&self, batch: Vec<Arc<trace::SpanData>>) -> trace::ExportResult {
+        match self.spans.lock() {
+            Ok(mut spans) => {
+                spans.extend(batch);
+                trace::ExportResult::Success
+            }
+            Err(_) => trace::ExportResult::FailedNotRetryable,
+        }
+    }
+
+    /// Ignored, spans stay available after shutdown.
+    fn shutdown(&self) {}
+
+    /// Allows `InMemorySpanExporter` to be downcast from trait object.
+    fn as_any(&self) -> &dyn any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InMemorySpanExporter;
+    use crate::api::{Provider, Span, Tracer};
+    use crate::{api, sdk};
+
+    #[test]
+    fn lookups() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = provider.get_tracer("test");
+
+        let parent = tracer.start("parent", None);
+        let parent_context = parent.get_context();
+        tracer.start("child", Some(parent_context.clone())).end();
+        parent.end();
+        drop(parent);
+        tracer.start("other", None).end();
+
+        assert_eq!(exporter.get_finished_spans().len(), 3);
+        assert_eq!(exporter.spans_by_name("child").len(), 1);
+        assert_eq!(
+            exporter.spans_by_trace_id(parent_context.trace_id()).len(),
+            2
+        );
+        let children = exporter.children_of(parent_context.span_id());
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "child");
+
+        exporter.reset();
+        assert!(exporter.get_finished_spans().is_empty());
+    }
+
+    #[test]
+    fn batch_processor() {
+        let exporter = InMemorySpanExporter::default();
+        let processor = sdk::BatchSpanProcessor::builder(
+            exporter.clone(),
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            |_| futures::stream::pending::<()>(),
+        )
+        .build();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        provider.get_tracer("test").start("batched", None).end();
+
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::Success]);
+        assert_eq!(exporter.spans_by_name("batched").len(), 1);
+    }
+}
@@ -2,20 +2,42 @@
 use crate::{api, sdk};
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::SystemTime;
 
+pub mod in_memory;
 pub mod stdout;
 
-/// Describes the result of an export.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum ExportResult {
-    /// Batch is successfully exported.
-    Success,
-    /// Batch export failed. Caller must not retry.
-    FailedNotRetryable,
-    /// Batch export failed transiently. Caller should record error and may retry.
-    FailedRetryable,
+pub use super::ExportResult;
+
+/// The future returned by [`SpanExporter::export_async`].
+///
+/// [`SpanExporter::export_async`]: trait.SpanExporter.html#method.export_async
+pub type ExportFuture<'a> = Pin<Box<dyn Future<Output = ExportResult> + Send + 'a>>;
+
+/// Runs a blocking export on a new thread and returns a future completing
+/// with its result, so that it can be timed out without blocking the caller.
+///
+/// Dropping the future does not stop the export, it keeps running until the
+/// upload returns. The export fails with a retryable error if the thread
+/// cannot be spawned.
+pub fn spawn_export<F>(export: F) -> ExportFuture<'static>
+where
+    F: FnOnce() -> ExportResult + Send + 'static,
+{
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let spawned = std::thread::Builder::new()
+        .name("opentelemetry-export".to_string())
+        .spawn(move || {
+            let _ = sender.send(export());
+        });
+    if spawned.is_err() {
+        return Box::pin(futures::future::ready(ExportResult::FailedRetryable));
+    }
+
+    Box::pin(async move { receiver.await.unwrap_or(ExportResult::FailedNotRetryable) })
 }
 
 /// `SpanExporter` defines the interface that protocol-specific exporters must
@@ -43,6 +65,20 @@ pub trait SpanExporter: Send + Sync + std::fmt::Debug {
     /// upper limit after which the call must time out with an error result.
     fn export(&self, batch: Vec<Arc<SpanData>>) -> ExportResult;
 
+    /// Exports a batch of telemetry data without blocking the calling task.
+    ///
+    /// The `BatchSpanProcessor` exports through this function and drops the
+    /// returned future if it does not complete within the export timeout. The
+    /// default implementation calls `export` when the future is first polled,
+    /// which blocks the processor's worker until it returns and cannot be
+    /// timed out. Exporters performing blocking I/O should override it, e.g.
+    /// by running the upload with [`spawn_export`].
+    ///
+    /// [`spawn_export`]: fn.spawn_export.html
+    fn export_async(&self, batch: Vec<Arc<SpanData>>) -> ExportFuture<'_> {
+        Box::pin(futures::future::lazy(move |_| self.export(batch)))
+    }
+
     /// Shuts down the exporter. Called when SDK is shut down. This is an
     /// opportunity for exporter to do any cleanup required.
     ///
@@ -90,6 +126,23 @@ pub struct SpanData {
     pub resource: Arc<sdk::Resource>,
 }
 
+impl SpanData {
+    /// The number of attributes dropped because of the attribute limit.
+    pub fn dropped_attributes_count(&self) -> u32 {
+        self.attributes.dropped_count()
+    }
+
+    /// The number of events dropped because of the event limit.
+    pub fn dropped_events_count(&self) -> u32 {
+        self.message_events.dropped_count()
+    }
+
+    /// The number of links dropped because of the link limit.
+    pub fn dropped_links_count(&self) -> u32 {
+        self.links.dropped_count()
+    }
+}
+
 #[cfg(feature = "serialize")]
 #[cfg(test)]
 mod tests {
@@ -66,12 +66,55 @@
 //! [`BoxedSpan`]: struct.BoxedSpan.html
 //! [`trace_provider`]: fn.trace_provider.html
 //! [trait objects]: https://doc.rust-lang.org/reference/types/trait-object.html#trait-objects
+//!
+//! ## Metrics
+//!
+//! Meters are provided the same way by the [`GlobalMeterProvider`], which
+//! can be accessed anywhere via [`meter_provider`] and is configured with
+//! [`set_meter_provider`]. Its [`BoxedMeter`] instances implement [`Meter`]
+//! by wrapping the meter of the configured [`MeterProvider`].
+//!
+//! ```rust
+//! use opentelemetry::api::{Counter, Key, Meter, MetricOptions};
+//! use opentelemetry::{global, sdk};
+//!
+//! // Route the metrics of all libraries to an SDK when your app starts
+//! let provider = sdk::MeterProvider::default();
+//! global::set_meter_provider(provider.clone());
+//!
+//! // Library code records through whatever provider was installed
+//! let meter = global::meter("my-lib");
+//! let requests = meter.new_i64_counter("requests", MetricOptions::default());
+//! requests.add(1, &meter.labels(vec![Key::new("path").string("/")]));
+//!
+//! assert_eq!(provider.accumulator().collect().len(), 1);
+//! ```
+//!
+//! [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+//! [`meter_provider`]: fn.meter_provider.html
+//! [`set_meter_provider`]: fn.set_meter_provider.html
+//! [`BoxedMeter`]: struct.BoxedMeter.html
+//! [`Meter`]: ../api/metrics/trait.Meter.html
+//! [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
 use crate::{api, api::Provider};
 use std::any::Any;
 use std::fmt;
 use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
+mod error_handler;
+mod metrics;
+
+#[cfg(test)]
+pub(crate) use error_handler::with_error_handler;
+pub use error_handler::{handle_error, set_error_handler};
+pub use metrics::{
+    meter, meter_provider, set_meter_provider, BoxedBatchObserver, BoxedCounter, BoxedGauge,
+    BoxedHandle, BoxedLabelSet, BoxedMeasure, BoxedMeter, BoxedObserver, BoxedUpDownCounter,
+    BoxedUpDownCounterHandle, GenericBatchObserver, GenericInstrument, GenericMeter,
+    GenericMeterProvider, GlobalMeterProvider, ObserverCallback,
+};
+
 /// Wraps the [`BoxedTracer`]'s [`Span`] so it can be used generically by
 /// applications without knowing the underlying type.
 ///
@@ -403,9 +446,10 @@ where
     *global_provider = GlobalProvider::new(new_provider);
 }
 
-/// Returns [`NoopMeter`] for now
+/// Creates a meter without a name via the configured [`GlobalMeterProvider`].
 ///
-/// [`NoopMeter`]: ../api/trace/noop/struct.NoopMeter.html
-pub fn global_meter() -> crate::api::NoopMeter {
-    crate::api::NoopMeter {}
+/// [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+#[deprecated(note = "use `global::meter` to create a named meter")]
+pub fn global_meter() -> BoxedMeter {
+    meter("")
 }
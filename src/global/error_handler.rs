@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A function handling errors that cannot be returned to the caller.
+type ErrorHandler = Arc<dyn Fn(&(dyn Error + 'static)) + Send + Sync>;
+
+lazy_static::lazy_static! {
+    /// The global error handler, errors are printed to stderr if unset.
+    static ref GLOBAL_ERROR_HANDLER: RwLock<Option<ErrorHandler>> = RwLock::new(None);
+}
+
+/// Handles an error that cannot be returned to the caller, e.g. an instrument
+/// conflict in the infallible `Meter` API, with the handler installed by
+/// [`set_error_handler`]. Errors are printed to stderr by default.
+///
+/// The handler is called without holding any lock, so it may report errors
+/// or replace the handler itself.
+///
+/// [`set_error_handler`]: fn.set_error_handler.html
+pub fn handle_error<E: Error + 'static>(err: E) {
+    let handler = GLOBAL_ERROR_HANDLER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match handler {
+        Some(handler) => handler(&err),
+        None => eprintln!("OpenTelemetry error occurred: {}", err),
+    }
+}
+
+/// Sets the function handling the errors passed to [`handle_error`].
+///
+/// [`handle_error`]: fn.handle_error.html
+pub fn set_error_handler<F>(handler: F)
+where
+    F: Fn(&(dyn Error + 'static)) + Send + Sync + 'static,
+{
+    replace_error_handler(Some(Arc::new(handler)));
+}
+
+/// Replace the global handler, returning the previous one.
+fn replace_error_handler(handler: Option<ErrorHandler>) -> Option<ErrorHandler> {
+    let mut global_handler = GLOBAL_ERROR_HANDLER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    std::mem::replace(&mut *global_handler, handler)
+}
+
+#[cfg(test)]
+lazy_static::lazy_static! {
+    /// Serializes tests installing a global handler.
+    static ref TEST_HANDLER_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+}
+
+/// Run `f` with `handler` installed as the global handler, the previous
+/// handler is restored afterwards. Tests calling this run one at a time.
+#[cfg(test)]
+pub(crate) fn with_error_handler<H, F, T>(handler: H, f: F) -> T
+where
+    H: Fn(&(dyn Error + 'static)) + Send + Sync + 'static,
+    F: FnOnce() -> T,
+{
+    /// Restores the previous handler, even if `f` panics.
+    struct Restore(Option<ErrorHandler>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            replace_error_handler(self.0.take());
+        }
+    }
+
+    let _lock = TEST_HANDLER_LOCK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let _restore = Restore(replace_error_handler(Some(Arc::new(handler))));
+
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handle_error, set_error_handler, with_error_handler};
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct TestError(usize);
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "test error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    #[test]
+    fn handler_may_report_errors_and_replace_itself() {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let handler_handled = handled.clone();
+        with_error_handler(
+            move |err| {
+                if err.to_string() == "test error 1" {
+                    handler_handled.fetch_add(1, Ordering::SeqCst);
+                    handle_error(TestError(2));
+                    set_error_handler(|_| {});
+                }
+            },
+            || handle_error(TestError(1)),
+        );
+
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,1064 @@
+//! # Global Metrics API
+//!
+//! The [`GlobalMeterProvider`] gives library code access to the
+//! [`MeterProvider`] configured by the application. Its [`BoxedMeter`]s and
+//! their instruments wrap the meter and instruments of the underlying
+//! provider as trait objects, so they can be used without knowing the type
+//! of the SDK that is installed.
+//!
+//! Label sets created by a `BoxedMeter` can only be used with instruments of
+//! the same underlying meter type, measurements recorded with a label set of
+//! a different provider are dropped.
+//!
+//! [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+//! [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
+//! [`BoxedMeter`]: struct.BoxedMeter.html
+use crate::api;
+use std::any::Any;
+use std::fmt;
+use std::marker;
+use std::sync::{Arc, RwLock};
+
+type DynLabelSet = dyn Any + Send + Sync;
+type DynHandle = dyn api::InstrumentHandle + Send + Sync;
+
+/// Wraps the label set of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedLabelSet(Arc<DynLabelSet>);
+
+impl api::LabelSet for BoxedLabelSet {}
+
+/// Allows a specific instrument to be used generically by the instruments of
+/// a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+pub trait GenericInstrument: Send + Sync + 'static {
+    /// Records a value if the label set belongs to the underlying meter.
+    fn record_one_boxed(&self, value: api::MeasurementValue, label_set: &DynLabelSet);
+
+    /// Returns a trait object so the underlying implementation can be swapped
+    /// out at runtime.
+    fn acquire_handle_boxed(&self, label_set: &DynLabelSet) -> Box<DynHandle>;
+}
+
+/// An instrument of the underlying meter and a way to acquire its handles.
+struct Erased<I, LS> {
+    instrument: I,
+    acquire_handle: fn(&I, &LS) -> Box<DynHandle>,
+}
+
+impl<I, LS> GenericInstrument for Erased<I, LS>
+where
+    I: api::Instrument<LS> + Send + Sync + 'static,
+    LS: api::LabelSet + Send + Sync + 'static,
+{
+    fn record_one_boxed(&self, value: api::MeasurementValue, label_set: &DynLabelSet) {
+        if let Some(label_set) = label_set.downcast_ref::<LS>() {
+            self.instrument.record_one(value, label_set)
+        }
+    }
+
+    fn acquire_handle_boxed(&self, label_set: &DynLabelSet) -> Box<DynHandle> {
+        match label_set.downcast_ref::<LS>() {
+            Some(label_set) => (self.acquire_handle)(&self.instrument, label_set),
+            None => Box::new(Unbound),
+        }
+    }
+}
+
+/// A handle ignoring all measurements, used for label sets that do not
+/// belong to the underlying meter and for observers.
+struct Unbound;
+
+impl api::InstrumentHandle for Unbound {
+    fn record_one(&self, _value: api::MeasurementValue) {
+        // Ignored
+    }
+}
+
+fn unbound<I, LS>(_instrument: &I, _label_set: &LS) -> Box<DynHandle> {
+    Box::new(Unbound)
+}
+
+/// Reports the observations of a boxed observer callback to the observer
+/// result of the underlying meter.
+struct Observed<'a, T, LS> {
+    result: &'a api::ObserverResult<'a, T, LS>,
+    convert: fn(api::MeasurementValue) -> T,
+}
+
+impl<'a, T, LS> api::Instrument<BoxedLabelSet> for Observed<'a, T, LS>
+where
+    T: Into<api::MeasurementValue>,
+    LS: api::LabelSet + 'static,
+{
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        if let Some(label_set) = label_set.0.downcast_ref::<LS>() {
+            self.result.observe((self.convert)(value), label_set)
+        }
+    }
+}
+
+/// A boxed observer callback.
+pub type ObserverCallback<T> = Box<dyn Fn(api::ObserverResult<'_, T, BoxedLabelSet>) + Send + Sync>;
+
+/// Allows a specific [`Meter`] to be used generically by [`BoxedMeter`]
+/// instances by mirroring the interface and boxing the return types.
+///
+/// [`Meter`]: ../api/metrics/trait.Meter.html
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+pub trait GenericMeter: fmt::Debug + Send + Sync + 'static {
+    /// Returns a trait object so the underlying implementation can be swapped
+    /// out at runtime.
+    fn labels_boxed(&self, key_values: Vec<api::KeyValue>) -> Arc<DynLabelSet>;
+
+    /// Creates a new `i64` counter as a trait object.
+    fn new_i64_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `f64` counter as a trait object.
+    fn new_f64_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `i64` up-down counter as a trait object.
+    fn new_i64_up_down_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `f64` up-down counter as a trait object.
+    fn new_f64_up_down_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `i64` gauge as a trait object.
+    fn new_i64_gauge_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `f64` gauge as a trait object.
+    fn new_f64_gauge_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `i64` measure as a trait object.
+    fn new_i64_measure_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `f64` measure as a trait object.
+    fn new_f64_measure_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Registers a new `i64` observer as a trait object.
+    fn register_i64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+        callback: ObserverCallback<i64>,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Registers a new `f64` observer as a trait object.
+    fn register_f64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+        callback: ObserverCallback<f64>,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new batch observer as a trait object.
+    fn new_batch_observer_boxed(&self) -> Box<dyn GenericBatchObserver>;
+}
+
+impl<M> GenericMeter for M
+where
+    M: api::Meter + fmt::Debug + Send + Sync + 'static,
+    M::LabelSet: Send + Sync + 'static,
+    M::I64Counter: Send + Sync + 'static,
+    <M::I64Counter as api::Counter<i64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::F64Counter: Send + Sync + 'static,
+    <M::F64Counter as api::Counter<f64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::I64UpDownCounter: Send + Sync + 'static,
+    <M::I64UpDownCounter as api::UpDownCounter<i64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::F64UpDownCounter: Send + Sync + 'static,
+    <M::F64UpDownCounter as api::UpDownCounter<f64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::I64Gauge: Send + Sync + 'static,
+    <M::I64Gauge as api::Gauge<i64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::F64Gauge: Send + Sync + 'static,
+    <M::F64Gauge as api::Gauge<f64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::I64Measure: Send + Sync + 'static,
+    <M::I64Measure as api::Measure<i64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::F64Measure: Send + Sync + 'static,
+    <M::F64Measure as api::Measure<f64, M::LabelSet>>::Handle: Send + Sync + 'static,
+    M::I64Observer: Send + Sync + 'static,
+    M::F64Observer: Send + Sync + 'static,
+    M::BatchObserver: Send + Sync + 'static,
+    <M::BatchObserver as api::BatchObserver<M::LabelSet>>::I64Observer: Send + Sync + 'static,
+    <M::BatchObserver as api::BatchObserver<M::LabelSet>>::F64Observer: Send + Sync + 'static,
+{
+    fn labels_boxed(&self, key_values: Vec<api::KeyValue>) -> Arc<DynLabelSet> {
+        Arc::new(self.labels(key_values))
+    }
+
+    fn new_i64_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_i64_counter(name, opts),
+            acquire_handle: |counter: &M::I64Counter, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Counter::<i64, M::LabelSet>::acquire_handle(
+                    counter, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_f64_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_f64_counter(name, opts),
+            acquire_handle: |counter: &M::F64Counter, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Counter::<f64, M::LabelSet>::acquire_handle(
+                    counter, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_i64_up_down_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_i64_up_down_counter(name, opts),
+            acquire_handle: |counter: &M::I64UpDownCounter,
+                             label_set: &M::LabelSet|
+             -> Box<DynHandle> {
+                Box::new(api::UpDownCounter::<i64, M::LabelSet>::acquire_handle(
+                    counter, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_f64_up_down_counter_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_f64_up_down_counter(name, opts),
+            acquire_handle: |counter: &M::F64UpDownCounter,
+                             label_set: &M::LabelSet|
+             -> Box<DynHandle> {
+                Box::new(api::UpDownCounter::<f64, M::LabelSet>::acquire_handle(
+                    counter, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_i64_gauge_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_i64_gauge(name, opts),
+            acquire_handle: |gauge: &M::I64Gauge, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Gauge::<i64, M::LabelSet>::acquire_handle(
+                    gauge, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_f64_gauge_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_f64_gauge(name, opts),
+            acquire_handle: |gauge: &M::F64Gauge, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Gauge::<f64, M::LabelSet>::acquire_handle(
+                    gauge, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_i64_measure_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_i64_measure(name, opts),
+            acquire_handle: |measure: &M::I64Measure, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Measure::<i64, M::LabelSet>::acquire_handle(
+                    measure, label_set,
+                ))
+            },
+        })
+    }
+
+    fn new_f64_measure_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.new_f64_measure(name, opts),
+            acquire_handle: |measure: &M::F64Measure, label_set: &M::LabelSet| -> Box<DynHandle> {
+                Box::new(api::Measure::<f64, M::LabelSet>::acquire_handle(
+                    measure, label_set,
+                ))
+            },
+        })
+    }
+
+    fn register_i64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+        callback: ObserverCallback<i64>,
+    ) -> Arc<dyn GenericInstrument> {
+        let observer = self.register_i64_observer(name, opts, move |result| {
+            let observed = Observed {
+                result: &result,
+                convert: api::MeasurementValue::into_i64,
+            };
+            callback(api::ObserverResult::new(&observed))
+        });
+
+        Arc::new(Erased {
+            instrument: observer,
+            acquire_handle: unbound,
+        })
+    }
+
+    fn register_f64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+        callback: ObserverCallback<f64>,
+    ) -> Arc<dyn GenericInstrument> {
+        let observer = self.register_f64_observer(name, opts, move |result| {
+            let observed = Observed {
+                result: &result,
+                convert: api::MeasurementValue::into_f64,
+            };
+            callback(api::ObserverResult::new(&observed))
+        });
+
+        Arc::new(Erased {
+            instrument: observer,
+            acquire_handle: unbound,
+        })
+    }
+
+    fn new_batch_observer_boxed(&self) -> Box<dyn GenericBatchObserver> {
+        Box::new(ErasedBatchObserver {
+            batch: self.new_batch_observer(),
+            _marker: marker::PhantomData::<fn(&M::LabelSet)>,
+        })
+    }
+}
+
+/// Allows a specific [`BatchObserver`] to be used generically by
+/// [`BoxedBatchObserver`] instances by mirroring the interface and boxing
+/// the return types.
+///
+/// [`BatchObserver`]: ../api/metrics/observer/trait.BatchObserver.html
+/// [`BoxedBatchObserver`]: struct.BoxedBatchObserver.html
+pub trait GenericBatchObserver: Send + Sync + 'static {
+    /// Creates a new `i64` observer as a trait object.
+    fn new_i64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Creates a new `f64` observer as a trait object.
+    fn new_f64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument>;
+
+    /// Registers a callback observing the boxed instruments of this batch
+    /// observer.
+    fn register_callback_boxed(&self, callback: Box<dyn Fn() + Send + Sync>);
+}
+
+/// A batch observer of the underlying meter.
+struct ErasedBatchObserver<B, LS> {
+    batch: B,
+    _marker: marker::PhantomData<fn(&LS)>,
+}
+
+impl<B, LS> GenericBatchObserver for ErasedBatchObserver<B, LS>
+where
+    B: api::BatchObserver<LS> + Send + Sync + 'static,
+    B::I64Observer: Send + Sync + 'static,
+    B::F64Observer: Send + Sync + 'static,
+    LS: api::LabelSet + Send + Sync + 'static,
+{
+    fn new_i64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.batch.new_i64_observer(name, opts),
+            acquire_handle: unbound,
+        })
+    }
+
+    fn new_f64_observer_boxed(
+        &self,
+        name: String,
+        opts: api::MetricOptions,
+    ) -> Arc<dyn GenericInstrument> {
+        Arc::new(Erased {
+            instrument: self.batch.new_f64_observer(name, opts),
+            acquire_handle: unbound,
+        })
+    }
+
+    fn register_callback_boxed(&self, callback: Box<dyn Fn() + Send + Sync>) {
+        // Boxed observations are recorded directly by the boxed instruments
+        self.batch.register_callback(move |_result| callback())
+    }
+}
+
+/// A boxed instrument of the underlying meter.
+#[derive(Clone)]
+struct BoxedInstrument(Arc<dyn GenericInstrument>);
+
+impl fmt::Debug for BoxedInstrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedInstrument").finish()
+    }
+}
+
+impl api::Instrument<BoxedLabelSet> for BoxedInstrument {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.0.record_one_boxed(value, &*label_set.0)
+    }
+}
+
+impl BoxedInstrument {
+    fn measurement<T: Into<api::MeasurementValue>>(
+        &self,
+        value: T,
+    ) -> api::Measurement<BoxedLabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+
+    fn acquire_handle<T>(&self, label_set: &BoxedLabelSet) -> BoxedHandle<T> {
+        BoxedHandle {
+            handle: Arc::from(self.0.acquire_handle_boxed(&*label_set.0)),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Wraps the handles of the instruments of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone)]
+pub struct BoxedHandle<T> {
+    handle: Arc<DynHandle>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> fmt::Debug for BoxedHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedHandle").finish()
+    }
+}
+
+impl<T> api::InstrumentHandle for BoxedHandle<T> {
+    fn record_one(&self, value: api::MeasurementValue) {
+        self.handle.record_one(value)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::CounterHandle<T> for BoxedHandle<T> {}
+
+impl<T: Into<api::MeasurementValue>> api::GaugeHandle<T> for BoxedHandle<T> {}
+
+impl<T: Into<api::MeasurementValue>> api::MeasureHandle<T> for BoxedHandle<T> {}
+
+/// Wraps a counter of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedCounter<T> {
+    instrument: BoxedInstrument,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> api::Instrument<BoxedLabelSet> for BoxedCounter<T> {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.instrument.record_one(value, label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::Counter<T, BoxedLabelSet> for BoxedCounter<T> {
+    type Handle = BoxedHandle<T>;
+
+    fn measurement(&self, value: T) -> api::Measurement<BoxedLabelSet> {
+        self.instrument.measurement(value)
+    }
+
+    fn acquire_handle(&self, labels: &BoxedLabelSet) -> Self::Handle {
+        self.instrument.acquire_handle(labels)
+    }
+}
+
+/// Wraps the handles of the up-down counters of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedUpDownCounterHandle<T>(BoxedHandle<T>);
+
+impl<T> api::InstrumentHandle for BoxedUpDownCounterHandle<T> {
+    fn record_one(&self, value: api::MeasurementValue) {
+        self.0.record_one(value)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::UpDownCounterHandle<T> for BoxedUpDownCounterHandle<T> {}
+
+/// Wraps an up-down counter of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedUpDownCounter<T> {
+    instrument: BoxedInstrument,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> api::Instrument<BoxedLabelSet> for BoxedUpDownCounter<T> {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.instrument.record_one(value, label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::UpDownCounter<T, BoxedLabelSet>
+    for BoxedUpDownCounter<T>
+{
+    type Handle = BoxedUpDownCounterHandle<T>;
+
+    fn measurement(&self, value: T) -> api::Measurement<BoxedLabelSet> {
+        self.instrument.measurement(value)
+    }
+
+    fn acquire_handle(&self, labels: &BoxedLabelSet) -> Self::Handle {
+        BoxedUpDownCounterHandle(self.instrument.acquire_handle(labels))
+    }
+}
+
+/// Wraps a gauge of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedGauge<T> {
+    instrument: BoxedInstrument,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> api::Instrument<BoxedLabelSet> for BoxedGauge<T> {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.instrument.record_one(value, label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::Gauge<T, BoxedLabelSet> for BoxedGauge<T> {
+    type Handle = BoxedHandle<T>;
+
+    fn measurement(&self, value: T) -> api::Measurement<BoxedLabelSet> {
+        self.instrument.measurement(value)
+    }
+
+    fn acquire_handle(&self, labels: &BoxedLabelSet) -> Self::Handle {
+        self.instrument.acquire_handle(labels)
+    }
+}
+
+/// Wraps a measure of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedMeasure<T> {
+    instrument: BoxedInstrument,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> api::Instrument<BoxedLabelSet> for BoxedMeasure<T> {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.instrument.record_one(value, label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::Measure<T, BoxedLabelSet> for BoxedMeasure<T> {
+    type Handle = BoxedHandle<T>;
+
+    fn measurement(&self, value: T) -> api::Measurement<BoxedLabelSet> {
+        self.instrument.measurement(value)
+    }
+
+    fn acquire_handle(&self, labels: &BoxedLabelSet) -> Self::Handle {
+        self.instrument.acquire_handle(labels)
+    }
+}
+
+/// Wraps an observer of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct BoxedObserver<T> {
+    instrument: BoxedInstrument,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+impl<T> api::Instrument<BoxedLabelSet> for BoxedObserver<T> {
+    fn record_one(&self, value: api::MeasurementValue, label_set: &BoxedLabelSet) {
+        self.instrument.record_one(value, label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::Observer<T, BoxedLabelSet> for BoxedObserver<T> {
+    fn observation(&self, value: T) -> api::Measurement<BoxedLabelSet> {
+        self.instrument.measurement(value)
+    }
+}
+
+/// Wraps a batch observer of the underlying meter of a [`BoxedMeter`].
+///
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+pub struct BoxedBatchObserver(Box<dyn GenericBatchObserver>);
+
+impl fmt::Debug for BoxedBatchObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedBatchObserver").finish()
+    }
+}
+
+impl api::BatchObserver<BoxedLabelSet> for BoxedBatchObserver {
+    type I64Observer = BoxedObserver<i64>;
+    type F64Observer = BoxedObserver<f64>;
+
+    fn new_i64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64Observer {
+        BoxedObserver {
+            instrument: BoxedInstrument(self.0.new_i64_observer_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    fn new_f64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64Observer {
+        BoxedObserver {
+            instrument: BoxedInstrument(self.0.new_f64_observer_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(api::BatchObserverResult<BoxedLabelSet>) + Send + Sync + 'static,
+    {
+        self.0
+            .register_callback_boxed(Box::new(move || callback(api::BatchObserverResult::new())))
+    }
+}
+
+/// Wraps the [`GlobalMeterProvider`]'s [`Meter`] so it can be used
+/// generically by applications without knowing the underlying type.
+///
+/// [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+/// [`Meter`]: ../api/metrics/trait.Meter.html
+#[derive(Clone, Debug)]
+pub struct BoxedMeter(Arc<dyn GenericMeter>);
+
+impl api::Meter for BoxedMeter {
+    type LabelSet = BoxedLabelSet;
+    type I64Counter = BoxedCounter<i64>;
+    type F64Counter = BoxedCounter<f64>;
+    type I64UpDownCounter = BoxedUpDownCounter<i64>;
+    type F64UpDownCounter = BoxedUpDownCounter<f64>;
+    type I64Gauge = BoxedGauge<i64>;
+    type F64Gauge = BoxedGauge<f64>;
+    type I64Measure = BoxedMeasure<i64>;
+    type F64Measure = BoxedMeasure<f64>;
+    type I64Observer = BoxedObserver<i64>;
+    type F64Observer = BoxedObserver<f64>;
+    type BatchObserver = BoxedBatchObserver;
+
+    /// Builds a label set of the underlying meter.
+    fn labels(&self, key_values: Vec<api::KeyValue>) -> Self::LabelSet {
+        BoxedLabelSet(self.0.labels_boxed(key_values))
+    }
+
+    /// Creates a new `i64` counter through the underlying meter.
+    fn new_i64_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64Counter {
+        BoxedCounter {
+            instrument: BoxedInstrument(self.0.new_i64_counter_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `f64` counter through the underlying meter.
+    fn new_f64_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64Counter {
+        BoxedCounter {
+            instrument: BoxedInstrument(self.0.new_f64_counter_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `i64` up-down counter through the underlying meter.
+    fn new_i64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64UpDownCounter {
+        BoxedUpDownCounter {
+            instrument: BoxedInstrument(self.0.new_i64_up_down_counter_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `f64` up-down counter through the underlying meter.
+    fn new_f64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64UpDownCounter {
+        BoxedUpDownCounter {
+            instrument: BoxedInstrument(self.0.new_f64_up_down_counter_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `i64` gauge through the underlying meter.
+    fn new_i64_gauge<S: Into<String>>(&self, name: S, opts: api::MetricOptions) -> Self::I64Gauge {
+        BoxedGauge {
+            instrument: BoxedInstrument(self.0.new_i64_gauge_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `f64` gauge through the underlying meter.
+    fn new_f64_gauge<S: Into<String>>(&self, name: S, opts: api::MetricOptions) -> Self::F64Gauge {
+        BoxedGauge {
+            instrument: BoxedInstrument(self.0.new_f64_gauge_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `i64` measure through the underlying meter.
+    fn new_i64_measure<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64Measure {
+        BoxedMeasure {
+            instrument: BoxedInstrument(self.0.new_i64_measure_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new `f64` measure through the underlying meter.
+    fn new_f64_measure<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64Measure {
+        BoxedMeasure {
+            instrument: BoxedInstrument(self.0.new_f64_measure_boxed(name.into(), opts)),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Registers a new `i64` observer through the underlying meter.
+    fn register_i64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Self::I64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, i64, BoxedLabelSet>) + Send + Sync + 'static,
+    {
+        BoxedObserver {
+            instrument: BoxedInstrument(self.0.register_i64_observer_boxed(
+                name.into(),
+                opts,
+                Box::new(callback),
+            )),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Registers a new `f64` observer through the underlying meter.
+    fn register_f64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Self::F64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, f64, BoxedLabelSet>) + Send + Sync + 'static,
+    {
+        BoxedObserver {
+            instrument: BoxedInstrument(self.0.register_f64_observer_boxed(
+                name.into(),
+                opts,
+                Box::new(callback),
+            )),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Creates a new batch observer through the underlying meter.
+    fn new_batch_observer(&self) -> Self::BatchObserver {
+        BoxedBatchObserver(self.0.new_batch_observer_boxed())
+    }
+
+    /// Records each measurement of the batch through the underlying meter.
+    fn record_batch<M: IntoIterator<Item = api::Measurement<Self::LabelSet>>>(
+        &self,
+        label_set: &Self::LabelSet,
+        measurements: M,
+    ) {
+        for measurement in measurements.into_iter() {
+            let instrument = measurement.instrument();
+            instrument.record_one(measurement.into_value(), label_set);
+        }
+    }
+}
+
+/// Allows a specific [`MeterProvider`] to be used generically by the
+/// [`GlobalMeterProvider`] by mirroring the interface and boxing the return
+/// types.
+///
+/// [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
+/// [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+pub trait GenericMeterProvider: fmt::Debug + 'static {
+    /// Creates a named meter instance that is a trait object through the
+    /// underlying `MeterProvider`.
+    fn versioned_meter_boxed(
+        &self,
+        name: &'static str,
+        version: Option<&'static str>,
+    ) -> Arc<dyn GenericMeter>;
+}
+
+impl<P> GenericMeterProvider for P
+where
+    P: api::MeterProvider,
+    P::Meter: GenericMeter,
+{
+    /// Return a boxed generic meter
+    fn versioned_meter_boxed(
+        &self,
+        name: &'static str,
+        version: Option<&'static str>,
+    ) -> Arc<dyn GenericMeter> {
+        Arc::new(self.versioned_meter(name, version))
+    }
+}
+
+/// Represents the globally configured [`MeterProvider`] instance for this
+/// application. This allows generic metrics through the returned
+/// [`BoxedMeter`] instances.
+///
+/// [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
+/// [`BoxedMeter`]: struct.BoxedMeter.html
+#[derive(Clone, Debug)]
+pub struct GlobalMeterProvider {
+    provider: Arc<dyn GenericMeterProvider + Send + Sync>,
+}
+
+impl GlobalMeterProvider {
+    /// Create a new GlobalMeterProvider instance from a struct that
+    /// implements `MeterProvider`.
+    fn new<P>(provider: P) -> Self
+    where
+        P: api::MeterProvider + Send + Sync,
+        P::Meter: GenericMeter,
+    {
+        GlobalMeterProvider {
+            provider: Arc::new(provider),
+        }
+    }
+}
+
+impl api::MeterProvider for GlobalMeterProvider {
+    type Meter = BoxedMeter;
+
+    /// Create a named meter using the global provider.
+    fn versioned_meter(&self, name: &'static str, version: Option<&'static str>) -> Self::Meter {
+        BoxedMeter(self.provider.versioned_meter_boxed(name, version))
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The global `MeterProvider` singleton.
+    static ref GLOBAL_METER_PROVIDER: RwLock<GlobalMeterProvider> = RwLock::new(GlobalMeterProvider::new(api::NoopMeterProvider {}));
+}
+
+/// Returns an instance of the currently configured global [`MeterProvider`]
+/// through [`GlobalMeterProvider`].
+///
+/// [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
+/// [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+pub fn meter_provider() -> GlobalMeterProvider {
+    GLOBAL_METER_PROVIDER
+        .read()
+        .expect("GLOBAL_METER_PROVIDER RwLock poisoned")
+        .clone()
+}
+
+/// Creates a named instance of [`Meter`] via the configured
+/// [`GlobalMeterProvider`].
+///
+/// This is a more convenient way of expressing
+/// `global::meter_provider().meter(name)`.
+///
+/// [`Meter`]: ../api/metrics/trait.Meter.html
+/// [`GlobalMeterProvider`]: struct.GlobalMeterProvider.html
+pub fn meter(name: &'static str) -> BoxedMeter {
+    api::MeterProvider::meter(&meter_provider(), name)
+}
+
+/// Sets the given [`MeterProvider`] instance as the current global meter
+/// provider. Meters that were created before keep using the previous
+/// provider.
+///
+/// [`MeterProvider`]: ../api/metrics/provider/trait.MeterProvider.html
+pub fn set_meter_provider<P>(new_provider: P)
+where
+    P: api::MeterProvider + Send + Sync,
+    P::Meter: GenericMeter,
+{
+    let mut global_provider = GLOBAL_METER_PROVIDER
+        .write()
+        .expect("GLOBAL_METER_PROVIDER RwLock poisoned");
+    *global_provider = GlobalMeterProvider::new(new_provider);
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::GlobalMeterProvider;
+    use crate::api::{
+        BatchObserver, Counter, CounterHandle, Key, Measure, Meter, MeterProvider, MetricOptions,
+        Observer, UpDownCounter, UpDownCounterHandle,
+    };
+    use crate::sdk;
+    use crate::sdk::metrics::{Aggregation, Number};
+
+    #[test]
+    fn records_through_the_underlying_meter() {
+        let provider = sdk::MeterProvider::default();
+        let meter = GlobalMeterProvider::new(provider.clone()).versioned_meter("lib", Some("1.0"));
+        let labels = meter.labels(vec![Key::new("queue").string("high")]);
+
+        let counter = meter.new_i64_counter("requests", MetricOptions::default());
+        counter.add(1, &labels);
+        counter.acquire_handle(&labels).add(2);
+        let queued = meter.new_i64_up_down_counter("queued", MetricOptions::default());
+        queued.add(3, &labels);
+        queued.acquire_handle(&labels).add(-1);
+        let measure = meter.new_f64_measure("latency", MetricOptions::default());
+        meter.record_batch(&labels, vec![measure.measurement(0.5)]);
+        meter.register_i64_observer("depth", MetricOptions::default(), {
+            let labels = labels.clone();
+            move |result| result.observe(4, &labels)
+        });
+        let batch = meter.new_batch_observer();
+        let ratio = batch.new_f64_observer("ratio", MetricOptions::default());
+        batch.register_callback(move |result| {
+            result.observe(&labels, vec![ratio.observation(0.25)])
+        });
+
+        // Label sets of other meter types are ignored
+        let noop_labels = GlobalMeterProvider::new(crate::api::NoopMeterProvider {})
+            .meter("noop")
+            .labels(Vec::new());
+        counter.add(10, &noop_labels);
+
+        let records = provider.accumulator().collect();
+        let find = |name: &str| {
+            records
+                .iter()
+                .find(|record| record.descriptor().name() == name)
+                .map(|record| {
+                    assert_eq!(record.descriptor().namespace(), "lib");
+                    assert_eq!(record.descriptor().version(), Some("1.0"));
+                    assert_eq!(record.labels().get("queue"), Some("high"));
+                    record.aggregation().clone()
+                })
+        };
+        assert_eq!(find("requests"), Some(Aggregation::Sum(Number::I64(3))));
+        assert_eq!(find("queued"), Some(Aggregation::Sum(Number::I64(2))));
+        match find("latency") {
+            Some(Aggregation::Histogram { count, .. }) => assert_eq!(count, 1),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        match find("depth") {
+            Some(Aggregation::LastValue { value, .. }) => assert_eq!(value, Number::I64(4)),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        match find("ratio") {
+            Some(Aggregation::LastValue { value, .. }) => assert_eq!(value, Number::F64(0.25)),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+    }
+}
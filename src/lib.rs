@@ -224,3 +224,5 @@ pub mod api;
 pub mod exporter;
 pub mod global;
 pub mod sdk;
+#[cfg(feature = "testing")]
+pub mod testing;
@@ -0,0 +1,307 @@
+//! # Accumulator
+//!
+//! The [`Accumulator`] keeps one aggregator per instrument and label set.
+//! Instruments update their aggregators as measurements are recorded, and
+//! `collect` snapshots all of them into export [`Record`]s. Aggregates are
+//! cumulative, they are never reset by collection.
+//!
+//! Observer callbacks are run at the start of each collection. Observers
+//! only export the label sets observed by the latest run of their callback.
+//!
+//! [`Accumulator`]: struct.Accumulator.html
+//! [`Record`]: ../../../exporter/metrics/struct.Record.html
+use crate::api;
+use crate::exporter::metrics::Record;
+use crate::sdk;
+use crate::sdk::metrics::{
+    Aggregator, AggregatorSelector, Descriptor, InstrumentKind, LabelSet, MetricsError, Number,
+    Selector,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Aggregates the measurements of all instruments of one or more `Meter`s.
+///
+/// Cloning an accumulator is cheap, clones share the same instruments.
+#[derive(Clone, Debug)]
+pub struct Accumulator {
+    inner: Arc<Inner>,
+}
+
+/// A callback observing one or more observer instruments.
+pub(crate) type Callback = Arc<dyn Fn() + Send + Sync>;
+
+/// A registered callback, with the observer it belongs to unless it is the
+/// callback of a batch observer.
+type Registration = (Option<Arc<InstrumentCore>>, Callback);
+
+struct Inner {
+    selector: Arc<dyn AggregatorSelector>,
+    resource: Arc<sdk::Resource>,
+    instruments: Mutex<Vec<Arc<InstrumentCore>>>,
+    callbacks: Mutex<Vec<Registration>>,
+    // Serializes collections, observers are reset and observed by each one
+    collecting: Mutex<()>,
+}
+
+impl fmt::Debug for Inner {
+    /// Formats the accumulator omitting its instruments and callbacks.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Accumulator")
+            .field("selector", &self.selector)
+            .field("resource", &self.resource)
+            .finish()
+    }
+}
+
+impl Default for Accumulator {
+    /// Create an accumulator with the default `Selector` and an empty
+    /// resource.
+    fn default() -> Self {
+        Accumulator::builder().build()
+    }
+}
+
+impl Accumulator {
+    /// Create a new accumulator builder.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// The resource describing the entity producing the metrics.
+    pub fn resource(&self) -> &Arc<sdk::Resource> {
+        &self.inner.resource
+    }
+
+    /// Add a new instrument, or return the existing instrument with the same
+    /// descriptor.
+    pub(crate) fn new_instrument(
+        &self,
+        descriptor: Descriptor,
+    ) -> Result<Arc<InstrumentCore>, MetricsError> {
+        if !descriptor.has_valid_boundaries() {
+            return Err(MetricsError::InvalidBoundaries {
+                requested: Box::new(descriptor),
+            });
+        }
+        let mut instruments = self
+            .inner
+            .instruments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(existing) = instruments.iter().find(|instrument| {
+            instrument.descriptor.namespace() == descriptor.namespace()
+                && instrument.descriptor.name() == descriptor.name()
+        }) {
+            return if *existing.descriptor == descriptor {
+                Ok(existing.clone())
+            } else {
+                Err(MetricsError::InstrumentConflict {
+                    existing: existing.descriptor.clone(),
+                    requested: Box::new(descriptor),
+                })
+            };
+        }
+
+        let instrument = self.detached_instrument(descriptor);
+        instruments.push(instrument.clone());
+
+        Ok(instrument)
+    }
+
+    /// Create an instrument that is not collected.
+    pub(crate) fn detached_instrument(&self, descriptor: Descriptor) -> Arc<InstrumentCore> {
+        Arc::new(InstrumentCore {
+            descriptor: Arc::new(descriptor),
+            selector: self.inner.selector.clone(),
+            records: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Register a callback run at the start of each collection. The callback
+    /// of an `observer` replaces its previous callback, so an observer that
+    /// is registered again is not observed twice.
+    pub(crate) fn register_callback(
+        &self,
+        observer: Option<&Arc<InstrumentCore>>,
+        callback: Callback,
+    ) {
+        let mut callbacks = self
+            .inner
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let existing = observer.and_then(|observer| {
+            callbacks
+                .iter_mut()
+                .find(|(registered, _)| match registered {
+                    Some(registered) => Arc::ptr_eq(registered, observer),
+                    None => false,
+                })
+        });
+        match existing {
+            Some((_, registered)) => *registered = callback,
+            None => callbacks.push((observer.cloned(), callback)),
+        }
+    }
+
+    /// Run the observer callbacks and snapshot the aggregates of all
+    /// instruments and label sets.
+    pub fn collect(&self) -> Vec<Record> {
+        let _collecting = self.inner.collecting.lock();
+        let instruments = self
+            .inner
+            .instruments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let callbacks = self
+            .inner
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect::<Vec<_>>();
+
+        for instrument in instruments.iter() {
+            if instrument.descriptor.instrument_kind() == InstrumentKind::Observer {
+                instrument.reset();
+            }
+        }
+        // Callbacks run without holding any lock but the collection lock, so
+        // they may create instruments or record measurements.
+        for callback in callbacks {
+            callback();
+        }
+
+        let mut records = Vec::new();
+        for instrument in instruments {
+            let aggregators = instrument
+                .records
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .map(|(labels, aggregator)| (labels.clone(), aggregator.clone()))
+                .collect::<Vec<_>>();
+            for (labels, aggregator) in aggregators {
+                if let Some(aggregation) = aggregator.aggregation() {
+                    records.push(Record::new(
+                        instrument.descriptor.clone(),
+                        labels,
+                        aggregation,
+                        self.inner.resource.clone(),
+                    ));
+                }
+            }
+        }
+
+        records
+    }
+}
+
+/// Builder for `Accumulator` configuration.
+#[derive(Debug, Default)]
+pub struct Builder {
+    selector: Option<Arc<dyn AggregatorSelector>>,
+    resource: Option<sdk::Resource>,
+}
+
+impl Builder {
+    /// The selector choosing the aggregator of each instrument.
+    pub fn with_selector<S: AggregatorSelector + 'static>(self, selector: S) -> Self {
+        Builder {
+            selector: Some(Arc::new(selector)),
+            ..self
+        }
+    }
+
+    /// The resource describing the entity producing the metrics.
+    pub fn with_resource(self, resource: sdk::Resource) -> Self {
+        Builder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Create a new accumulator from this configuration.
+    pub fn build(self) -> Accumulator {
+        Accumulator {
+            inner: Arc::new(Inner {
+                selector: self
+                    .selector
+                    .unwrap_or_else(|| Arc::new(Selector::default())),
+                resource: Arc::new(self.resource.unwrap_or_default()),
+                instruments: Mutex::new(Vec::new()),
+                callbacks: Mutex::new(Vec::new()),
+                collecting: Mutex::new(()),
+            }),
+        }
+    }
+}
+
+/// The aggregators of one instrument, by label set.
+pub(crate) struct InstrumentCore {
+    descriptor: Arc<Descriptor>,
+    selector: Arc<dyn AggregatorSelector>,
+    records: Mutex<HashMap<LabelSet, Arc<dyn Aggregator>>>,
+}
+
+impl InstrumentCore {
+    pub(crate) fn descriptor(&self) -> &Descriptor {
+        &self.descriptor
+    }
+
+    /// The aggregator for `labels`, created on first use.
+    pub(crate) fn aggregator(&self, labels: &LabelSet) -> Arc<dyn Aggregator> {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(labels.clone())
+            .or_insert_with(|| self.selector.aggregator_for(&self.descriptor))
+            .clone()
+    }
+
+    /// Forget all label sets and their aggregates.
+    fn reset(&self) {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// The number recorded for `value`. Negative values are invalid for
+    /// monotonic counters and absolute measures.
+    pub(crate) fn number(&self, value: api::MeasurementValue) -> Result<Number, MetricsError> {
+        let number = Number::from_measurement(value, self.descriptor.number_kind());
+        if number.is_negative() && self.descriptor.non_negative() {
+            return Err(MetricsError::NegativeValue {
+                instrument: self.descriptor.clone(),
+                value: number,
+            });
+        }
+
+        Ok(number)
+    }
+
+    /// Record a single measurement, unless it is invalid.
+    pub(crate) fn record(
+        &self,
+        value: api::MeasurementValue,
+        labels: &LabelSet,
+    ) -> Result<(), MetricsError> {
+        let number = self.number(value)?;
+        self.aggregator(labels).update(number);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for InstrumentCore {
+    /// Formats the instrument omitting its aggregators.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentCore")
+            .field("descriptor", &self.descriptor)
+            .finish()
+    }
+}
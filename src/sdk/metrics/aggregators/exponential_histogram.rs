@@ -0,0 +1,463 @@
+use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+use std::sync::Mutex;
+
+/// The scale of new histograms, the finest resolution.
+const MAX_SCALE: i32 = 20;
+/// The coarsest scale, at which two buckets cover all finite values, from
+/// the smallest subnormal to the largest magnitude.
+const MIN_SCALE: i32 = -11;
+
+/// Counts measurements in base-2 exponential buckets.
+///
+/// The histogram starts at the finest scale and halves its resolution each
+/// time the recorded values would need more than `max_size` buckets.
+#[derive(Debug)]
+pub struct ExponentialHistogramAggregator {
+    state: Mutex<ExponentialHistogram>,
+}
+
+impl ExponentialHistogramAggregator {
+    /// Create a new histogram of numbers of `kind`, with at most `max_size`
+    /// positive and `max_size` negative buckets.
+    pub fn new(kind: NumberKind, max_size: usize) -> Self {
+        ExponentialHistogramAggregator {
+            state: Mutex::new(ExponentialHistogram::new(kind, max_size)),
+        }
+    }
+
+    /// Add the measurements of another histogram to this one.
+    pub fn merge(&self, other: &ExponentialHistogram) {
+        if let Ok(mut state) = self.state.lock() {
+            state.merge(other);
+        }
+    }
+}
+
+impl Aggregator for ExponentialHistogramAggregator {
+    fn update(&self, number: Number) {
+        if let Ok(mut state) = self.state.lock() {
+            state.record(number);
+        }
+    }
+
+    fn aggregation(&self) -> Option<Aggregation> {
+        let state = self.state.lock().ok()?;
+        Some(Aggregation::ExponentialHistogram(state.clone()))
+    }
+}
+
+/// The distribution of measurements over base-2 exponential buckets.
+///
+/// At scale `s`, the bucket at index `i` counts the values whose magnitude
+/// is in `(base^i, base^(i + 1)]`, where `base = 2^(2^-s)`. Zero is counted
+/// separately, and values that are not finite are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExponentialHistogram {
+    max_size: usize,
+    scale: i32,
+    zero_count: u64,
+    positive: Buckets,
+    negative: Buckets,
+    sum: Number,
+    count: u64,
+    min: Number,
+    max: Number,
+}
+
+impl ExponentialHistogram {
+    /// Create a new empty histogram of numbers of `kind`, with at most
+    /// `max_size` positive and `max_size` negative buckets.
+    pub fn new(kind: NumberKind, max_size: usize) -> Self {
+        ExponentialHistogram {
+            // Two buckets hold any value at the coarsest scale
+            max_size: max_size.max(2),
+            scale: MAX_SCALE,
+            zero_count: 0,
+            positive: Buckets::default(),
+            negative: Buckets::default(),
+            sum: Number::zero(kind),
+            count: 0,
+            min: Number::zero(kind),
+            max: Number::zero(kind),
+        }
+    }
+
+    /// The scale of the buckets, see `ExponentialHistogram`.
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    /// The number of measurements equal to zero.
+    pub fn zero_count(&self) -> u64 {
+        self.zero_count
+    }
+
+    /// The buckets of positive measurements.
+    pub fn positive(&self) -> &Buckets {
+        &self.positive
+    }
+
+    /// The buckets of negative measurements, indexed by magnitude.
+    pub fn negative(&self) -> &Buckets {
+        &self.negative
+    }
+
+    /// The sum of all measurements.
+    pub fn sum(&self) -> Number {
+        self.sum
+    }
+
+    /// The number of measurements.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The smallest measurement, zero if there are none.
+    pub fn min(&self) -> Number {
+        self.min
+    }
+
+    /// The largest measurement, zero if there are none.
+    pub fn max(&self) -> Number {
+        self.max
+    }
+
+    /// The lower boundary of the magnitude of the bucket at `index`.
+    pub fn lower_boundary(&self, index: i32) -> f64 {
+        2f64.powf(f64::from(index) / 2f64.powi(self.scale))
+    }
+
+    /// Add a measurement to the histogram.
+    pub fn record(&mut self, number: Number) {
+        let value = number.to_f64();
+        if !value.is_finite() {
+            return;
+        }
+        self.add_summary(number, number, number, 1);
+        if value == 0.0 {
+            self.zero_count += 1;
+            return;
+        }
+
+        let mut index = bucket_index(value.abs(), self.scale);
+        let buckets = if value > 0.0 {
+            &self.positive
+        } else {
+            &self.negative
+        };
+        let change = scale_change(buckets.range_with(index, index), self.max_size);
+        if change > 0 {
+            self.downscale(change);
+            index >>= change;
+        }
+        if value > 0.0 {
+            self.positive.increment(index, 1);
+        } else {
+            self.negative.increment(index, 1);
+        }
+    }
+
+    /// Add the measurements of another histogram to this one. The result
+    /// has the coarsest of both scales, or a coarser one if the combined
+    /// buckets would not fit.
+    pub fn merge(&mut self, other: &ExponentialHistogram) {
+        if other.count == 0 {
+            return;
+        }
+        self.add_summary(other.min, other.max, other.sum, other.count);
+        self.zero_count += other.zero_count;
+
+        let scale = self.scale.min(other.scale);
+        let mut change = 0;
+        for (ours, theirs) in &[
+            (&self.positive, &other.positive),
+            (&self.negative, &other.negative),
+        ] {
+            let ours = ours.range_at(self.scale - scale);
+            let range = match (ours, theirs.range_at(other.scale - scale)) {
+                (Some((low, high)), Some((other_low, other_high))) => {
+                    Some((low.min(other_low), high.max(other_high)))
+                }
+                (ours, theirs) => ours.or(theirs),
+            };
+            change = change.max(scale_change(range, self.max_size));
+        }
+
+        self.downscale(self.scale - scale + change);
+        let shift = other.scale - self.scale;
+        for (index, count) in other.positive.iter() {
+            self.positive.increment(index >> shift, count);
+        }
+        for (index, count) in other.negative.iter() {
+            self.negative.increment(index >> shift, count);
+        }
+    }
+
+    /// Estimates the value at quantile `q` of the measurements, from `0.0`
+    /// for the minimum to `1.0` for the maximum, by interpolating within the
+    /// bucket of that rank. Returns `None` if the histogram is empty or `q`
+    /// is out of range.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        let (min, max) = (self.min.to_f64(), self.max.to_f64());
+        let rank = (q * self.count as f64).ceil().max(1.0);
+
+        // Buckets in increasing order of values, with their boundaries and
+        // the rank offset of their inclusive boundary: the upper one for
+        // positive magnitudes, the lower one for negative ones
+        let negative = self.negative.iter().rev().map(|(index, count)| {
+            let (lower, upper) = (self.lower_boundary(index), self.lower_boundary(index + 1));
+            (-upper, -lower, count, 1.0)
+        });
+        let zero = Some((0.0, 0.0, self.zero_count, 0.0));
+        let positive = self.positive.iter().map(|(index, count)| {
+            let (lower, upper) = (self.lower_boundary(index), self.lower_boundary(index + 1));
+            (lower, upper, count, 0.0)
+        });
+
+        let mut cumulative = 0.0;
+        for (lower, upper, count, offset) in negative.chain(zero).chain(positive) {
+            if count == 0 {
+                continue;
+            }
+            let count = count as f64;
+            if cumulative + count >= rank {
+                let value = lower + (upper - lower) * (rank - cumulative - offset) / count;
+                return Some(value.max(min).min(max));
+            }
+            cumulative += count;
+        }
+
+        Some(max)
+    }
+
+    fn add_summary(&mut self, min: Number, max: Number, sum: Number, count: u64) {
+        if self.count == 0 {
+            self.min = min;
+            self.max = max;
+        } else {
+            self.min = self.min.min(min);
+            self.max = self.max.max(max);
+        }
+        self.sum = self.sum.add(sum);
+        self.count += count;
+    }
+
+    fn downscale(&mut self, change: i32) {
+        if change > 0 {
+            self.positive = self.positive.downscaled(change);
+            self.negative = self.negative.downscaled(change);
+            self.scale -= change;
+        }
+    }
+}
+
+/// Consecutive bucket counts of an `ExponentialHistogram`, starting at an
+/// index offset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Buckets {
+    offset: i32,
+    counts: Vec<u64>,
+}
+
+impl Buckets {
+    /// The index of the first bucket.
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// The counts of the buckets, starting at `offset`.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns an iterator over the indexes and counts of the buckets.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (i32, u64)> + '_ {
+        let offset = self.offset;
+        self.counts
+            .iter()
+            .enumerate()
+            .map(move |(position, count)| (offset + position as i32, *count))
+    }
+
+    /// The first and last index of the buckets, reduced by `change` scales.
+    fn range_at(&self, change: i32) -> Option<(i32, i32)> {
+        if self.counts.is_empty() {
+            None
+        } else {
+            let last = self.offset + self.counts.len() as i32 - 1;
+            Some((self.offset >> change, last >> change))
+        }
+    }
+
+    fn range_with(&self, low: i32, high: i32) -> Option<(i32, i32)> {
+        match self.range_at(0) {
+            Some((first, last)) => Some((first.min(low), last.max(high))),
+            None => Some((low, high)),
+        }
+    }
+
+    fn increment(&mut self, index: i32, count: u64) {
+        if self.counts.is_empty() {
+            self.offset = index;
+        } else if index < self.offset {
+            let mut counts = vec![0; (self.offset - index) as usize];
+            counts.append(&mut self.counts);
+            self.counts = counts;
+            self.offset = index;
+        }
+        let position = (index - self.offset) as usize;
+        if position >= self.counts.len() {
+            self.counts.resize(position + 1, 0);
+        }
+        self.counts[position] += count;
+    }
+
+    fn downscaled(&self, change: i32) -> Buckets {
+        let mut buckets = Buckets::default();
+        for (index, count) in self.iter() {
+            buckets.increment(index >> change, count);
+        }
+
+        buckets
+    }
+}
+
+/// The index of the bucket of a positive `value` at `scale`. Exact for
+/// powers of two, which are the upper boundaries of their buckets.
+///
+/// The base-2 logarithm of finite values is within `-1074..=1024`, so the
+/// index fits in an `i32` up to `MAX_SCALE` and the cast never saturates.
+fn bucket_index(value: f64, scale: i32) -> i32 {
+    (value.log2() * 2f64.powi(scale)).ceil() as i32 - 1
+}
+
+/// How much the scale must be reduced for the buckets of `range` to fit in
+/// `max_size` buckets.
+fn scale_change(range: Option<(i32, i32)>, max_size: usize) -> i32 {
+    // The range of the smallest and largest values at `MAX_SCALE` overflows
+    // an `i32`
+    let (mut low, mut high) = match range {
+        Some((low, high)) => (i64::from(low), i64::from(high)),
+        None => return 0,
+    };
+    let mut change = 0;
+    while (high - low) as u64 >= max_size as u64 && change < MAX_SCALE - MIN_SCALE {
+        low >>= 1;
+        high >>= 1;
+        change += 1;
+    }
+
+    change
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExponentialHistogram, ExponentialHistogramAggregator};
+    use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+
+    fn recorded(max_size: usize, values: &[f64]) -> ExponentialHistogram {
+        let mut histogram = ExponentialHistogram::new(NumberKind::F64, max_size);
+        for value in values {
+            histogram.record(Number::F64(*value));
+        }
+
+        histogram
+    }
+
+    #[test]
+    fn adjusts_scale_to_max_size() {
+        let histogram = recorded(4, &[1.0, 2.0, 4.0, 0.0, -4.0, f64::NAN]);
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.sum(), Number::F64(3.0));
+        assert_eq!(histogram.zero_count(), 1);
+        assert_eq!(
+            (histogram.min(), histogram.max()),
+            (Number::F64(-4.0), Number::F64(4.0))
+        );
+
+        // 1, 2 and 4 are the upper boundaries of consecutive buckets at scale 0
+        assert_eq!(histogram.scale(), 0);
+        assert_eq!(histogram.positive().offset(), -1);
+        assert_eq!(histogram.positive().counts(), &[1, 1, 1]);
+        assert_eq!(histogram.negative().offset(), 1);
+        assert_eq!(histogram.negative().counts(), &[1]);
+
+        let histogram = recorded(2, &[1.0, 2.0, 4.0]);
+        assert_eq!(histogram.scale(), -1);
+        assert_eq!(histogram.positive().offset(), -1);
+        assert_eq!(histogram.positive().counts(), &[1, 2]);
+        assert_eq!(histogram.lower_boundary(0), 1.0);
+        assert_eq!(histogram.lower_boundary(1), 4.0);
+    }
+
+    #[test]
+    fn records_smallest_and_largest_values() {
+        let extremes = [5e-324, f64::MAX, -f64::MAX];
+        let histogram = recorded(160, &extremes);
+        assert_eq!(histogram.count(), 3);
+        assert_eq!(histogram.scale(), -4);
+        assert_eq!(histogram.positive().offset(), -68);
+        assert_eq!(histogram.positive().counts().len(), 132);
+        assert_eq!(histogram.negative().offset(), 63);
+        assert_eq!(histogram.quantile(0.0), Some(-f64::MAX));
+        assert_eq!(histogram.quantile(1.0), Some(f64::MAX));
+
+        let histogram = recorded(2, &extremes);
+        assert_eq!(histogram.scale(), -11);
+        assert_eq!(histogram.positive().offset(), -1);
+        assert_eq!(histogram.positive().counts(), &[1, 1]);
+        assert_eq!(histogram.negative().counts(), &[1]);
+    }
+
+    #[test]
+    fn estimates_quantiles() {
+        let values = (1..=1000).map(f64::from).collect::<Vec<_>>();
+        let histogram = recorded(160, &values);
+        assert!(histogram.positive().counts().len() <= 160);
+
+        assert_eq!(histogram.quantile(0.0), Some(1.0));
+        assert_eq!(histogram.quantile(1.0), Some(1000.0));
+        for q in &[0.5, 0.9, 0.99] {
+            let estimate = histogram.quantile(*q).unwrap();
+            let error = (estimate - q * 1000.0).abs() / (q * 1000.0);
+            assert!(error < 0.02, "quantile {} estimated {}", q, estimate);
+        }
+        assert_eq!(histogram.quantile(1.5), None);
+        assert_eq!(
+            ExponentialHistogram::new(NumberKind::F64, 160).quantile(0.5),
+            None
+        );
+
+        let histogram = recorded(160, &[-8.0, -2.0, 0.0, 3.0]);
+        assert_eq!(histogram.quantile(0.25), Some(-8.0));
+        assert_eq!(histogram.quantile(0.5).map(f64::signum), Some(-1.0));
+        assert_eq!(histogram.quantile(0.75), Some(0.0));
+    }
+
+    #[test]
+    fn merges_histograms() {
+        let aggregator = ExponentialHistogramAggregator::new(NumberKind::F64, 4);
+        for value in &[1.0, 2.0] {
+            aggregator.update(Number::F64(*value));
+        }
+        aggregator.merge(&recorded(4, &[-1.0, 16.0]));
+        aggregator.merge(&recorded(4, &[]));
+
+        let merged = match aggregator.aggregation() {
+            Some(Aggregation::ExponentialHistogram(histogram)) => histogram,
+            other => panic!("unexpected aggregation {:?}", other),
+        };
+        assert_eq!(merged, recorded(4, &[1.0, 2.0, -1.0, 16.0]));
+        assert_eq!(merged.scale(), -1);
+        assert_eq!(merged.positive().offset(), -1);
+        assert_eq!(merged.positive().counts(), &[1, 1, 1]);
+        assert_eq!(merged.negative().counts(), &[1]);
+        assert_eq!(
+            (merged.min(), merged.max()),
+            (Number::F64(-1.0), Number::F64(16.0))
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+use std::sync::Mutex;
+
+/// Counts measurements in buckets with fixed boundaries.
+#[derive(Debug)]
+pub struct HistogramAggregator {
+    boundaries: Vec<f64>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    counts: Vec<u64>,
+    sum: Number,
+    count: u64,
+}
+
+impl HistogramAggregator {
+    /// Create a new histogram of numbers of `kind` with the given bucket
+    /// boundaries, which must be sorted in increasing order.
+    pub fn new(kind: NumberKind, boundaries: Vec<f64>) -> Self {
+        HistogramAggregator {
+            state: Mutex::new(State {
+                counts: vec![0; boundaries.len() + 1],
+                sum: Number::zero(kind),
+                count: 0,
+            }),
+            boundaries,
+        }
+    }
+}
+
+impl Aggregator for HistogramAggregator {
+    fn update(&self, number: Number) {
+        let value = number.to_f64();
+        let bucket = self
+            .boundaries
+            .iter()
+            .position(|boundary| value <= *boundary)
+            .unwrap_or(self.boundaries.len());
+        if let Ok(mut state) = self.state.lock() {
+            state.counts[bucket] += 1;
+            state.sum = state.sum.add(number);
+            state.count += 1;
+        }
+    }
+
+    fn aggregation(&self) -> Option<Aggregation> {
+        let state = self.state.lock().ok()?;
+        Some(Aggregation::Histogram {
+            boundaries: self.boundaries.clone(),
+            counts: state.counts.clone(),
+            sum: state.sum,
+            count: state.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HistogramAggregator;
+    use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+
+    #[test]
+    fn counts_values_per_bucket() {
+        let aggregator = HistogramAggregator::new(NumberKind::F64, vec![1.0, 10.0]);
+        for value in &[0.5, 1.0, 9.5, 10.0, 100.0] {
+            aggregator.update(Number::F64(*value));
+        }
+
+        assert_eq!(
+            aggregator.aggregation(),
+            Some(Aggregation::Histogram {
+                boundaries: vec![1.0, 10.0],
+                counts: vec![2, 2, 1],
+                sum: Number::F64(121.0),
+                count: 5,
+            })
+        );
+    }
+}
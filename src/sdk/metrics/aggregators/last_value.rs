@@ -0,0 +1,48 @@
+use crate::sdk::metrics::{Aggregation, Aggregator, Number};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Keeps the most recent measurement and when it was recorded.
+#[derive(Debug, Default)]
+pub struct LastValueAggregator {
+    last: Mutex<Option<(Number, SystemTime)>>,
+}
+
+impl LastValueAggregator {
+    /// Create a new aggregator without a value.
+    pub fn new() -> Self {
+        LastValueAggregator::default()
+    }
+}
+
+impl Aggregator for LastValueAggregator {
+    fn update(&self, number: Number) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((number, SystemTime::now()));
+        }
+    }
+
+    fn aggregation(&self) -> Option<Aggregation> {
+        let last = *self.last.lock().ok()?;
+        last.map(|(value, timestamp)| Aggregation::LastValue { value, timestamp })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LastValueAggregator;
+    use crate::sdk::metrics::{Aggregation, Aggregator, Number};
+
+    #[test]
+    fn keeps_last_value() {
+        let aggregator = LastValueAggregator::new();
+        assert_eq!(aggregator.aggregation(), None);
+
+        aggregator.update(Number::F64(1.5));
+        aggregator.update(Number::F64(-2.0));
+        match aggregator.aggregation() {
+            Some(Aggregation::LastValue { value, .. }) => assert_eq!(value, Number::F64(-2.0)),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::sdk::metrics::{Aggregation, Aggregator, Number};
+use std::sync::Mutex;
+
+/// Aggregates measurements into their minimum, maximum, sum and count.
+#[derive(Debug, Default)]
+pub struct MinMaxSumCountAggregator {
+    state: Mutex<Option<State>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct State {
+    min: Number,
+    max: Number,
+    sum: Number,
+    count: u64,
+}
+
+impl MinMaxSumCountAggregator {
+    /// Create a new aggregator without measurements.
+    pub fn new() -> Self {
+        MinMaxSumCountAggregator::default()
+    }
+}
+
+impl Aggregator for MinMaxSumCountAggregator {
+    fn update(&self, number: Number) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = Some(match *state {
+                Some(state) => State {
+                    min: state.min.min(number),
+                    max: state.max.max(number),
+                    sum: state.sum.add(number),
+                    count: state.count + 1,
+                },
+                None => State {
+                    min: number,
+                    max: number,
+                    sum: number,
+                    count: 1,
+                },
+            });
+        }
+    }
+
+    fn aggregation(&self) -> Option<Aggregation> {
+        let state = (*self.state.lock().ok()?)?;
+        Some(Aggregation::MinMaxSumCount {
+            min: state.min,
+            max: state.max,
+            sum: state.sum,
+            count: state.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinMaxSumCountAggregator;
+    use crate::sdk::metrics::{Aggregation, Aggregator, Number};
+
+    #[test]
+    fn summarizes_measurements() {
+        let aggregator = MinMaxSumCountAggregator::new();
+        assert_eq!(aggregator.aggregation(), None);
+
+        for value in &[5, -1, 8] {
+            aggregator.update(Number::I64(*value));
+        }
+        assert_eq!(
+            aggregator.aggregation(),
+            Some(Aggregation::MinMaxSumCount {
+                min: Number::I64(-1),
+                max: Number::I64(8),
+                sum: Number::I64(12),
+                count: 3,
+            })
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! # Metric Aggregators
+//!
+//! An [`Aggregator`] combines the measurements of one instrument and label
+//! set into an [`Aggregation`], which is what exporters receive. The
+//! [`AggregatorSelector`] of an `Accumulator` decides which aggregator is
+//! used for each instrument.
+//!
+//! [`Aggregator`]: trait.Aggregator.html
+//! [`Aggregation`]: enum.Aggregation.html
+//! [`AggregatorSelector`]: trait.AggregatorSelector.html
+use crate::sdk::metrics::{Descriptor, InstrumentKind, Number};
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+mod exponential_histogram;
+mod histogram;
+mod last_value;
+mod min_max_sum_count;
+mod sum;
+
+pub use exponential_histogram::{Buckets, ExponentialHistogram, ExponentialHistogramAggregator};
+pub use histogram::HistogramAggregator;
+pub use last_value::LastValueAggregator;
+pub use min_max_sum_count::MinMaxSumCountAggregator;
+pub use sum::SumAggregator;
+
+/// The default histogram boundaries, the same as the default buckets of the
+/// Prometheus client libraries.
+pub const DEFAULT_BOUNDARIES: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The default maximum number of buckets of exponential histograms, for
+/// each sign.
+pub const DEFAULT_MAX_SIZE: usize = 160;
+
+/// Combines the measurements of one instrument and label set.
+///
+/// Aggregators are updated concurrently and must be cheap to update.
+pub trait Aggregator: Send + Sync + fmt::Debug {
+    /// Add a measurement to the aggregate.
+    fn update(&self, number: Number);
+
+    /// Snapshot of the aggregate, `None` if there is nothing to export yet.
+    fn aggregation(&self) -> Option<Aggregation>;
+}
+
+/// Chooses the aggregator for each instrument.
+pub trait AggregatorSelector: Send + Sync + fmt::Debug {
+    /// Create a new aggregator for the instrument described by `descriptor`.
+    fn aggregator_for(&self, descriptor: &Descriptor) -> Arc<dyn Aggregator>;
+}
+
+/// A snapshot of an aggregator, as received by exporters.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aggregation {
+    /// The sum of all measurements.
+    Sum(Number),
+    /// The most recent measurement.
+    LastValue {
+        /// The last recorded value
+        value: Number,
+        /// When the value was recorded
+        timestamp: SystemTime,
+    },
+    /// Summary statistics of all measurements.
+    MinMaxSumCount {
+        /// The smallest measurement
+        min: Number,
+        /// The largest measurement
+        max: Number,
+        /// The sum of all measurements
+        sum: Number,
+        /// The number of measurements
+        count: u64,
+    },
+    /// The distribution of all measurements over fixed buckets.
+    Histogram {
+        /// Upper bounds of the buckets, sorted in increasing order
+        boundaries: Vec<f64>,
+        /// Number of measurements per bucket. The bucket at index `i` counts
+        /// values less than or equal to `boundaries[i]` that are not counted
+        /// by a lower bucket, the last one counts all values greater than the
+        /// last boundary.
+        counts: Vec<u64>,
+        /// The sum of all measurements
+        sum: Number,
+        /// The number of measurements
+        count: u64,
+    },
+    /// The distribution of all measurements over base-2 exponential
+    /// buckets.
+    ExponentialHistogram(ExponentialHistogram),
+}
+
+/// The built in aggregator selectors.
+///
+/// Measures created with explicit boundaries, see `MetricOptions`, are
+/// aggregated as histograms over these boundaries with any selector.
+#[derive(Clone, Debug)]
+pub enum Selector {
+    /// Sums for counters and up-down counters, last values for gauges and
+    /// observers and min, max, sum and count for measures.
+    Simple,
+    /// Like `Simple`, with histograms over the given boundaries for measures.
+    Histogram(Vec<f64>),
+    /// Like `Simple`, with exponential histograms of at most the given
+    /// number of buckets for measures, see `DEFAULT_MAX_SIZE`.
+    Exponential(usize),
+}
+
+impl Default for Selector {
+    /// Histograms over `DEFAULT_BOUNDARIES` for measures.
+    fn default() -> Self {
+        Selector::Histogram(DEFAULT_BOUNDARIES.to_vec())
+    }
+}
+
+impl AggregatorSelector for Selector {
+    fn aggregator_for(&self, descriptor: &Descriptor) -> Arc<dyn Aggregator> {
+        let kind = descriptor.number_kind();
+        match (descriptor.instrument_kind(), descriptor.boundaries(), self) {
+            (InstrumentKind::Counter, _, _) | (InstrumentKind::UpDownCounter, _, _) => {
+                Arc::new(SumAggregator::new(kind))
+            }
+            (InstrumentKind::Gauge, _, _) | (InstrumentKind::Observer, _, _) => {
+                Arc::new(LastValueAggregator::new())
+            }
+            (InstrumentKind::Measure, Some(boundaries), _) => {
+                Arc::new(HistogramAggregator::new(kind, boundaries.to_vec()))
+            }
+            (InstrumentKind::Measure, None, Selector::Simple) => {
+                Arc::new(MinMaxSumCountAggregator::new())
+            }
+            (InstrumentKind::Measure, None, Selector::Histogram(boundaries)) => {
+                Arc::new(HistogramAggregator::new(kind, boundaries.clone()))
+            }
+            (InstrumentKind::Measure, None, Selector::Exponential(max_size)) => {
+                Arc::new(ExponentialHistogramAggregator::new(kind, *max_size))
+            }
+        }
+    }
+}
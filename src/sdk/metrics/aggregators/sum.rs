@@ -0,0 +1,51 @@
+use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+use std::sync::Mutex;
+
+/// Aggregates measurements into their sum.
+#[derive(Debug)]
+pub struct SumAggregator {
+    sum: Mutex<Number>,
+}
+
+impl SumAggregator {
+    /// Create a new sum of numbers of `kind`, starting at zero.
+    pub fn new(kind: NumberKind) -> Self {
+        SumAggregator {
+            sum: Mutex::new(Number::zero(kind)),
+        }
+    }
+}
+
+impl Aggregator for SumAggregator {
+    fn update(&self, number: Number) {
+        if let Ok(mut sum) = self.sum.lock() {
+            *sum = sum.add(number);
+        }
+    }
+
+    fn aggregation(&self) -> Option<Aggregation> {
+        self.sum.lock().ok().map(|sum| Aggregation::Sum(*sum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SumAggregator;
+    use crate::sdk::metrics::{Aggregation, Aggregator, Number, NumberKind};
+
+    #[test]
+    fn sums_measurements() {
+        let aggregator = SumAggregator::new(NumberKind::I64);
+        assert_eq!(
+            aggregator.aggregation(),
+            Some(Aggregation::Sum(Number::I64(0)))
+        );
+
+        aggregator.update(Number::I64(3));
+        aggregator.update(Number::I64(4));
+        assert_eq!(
+            aggregator.aggregation(),
+            Some(Aggregation::Sum(Number::I64(7)))
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! # Instrument Descriptors
+//!
+//! A [`Descriptor`] holds everything known about an instrument when it is
+//! created. Aggregators are selected by descriptor and exporters use it to
+//! name and type the exported metrics.
+//!
+//! [`Descriptor`]: struct.Descriptor.html
+use crate::api;
+use crate::sdk::metrics::NumberKind;
+
+/// The kind of an instrument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InstrumentKind {
+    /// Records additive values with `add`
+    Counter,
+    /// Records increments and decrements with `add`
+    UpDownCounter,
+    /// Records the current value with `set`
+    Gauge,
+    /// Records individual measurements with `record`
+    Measure,
+    /// Reports the current value from a callback at collection time
+    Observer,
+}
+
+/// Describes an instrument created by a `Meter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Descriptor {
+    name: String,
+    namespace: String,
+    version: Option<String>,
+    instrument_kind: InstrumentKind,
+    number_kind: NumberKind,
+    description: String,
+    unit: api::Unit,
+    keys: Vec<api::Key>,
+    alternate: bool,
+    boundaries: Option<Vec<f64>>,
+}
+
+impl Descriptor {
+    /// Create a new descriptor from the options passed to a `Meter`.
+    pub fn new<N: Into<String>, S: Into<String>>(
+        name: N,
+        namespace: S,
+        instrument_kind: InstrumentKind,
+        number_kind: NumberKind,
+        opts: api::MetricOptions,
+    ) -> Self {
+        let api::MetricOptions {
+            description,
+            unit,
+            keys,
+            alternate,
+            monotonic,
+            boundaries,
+        } = opts;
+        // `monotonic` is the opposite of `alternate` for counters
+        let alternate = match (instrument_kind, monotonic) {
+            (InstrumentKind::Counter, Some(monotonic)) => !monotonic,
+            (InstrumentKind::Gauge, Some(monotonic))
+            | (InstrumentKind::Observer, Some(monotonic)) => monotonic,
+            _ => alternate,
+        };
+
+        Descriptor {
+            name: name.into(),
+            namespace: namespace.into(),
+            version: None,
+            instrument_kind,
+            number_kind,
+            description,
+            unit,
+            keys,
+            alternate,
+            boundaries,
+        }
+    }
+
+    /// The name of the instrument.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The namespace of the instrument, the component name of its `Meter`.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The version of the instrumentation library of its `Meter`, if known.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Set the version of the instrumentation library.
+    pub(crate) fn with_version<S: Into<String>>(self, version: Option<S>) -> Self {
+        Descriptor {
+            version: version.map(Into::into),
+            ..self
+        }
+    }
+
+    /// The kind of the instrument.
+    pub fn instrument_kind(&self) -> InstrumentKind {
+        self.instrument_kind
+    }
+
+    /// The kind of numbers recorded by the instrument.
+    pub fn number_kind(&self) -> NumberKind {
+        self.number_kind
+    }
+
+    /// The description of the instrument, may be empty.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The unit of recorded values, may be empty.
+    pub fn unit(&self) -> &api::Unit {
+        &self.unit
+    }
+
+    /// The label keys recommended for the instrument.
+    pub fn keys(&self) -> &[api::Key] {
+        &self.keys
+    }
+
+    /// The alternate property of the instrument, see `MetricOptions`.
+    pub fn alternate(&self) -> bool {
+        self.alternate
+    }
+
+    /// Returns `true` for monotonic counters, and for gauges and observers
+    /// with the `alternate` option.
+    pub fn monotonic(&self) -> bool {
+        match self.instrument_kind {
+            InstrumentKind::Counter => !self.alternate,
+            InstrumentKind::Gauge | InstrumentKind::Observer => self.alternate,
+            InstrumentKind::UpDownCounter | InstrumentKind::Measure => false,
+        }
+    }
+
+    /// Returns `true` for measures without the `alternate` option, which do
+    /// not accept negative values.
+    pub fn absolute(&self) -> bool {
+        self.instrument_kind == InstrumentKind::Measure && !self.alternate
+    }
+
+    /// Returns `true` if negative values are invalid for the instrument.
+    pub(crate) fn non_negative(&self) -> bool {
+        match self.instrument_kind {
+            InstrumentKind::Counter => self.monotonic(),
+            InstrumentKind::Measure => self.absolute(),
+            _ => false,
+        }
+    }
+
+    /// The explicit histogram boundaries of the instrument, if any.
+    pub fn boundaries(&self) -> Option<&[f64]> {
+        self.boundaries.as_deref()
+    }
+
+    /// Remove the explicit histogram boundaries.
+    pub(crate) fn without_boundaries(self) -> Self {
+        Descriptor {
+            boundaries: None,
+            ..self
+        }
+    }
+
+    /// Returns `false` if the explicit boundaries are not finite or not
+    /// strictly increasing.
+    pub(crate) fn has_valid_boundaries(&self) -> bool {
+        self.boundaries.as_ref().map_or(true, |boundaries| {
+            boundaries.iter().all(|boundary| boundary.is_finite())
+                && boundaries.windows(2).all(|pair| pair[0] < pair[1])
+        })
+    }
+}
@@ -0,0 +1,59 @@
+//! # Metrics Errors
+use crate::sdk::metrics::{Descriptor, Number};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// Errors returned by the fallible methods of a `Meter` and its instruments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricsError {
+    /// An instrument with the same namespace and name, but a different
+    /// descriptor, was already created in the accumulator.
+    InstrumentConflict {
+        /// The descriptor of the existing instrument
+        existing: Arc<Descriptor>,
+        /// The descriptor of the requested instrument
+        requested: Box<Descriptor>,
+    },
+    /// The explicit histogram boundaries of the requested instrument are not
+    /// finite or not strictly increasing.
+    InvalidBoundaries {
+        /// The descriptor of the requested instrument
+        requested: Box<Descriptor>,
+    },
+    /// A negative value was recorded by a monotonic counter or an absolute
+    /// measure. The value is dropped.
+    NegativeValue {
+        /// The descriptor of the instrument
+        instrument: Arc<Descriptor>,
+        /// The recorded value
+        value: Number,
+    },
+}
+
+impl fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricsError::InstrumentConflict { existing, .. } => write!(
+                f,
+                "instrument `{}` of namespace `{}` is already registered with a different descriptor",
+                existing.name(),
+                existing.namespace()
+            ),
+            MetricsError::InvalidBoundaries { requested } => write!(
+                f,
+                "histogram boundaries {:?} of instrument `{}` are not finite and strictly increasing",
+                requested.boundaries().unwrap_or_default(),
+                requested.name()
+            ),
+            MetricsError::NegativeValue { instrument, value } => write!(
+                f,
+                "negative value {} recorded by instrument `{}` is invalid",
+                value,
+                instrument.name()
+            ),
+        }
+    }
+}
+
+impl Error for MetricsError {}
@@ -0,0 +1,469 @@
+//! # Metric Instruments
+//!
+//! The SDK implementations of the `Counter`, `UpDownCounter`, `Gauge`,
+//! `Measure` and `Observer` instruments. All of them record into the
+//! aggregators of an `Accumulator`, and differ only in the API they
+//! implement.
+//!
+//! Negative values recorded by monotonic counters and absolute measures are
+//! dropped and passed to `global::handle_error`. Their `try_add` and
+//! `try_record` methods return them to the caller instead.
+use crate::api;
+use crate::global;
+use crate::sdk::metrics::accumulator::InstrumentCore;
+use crate::sdk::metrics::{Aggregator, InstrumentKind, LabelSet, Meter, MetricsError, NumberKind};
+use std::fmt;
+use std::marker;
+use std::sync::Arc;
+
+/// An instrument bound to one label set.
+#[derive(Clone)]
+struct Bound {
+    core: Arc<InstrumentCore>,
+    aggregator: Arc<dyn Aggregator>,
+}
+
+impl Bound {
+    fn new(core: &Arc<InstrumentCore>, labels: &LabelSet) -> Self {
+        Bound {
+            core: core.clone(),
+            aggregator: core.aggregator(labels),
+        }
+    }
+
+    fn record(&self, value: api::MeasurementValue) -> Result<(), MetricsError> {
+        let number = self.core.number(value)?;
+        self.aggregator.update(number);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bound")
+            .field("descriptor", self.core.descriptor())
+            .finish()
+    }
+}
+
+/// An SDK `Counter`, aggregated as a sum by default.
+#[derive(Debug)]
+pub struct Counter<T> {
+    core: Arc<InstrumentCore>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// A `Counter` bound to a label set.
+#[derive(Clone, Debug)]
+pub struct CounterHandle<T> {
+    bound: Bound,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An SDK `UpDownCounter`, aggregated as a sum by default.
+#[derive(Debug)]
+pub struct UpDownCounter<T> {
+    core: Arc<InstrumentCore>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An `UpDownCounter` bound to a label set.
+#[derive(Clone, Debug)]
+pub struct UpDownCounterHandle<T> {
+    bound: Bound,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An SDK `Gauge`, aggregated as the last value by default.
+#[derive(Debug)]
+pub struct Gauge<T> {
+    core: Arc<InstrumentCore>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// A `Gauge` bound to a label set.
+#[derive(Clone, Debug)]
+pub struct GaugeHandle<T> {
+    bound: Bound,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An SDK `Measure`, aggregated as a histogram by default.
+#[derive(Debug)]
+pub struct Measure<T> {
+    core: Arc<InstrumentCore>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// A `Measure` bound to a label set.
+#[derive(Clone, Debug)]
+pub struct MeasureHandle<T> {
+    bound: Bound,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An SDK `Observer`, aggregated as the last observed value by default.
+#[derive(Debug)]
+pub struct Observer<T> {
+    core: Arc<InstrumentCore>,
+    _marker: marker::PhantomData<fn(T)>,
+}
+
+/// An SDK `BatchObserver`, creating observers in the accumulator of its
+/// `Meter`.
+#[derive(Clone, Debug)]
+pub struct BatchObserver {
+    meter: Meter,
+}
+
+impl<T> Counter<T> {
+    pub(crate) fn new(core: Arc<InstrumentCore>) -> Self {
+        Counter {
+            core,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> Counter<T> {
+    /// Adds the value to the sum, or returns an error if the value is
+    /// negative and the counter is monotonic.
+    pub fn try_add(&self, value: T, label_set: &LabelSet) -> Result<(), MetricsError> {
+        self.core.record(value.into(), label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> CounterHandle<T> {
+    /// Adds the value to the sum, or returns an error if the value is
+    /// negative and the counter is monotonic.
+    pub fn try_add(&self, value: T) -> Result<(), MetricsError> {
+        self.bound.record(value.into())
+    }
+}
+
+impl<T> UpDownCounter<T> {
+    pub(crate) fn new(core: Arc<InstrumentCore>) -> Self {
+        UpDownCounter {
+            core,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> Gauge<T> {
+    pub(crate) fn new(core: Arc<InstrumentCore>) -> Self {
+        Gauge {
+            core,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> Measure<T> {
+    pub(crate) fn new(core: Arc<InstrumentCore>) -> Self {
+        Measure {
+            core,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> Measure<T> {
+    /// Records the value, or returns an error if the value is negative and
+    /// the measure is absolute.
+    pub fn try_record(&self, value: T, label_set: &LabelSet) -> Result<(), MetricsError> {
+        self.core.record(value.into(), label_set)
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> MeasureHandle<T> {
+    /// Records the value, or returns an error if the value is negative and
+    /// the measure is absolute.
+    pub fn try_record(&self, value: T) -> Result<(), MetricsError> {
+        self.bound.record(value.into())
+    }
+}
+
+impl<T> Observer<T> {
+    pub(crate) fn new(core: Arc<InstrumentCore>) -> Self {
+        Observer {
+            core,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    pub(crate) fn core(&self) -> &Arc<InstrumentCore> {
+        &self.core
+    }
+}
+
+impl BatchObserver {
+    pub(crate) fn new(meter: Meter) -> Self {
+        BatchObserver { meter }
+    }
+
+    /// Creates a new `i64` observer, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_i64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Observer<i64>, MetricsError> {
+        self.meter
+            .try_new_instrument(name, InstrumentKind::Observer, NumberKind::I64, opts)
+            .map(Observer::new)
+    }
+
+    /// Creates a new `f64` observer, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_f64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Observer<f64>, MetricsError> {
+        self.meter
+            .try_new_instrument(name, InstrumentKind::Observer, NumberKind::F64, opts)
+            .map(Observer::new)
+    }
+}
+
+// Deriving `Clone` would require `T: Clone`
+impl<T> Clone for Counter<T> {
+    fn clone(&self) -> Self {
+        Counter::new(self.core.clone())
+    }
+}
+
+impl<T> Clone for UpDownCounter<T> {
+    fn clone(&self) -> Self {
+        UpDownCounter::new(self.core.clone())
+    }
+}
+
+impl<T> Clone for Gauge<T> {
+    fn clone(&self) -> Self {
+        Gauge::new(self.core.clone())
+    }
+}
+
+impl<T> Clone for Measure<T> {
+    fn clone(&self) -> Self {
+        Measure::new(self.core.clone())
+    }
+}
+
+impl<T> Clone for Observer<T> {
+    fn clone(&self) -> Self {
+        Observer::new(self.core.clone())
+    }
+}
+
+impl<T: 'static> api::Instrument<LabelSet> for Counter<T> {
+    /// Record a single counter measurement value
+    fn record_one(&self, value: api::MeasurementValue, label_set: &LabelSet) {
+        if let Err(err) = self.core.record(value, label_set) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::Counter<T, LabelSet> for Counter<T> {
+    type Handle = CounterHandle<T>;
+
+    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
+    fn measurement(&self, value: T) -> api::Measurement<LabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+
+    /// Creates a handle for this instrument.
+    fn acquire_handle(&self, labels: &LabelSet) -> Self::Handle {
+        CounterHandle {
+            bound: Bound::new(&self.core, labels),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> api::InstrumentHandle for CounterHandle<T> {
+    /// Record a single counter measurement value for precomputed labels
+    fn record_one(&self, value: api::MeasurementValue) {
+        if let Err(err) = self.bound.record(value) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::CounterHandle<T> for CounterHandle<T> {}
+
+impl<T: 'static> api::Instrument<LabelSet> for UpDownCounter<T> {
+    /// Record a single up-down counter measurement value
+    fn record_one(&self, value: api::MeasurementValue, label_set: &LabelSet) {
+        if let Err(err) = self.core.record(value, label_set) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::UpDownCounter<T, LabelSet>
+    for UpDownCounter<T>
+{
+    type Handle = UpDownCounterHandle<T>;
+
+    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
+    fn measurement(&self, value: T) -> api::Measurement<LabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+
+    /// Creates a handle for this instrument.
+    fn acquire_handle(&self, labels: &LabelSet) -> Self::Handle {
+        UpDownCounterHandle {
+            bound: Bound::new(&self.core, labels),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> api::InstrumentHandle for UpDownCounterHandle<T> {
+    /// Record a single up-down counter measurement value for precomputed labels
+    fn record_one(&self, value: api::MeasurementValue) {
+        if let Err(err) = self.bound.record(value) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::UpDownCounterHandle<T> for UpDownCounterHandle<T> {}
+
+impl<T: 'static> api::Instrument<LabelSet> for Gauge<T> {
+    /// Record a single gauge measurement value
+    fn record_one(&self, value: api::MeasurementValue, label_set: &LabelSet) {
+        if let Err(err) = self.core.record(value, label_set) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::Gauge<T, LabelSet> for Gauge<T> {
+    type Handle = GaugeHandle<T>;
+
+    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
+    fn measurement(&self, value: T) -> api::Measurement<LabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+
+    /// Creates a handle for this instrument.
+    fn acquire_handle(&self, labels: &LabelSet) -> Self::Handle {
+        GaugeHandle {
+            bound: Bound::new(&self.core, labels),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> api::InstrumentHandle for GaugeHandle<T> {
+    /// Record a single gauge measurement value for precomputed labels
+    fn record_one(&self, value: api::MeasurementValue) {
+        if let Err(err) = self.bound.record(value) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::GaugeHandle<T> for GaugeHandle<T> {}
+
+impl<T: 'static> api::Instrument<LabelSet> for Measure<T> {
+    /// Record a single measure measurement value
+    fn record_one(&self, value: api::MeasurementValue, label_set: &LabelSet) {
+        if let Err(err) = self.core.record(value, label_set) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::Measure<T, LabelSet> for Measure<T> {
+    type Handle = MeasureHandle<T>;
+
+    /// Creates a `Measurement` object to be used by a `Meter` when batch recording.
+    fn measurement(&self, value: T) -> api::Measurement<LabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+
+    /// Creates a handle for this instrument.
+    fn acquire_handle(&self, labels: &LabelSet) -> Self::Handle {
+        MeasureHandle {
+            bound: Bound::new(&self.core, labels),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<T> api::InstrumentHandle for MeasureHandle<T> {
+    /// Record a single measure measurement value for precomputed labels
+    fn record_one(&self, value: api::MeasurementValue) {
+        if let Err(err) = self.bound.record(value) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue>> api::MeasureHandle<T> for MeasureHandle<T> {}
+
+impl<T: 'static> api::Instrument<LabelSet> for Observer<T> {
+    /// Record a single observed value
+    fn record_one(&self, value: api::MeasurementValue, label_set: &LabelSet) {
+        if let Err(err) = self.core.record(value, label_set) {
+            global::handle_error(err);
+        }
+    }
+}
+
+impl<T: Into<api::MeasurementValue> + 'static> api::Observer<T, LabelSet> for Observer<T> {
+    /// Creates a `Measurement` to be reported by a `BatchObserver` callback.
+    fn observation(&self, value: T) -> api::Measurement<LabelSet> {
+        api::Measurement::new(Arc::new(self.clone()), value.into())
+    }
+}
+
+impl api::BatchObserver<LabelSet> for BatchObserver {
+    type I64Observer = Observer<i64>;
+    type F64Observer = Observer<f64>;
+
+    /// Creates a new `i64` observer observed by the batch callback.
+    fn new_i64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64Observer {
+        Observer::new(self.meter.new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::I64,
+            opts,
+        ))
+    }
+
+    /// Creates a new `f64` observer observed by the batch callback.
+    fn new_f64_observer<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64Observer {
+        Observer::new(self.meter.new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::F64,
+            opts,
+        ))
+    }
+
+    /// Registers a callback run at the start of each collection.
+    fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn(api::BatchObserverResult<LabelSet>) + Send + Sync + 'static,
+    {
+        self.meter.accumulator().register_callback(
+            None,
+            Arc::new(move || callback(api::BatchObserverResult::new())),
+        );
+    }
+}
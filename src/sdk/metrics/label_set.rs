@@ -0,0 +1,85 @@
+//! # Label Sets
+use crate::api;
+use std::sync::Arc;
+
+/// A set of labels with unique keys, sorted by key.
+///
+/// Label sets are cheap to clone and are used to look up the aggregator of
+/// an instrument for each distinct set of labels.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LabelSet {
+    labels: Arc<Vec<(String, String)>>,
+}
+
+impl LabelSet {
+    /// Create a new label set. If a key is given more than once, the last
+    /// value is used.
+    pub fn new<T: IntoIterator<Item = api::KeyValue>>(key_values: T) -> Self {
+        let mut labels = Vec::<(String, String)>::new();
+        for api::KeyValue { key, value } in key_values.into_iter() {
+            let key: String = key.into();
+            let value: String = value.into();
+            match labels.binary_search_by(|(existing, _)| existing.cmp(&key)) {
+                Ok(index) => labels[index].1 = value,
+                Err(index) => labels.insert(index, (key, value)),
+            }
+        }
+
+        LabelSet {
+            labels: Arc::new(labels),
+        }
+    }
+
+    /// Returns the value of the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.labels
+            .binary_search_by(|(existing, _)| existing.as_str().cmp(key))
+            .ok()
+            .map(|index| self.labels[index].1.as_str())
+    }
+
+    /// Returns an iterator over the keys and values, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.labels
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of labels in the set.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns `true` if the set has no labels.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl api::LabelSet for LabelSet {}
+
+#[cfg(test)]
+mod tests {
+    use super::LabelSet;
+    use crate::api::Key;
+
+    #[test]
+    fn sorts_and_deduplicates_keys() {
+        let labels = LabelSet::new(vec![
+            Key::new("b").string("1"),
+            Key::new("a").i64(2),
+            Key::new("b").string("3"),
+        ]);
+
+        assert_eq!(
+            labels.iter().collect::<Vec<_>>(),
+            vec![("a", "2"), ("b", "3")]
+        );
+        assert_eq!(labels.get("b"), Some("3"));
+        assert_eq!(labels.get("c"), None);
+        assert_eq!(
+            labels,
+            LabelSet::new(vec![Key::new("b").string("3"), Key::new("a").i64(2)])
+        );
+    }
+}
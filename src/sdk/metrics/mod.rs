@@ -9,77 +9,337 @@
 //! `Meter` struct, used for recording metrics. There are three distinct
 //! instruments in the Metrics API, commonly known as `Counter`s, `Gauge`s,
 //! and `Measure`s.
-use crate::api;
-use crate::exporter::metrics::prometheus;
-use std::borrow::Cow;
-use std::collections::HashMap;
+//!
+//! Measurements are aggregated in memory by an [`Accumulator`], using the
+//! aggregator chosen for each instrument by its [`AggregatorSelector`].
+//! Collecting the accumulator produces [`Record`]s, which are handed to an
+//! exporter, e.g. the [`prometheus`] exporter.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::api::{Counter, Key, Meter, MetricOptions};
+//! use opentelemetry::sdk;
+//!
+//! let meter = sdk::Meter::new("example");
+//! let requests = meter.new_i64_counter("requests", MetricOptions::default());
+//! requests.add(1, &meter.labels(vec![Key::new("path").string("/")]));
+//!
+//! for record in meter.accumulator().collect() {
+//!     println!("{} {:?}", record.descriptor().name(), record.aggregation());
+//! }
+//! ```
+//!
+//! [`Accumulator`]: accumulator/struct.Accumulator.html
+//! [`AggregatorSelector`]: aggregators/trait.AggregatorSelector.html
+//! [`Record`]: ../../exporter/metrics/struct.Record.html
+//! [`prometheus`]: ../../exporter/metrics/prometheus/index.html
+use crate::{api, global};
+use std::sync::Arc;
+
+pub mod accumulator;
+pub mod aggregators;
+pub mod descriptor;
+pub mod error;
+pub mod instrument;
+pub mod label_set;
+pub mod number;
+pub mod provider;
 
-/// Collection of label key and value types.
-pub type LabelSet = HashMap<Cow<'static, str>, Cow<'static, str>>;
-impl api::LabelSet for LabelSet {}
+pub use accumulator::Accumulator;
+pub use aggregators::{Aggregation, Aggregator, AggregatorSelector, Selector};
+pub use descriptor::{Descriptor, InstrumentKind};
+pub use error::MetricsError;
+pub use instrument::{
+    BatchObserver, Counter, CounterHandle, Gauge, GaugeHandle, Measure, MeasureHandle, Observer,
+    UpDownCounter, UpDownCounterHandle,
+};
+pub use label_set::LabelSet;
+pub use number::{Number, NumberKind};
+pub use provider::MeterProvider;
 
 /// `Meter` implementation to create manage metric instruments and record
 /// batch measurements
-#[allow(missing_debug_implementations)]
+///
+/// Instruments are identified by their namespace and name within an
+/// `Accumulator`. Creating an instrument with the descriptor of an existing
+/// one returns the existing instrument. Creating it with a different
+/// descriptor fails with `MetricsError::InstrumentConflict` for the `try_*`
+/// constructors. The `api::Meter` constructors report the conflict to the
+/// global error handler and return an instrument whose measurements are not
+/// exported instead.
+#[derive(Clone, Debug)]
 pub struct Meter {
-    registry: &'static prometheus::Registry,
-    component: &'static str,
+    namespace: String,
+    version: Option<&'static str>,
+    accumulator: Accumulator,
 }
 
 impl Meter {
-    /// Create a new `Meter` instance with a component name and empty registry.
-    pub fn new(component: &'static str) -> Self {
+    /// Create a new `Meter` instance with a namespace and its own default
+    /// `Accumulator`.
+    pub fn new<S: Into<String>>(namespace: S) -> Self {
+        Meter::with_accumulator(namespace, Accumulator::default())
+    }
+
+    /// Create a new `Meter` instance recording into `accumulator`, which may
+    /// be shared with other meters.
+    pub fn with_accumulator<S: Into<String>>(namespace: S, accumulator: Accumulator) -> Self {
         Meter {
-            registry: prometheus::default_registry(),
-            component,
+            namespace: namespace.into(),
+            version: None,
+            accumulator,
         }
     }
 
-    /// Build prometheus `Opts` from `name` and `description`.
-    fn build_opts(
+    /// Set the version of the instrumentation library using this `Meter`.
+    pub fn with_version(self, version: Option<&'static str>) -> Self {
+        Meter { version, ..self }
+    }
+
+    /// The namespace of the instruments created by this `Meter`.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The version of the instrumentation library using this `Meter`.
+    pub fn version(&self) -> Option<&'static str> {
+        self.version
+    }
+
+    /// The accumulator aggregating the measurements of this meter.
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+
+    /// Creates a new `i64` counter, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_i64_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Counter<i64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Counter, NumberKind::I64, opts)
+            .map(Counter::new)
+    }
+
+    /// Creates a new `f64` counter, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_f64_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Counter<f64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Counter, NumberKind::F64, opts)
+            .map(Counter::new)
+    }
+
+    /// Creates a new `i64` up-down counter, or returns the existing one with
+    /// the same descriptor.
+    pub fn try_new_i64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<UpDownCounter<i64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::UpDownCounter, NumberKind::I64, opts)
+            .map(UpDownCounter::new)
+    }
+
+    /// Creates a new `f64` up-down counter, or returns the existing one with
+    /// the same descriptor.
+    pub fn try_new_f64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<UpDownCounter<f64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::UpDownCounter, NumberKind::F64, opts)
+            .map(UpDownCounter::new)
+    }
+
+    /// Creates a new `i64` gauge, or returns the existing one with the same
+    /// descriptor.
+    pub fn try_new_i64_gauge<S: Into<String>>(
         &self,
-        mut name: String,
-        unit: api::Unit,
-        description: String,
-    ) -> prometheus::Opts {
-        if !unit.as_str().is_empty() {
-            name.push_str(&format!("_{}", unit.as_str()));
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Gauge<i64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Gauge, NumberKind::I64, opts)
+            .map(Gauge::new)
+    }
+
+    /// Creates a new `f64` gauge, or returns the existing one with the same
+    /// descriptor.
+    pub fn try_new_f64_gauge<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Gauge<f64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Gauge, NumberKind::F64, opts)
+            .map(Gauge::new)
+    }
+
+    /// Creates a new `i64` measure, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_i64_measure<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Measure<i64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Measure, NumberKind::I64, opts)
+            .map(Measure::new)
+    }
+
+    /// Creates a new `f64` measure, or returns the existing one with the
+    /// same descriptor.
+    pub fn try_new_f64_measure<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Result<Measure<f64>, MetricsError> {
+        self.try_new_instrument(name, InstrumentKind::Measure, NumberKind::F64, opts)
+            .map(Measure::new)
+    }
+
+    /// Registers a new `i64` observer. If an observer with the same
+    /// descriptor exists, `callback` replaces its callback.
+    pub fn try_register_i64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Result<Observer<i64>, MetricsError>
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, i64, LabelSet>) + Send + Sync + 'static,
+    {
+        let observer = Observer::new(self.try_new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::I64,
+            opts,
+        )?);
+        self.register_callback(observer.clone(), callback);
+
+        Ok(observer)
+    }
+
+    /// Registers a new `f64` observer. If an observer with the same
+    /// descriptor exists, `callback` replaces its callback.
+    pub fn try_register_f64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Result<Observer<f64>, MetricsError>
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, f64, LabelSet>) + Send + Sync + 'static,
+    {
+        let observer = Observer::new(self.try_new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::F64,
+            opts,
+        )?);
+        self.register_callback(observer.clone(), callback);
+
+        Ok(observer)
+    }
+
+    /// Register a new instrument in the accumulator.
+    fn try_new_instrument<S: Into<String>>(
+        &self,
+        name: S,
+        instrument_kind: InstrumentKind,
+        number_kind: NumberKind,
+        opts: api::MetricOptions,
+    ) -> Result<Arc<accumulator::InstrumentCore>, MetricsError> {
+        self.accumulator.new_instrument(
+            Descriptor::new(
+                name,
+                self.namespace.clone(),
+                instrument_kind,
+                number_kind,
+                opts,
+            )
+            .with_version(self.version),
+        )
+    }
+
+    /// Register a new instrument in the accumulator. Invalid boundaries are
+    /// replaced by the default ones, and an instrument that conflicts with an
+    /// existing one is not exported. Both errors are reported to the global
+    /// error handler.
+    fn new_instrument<S: Into<String>>(
+        &self,
+        name: S,
+        instrument_kind: InstrumentKind,
+        number_kind: NumberKind,
+        opts: api::MetricOptions,
+    ) -> Arc<accumulator::InstrumentCore> {
+        let mut result = self.try_new_instrument(name, instrument_kind, number_kind, opts);
+        if let Err(MetricsError::InvalidBoundaries { requested }) = result {
+            let without_boundaries = (*requested).clone().without_boundaries();
+            global::handle_error(MetricsError::InvalidBoundaries { requested });
+            result = self.accumulator.new_instrument(without_boundaries);
         }
-        // Prometheus cannot have empty help strings
-        let help = if !description.is_empty() {
-            description
-        } else {
-            format!("{} metric", name)
-        };
-        prometheus::Opts::new(name, help).namespace(self.component)
+
+        result.unwrap_or_else(|err| {
+            let descriptor = match &err {
+                MetricsError::InstrumentConflict { requested, .. }
+                | MetricsError::InvalidBoundaries { requested } => (**requested).clone(),
+                // Not returned when creating instruments
+                MetricsError::NegativeValue { instrument, .. } => (**instrument).clone(),
+            };
+            global::handle_error(err);
+            self.accumulator.detached_instrument(descriptor)
+        })
+    }
+
+    /// Observe `observer` with `callback` at collection time, replacing its
+    /// previous callback.
+    fn register_callback<T, F>(&self, observer: Observer<T>, callback: F)
+    where
+        T: Into<api::MeasurementValue> + 'static,
+        F: Fn(api::ObserverResult<'_, T, LabelSet>) + Send + Sync + 'static,
+    {
+        let core = observer.core().clone();
+        self.accumulator.register_callback(
+            Some(&core),
+            Arc::new(move || callback(api::ObserverResult::new(&observer))),
+        );
     }
 }
 
 impl api::Meter for Meter {
     /// The label set used by this `Meter`.
     type LabelSet = LabelSet;
-    /// This implementation of `api::Meter` produces `prometheus::IntCounterVec;` instances.
-    type I64Counter = prometheus::IntCounterVec;
-    /// This implementation of `api::Meter` produces `prometheus::CounterVec;` instances.
-    type F64Counter = prometheus::CounterVec;
-    /// This implementation of `api::Meter` produces `prometheus::IntGaugeVec;` instances.
-    type I64Gauge = prometheus::IntGaugeVec;
-    /// This implementation of `api::Meter` produces `prometheus::GaugeVec;` instances.
-    type F64Gauge = prometheus::GaugeVec;
-    /// This implementation of `api::Meter` produces `prometheus::IntMeasure;` instances.
-    type I64Measure = prometheus::IntMeasure;
-    /// This implementation of `api::Meter` produces `prometheus::HistogramVec;` instances.
-    type F64Measure = prometheus::HistogramVec;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Counter<i64>` instances.
+    type I64Counter = Counter<i64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Counter<f64>` instances.
+    type F64Counter = Counter<f64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::UpDownCounter<i64>` instances.
+    type I64UpDownCounter = UpDownCounter<i64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::UpDownCounter<f64>` instances.
+    type F64UpDownCounter = UpDownCounter<f64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Gauge<i64>` instances.
+    type I64Gauge = Gauge<i64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Gauge<f64>` instances.
+    type F64Gauge = Gauge<f64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Measure<i64>` instances.
+    type I64Measure = Measure<i64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Measure<f64>` instances.
+    type F64Measure = Measure<f64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Observer<i64>` instances.
+    type I64Observer = Observer<i64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::Observer<f64>` instances.
+    type F64Observer = Observer<f64>;
+    /// This implementation of `api::Meter` produces `sdk::metrics::BatchObserver` instances.
+    type BatchObserver = BatchObserver;
 
     /// Builds a `LabelSet` from `KeyValue`s.
     fn labels(&self, key_values: Vec<api::KeyValue>) -> Self::LabelSet {
-        let mut label_set: Self::LabelSet = Default::default();
-
-        for api::KeyValue { key, value } in key_values.into_iter() {
-            label_set.insert(Cow::Owned(key.into()), Cow::Owned(value.into()));
-        }
-
-        label_set
+        LabelSet::new(key_values)
     }
 
     /// Creates a new `i64` counter with a given name and customized with passed options.
@@ -88,18 +348,7 @@ impl api::Meter for Meter {
         name: S,
         opts: api::MetricOptions,
     ) -> Self::I64Counter {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let counter_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let counter = prometheus::IntCounterVec::new(counter_opts, &labels).unwrap();
-        self.registry.register(Box::new(counter.clone())).unwrap();
-
-        counter
+        Counter::new(self.new_instrument(name, InstrumentKind::Counter, NumberKind::I64, opts))
     }
 
     /// Creates a new `f64` counter with a given name and customized with passed options.
@@ -108,50 +357,45 @@ impl api::Meter for Meter {
         name: S,
         opts: api::MetricOptions,
     ) -> Self::F64Counter {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let counter_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let counter = prometheus::CounterVec::new(counter_opts, &labels).unwrap();
-        self.registry.register(Box::new(counter.clone())).unwrap();
+        Counter::new(self.new_instrument(name, InstrumentKind::Counter, NumberKind::F64, opts))
+    }
+
+    /// Creates a new `i64` up-down counter with a given name and customized with passed options.
+    fn new_i64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::I64UpDownCounter {
+        UpDownCounter::new(self.new_instrument(
+            name,
+            InstrumentKind::UpDownCounter,
+            NumberKind::I64,
+            opts,
+        ))
+    }
 
-        counter
+    /// Creates a new `f64` up-down counter with a given name and customized with passed options.
+    fn new_f64_up_down_counter<S: Into<String>>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+    ) -> Self::F64UpDownCounter {
+        UpDownCounter::new(self.new_instrument(
+            name,
+            InstrumentKind::UpDownCounter,
+            NumberKind::F64,
+            opts,
+        ))
     }
 
     /// Creates a new `i64` gauge with a given name and customized with passed options.
     fn new_i64_gauge<S: Into<String>>(&self, name: S, opts: api::MetricOptions) -> Self::I64Gauge {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let gauge_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let gauge = prometheus::IntGaugeVec::new(gauge_opts, &labels).unwrap();
-        self.registry.register(Box::new(gauge.clone())).unwrap();
-
-        gauge
+        Gauge::new(self.new_instrument(name, InstrumentKind::Gauge, NumberKind::I64, opts))
     }
 
     /// Creates a new `f64` gauge with a given name and customized with passed options.
     fn new_f64_gauge<S: Into<String>>(&self, name: S, opts: api::MetricOptions) -> Self::F64Gauge {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let gauge_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let gauge = prometheus::GaugeVec::new(gauge_opts, &labels).unwrap();
-        self.registry.register(Box::new(gauge.clone())).unwrap();
-
-        gauge
+        Gauge::new(self.new_instrument(name, InstrumentKind::Gauge, NumberKind::F64, opts))
     }
 
     /// Creates a new `i64` measure with a given name and customized with passed options.
@@ -160,19 +404,7 @@ impl api::Meter for Meter {
         name: S,
         opts: api::MetricOptions,
     ) -> Self::I64Measure {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let common_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let histogram_opts = prometheus::HistogramOpts::from(common_opts);
-        let histogram = prometheus::HistogramVec::new(histogram_opts, &labels).unwrap();
-        self.registry.register(Box::new(histogram.clone())).unwrap();
-
-        prometheus::IntMeasure::new(histogram)
+        Measure::new(self.new_instrument(name, InstrumentKind::Measure, NumberKind::I64, opts))
     }
 
     /// Creates a new `f64` measure with a given name and customized with passed options.
@@ -181,19 +413,57 @@ impl api::Meter for Meter {
         name: S,
         opts: api::MetricOptions,
     ) -> Self::F64Measure {
-        let api::MetricOptions {
-            description,
-            unit,
-            keys,
-            alternate: _alternative,
-        } = opts;
-        let common_opts = self.build_opts(name.into(), unit, description);
-        let labels = prometheus::convert_labels(&keys);
-        let histogram_opts = prometheus::HistogramOpts::from(common_opts);
-        let histogram = prometheus::HistogramVec::new(histogram_opts, &labels).unwrap();
-        self.registry.register(Box::new(histogram.clone())).unwrap();
+        Measure::new(self.new_instrument(name, InstrumentKind::Measure, NumberKind::F64, opts))
+    }
 
-        histogram
+    /// Registers a new `i64` observer, observed by `callback` at collection time.
+    fn register_i64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Self::I64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, i64, LabelSet>) + Send + Sync + 'static,
+    {
+        let observer = Observer::new(self.new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::I64,
+            opts,
+        ));
+        self.register_callback(observer.clone(), callback);
+
+        observer
+    }
+
+    /// Registers a new `f64` observer, observed by `callback` at collection time.
+    fn register_f64_observer<S, F>(
+        &self,
+        name: S,
+        opts: api::MetricOptions,
+        callback: F,
+    ) -> Self::F64Observer
+    where
+        S: Into<String>,
+        F: Fn(api::ObserverResult<'_, f64, LabelSet>) + Send + Sync + 'static,
+    {
+        let observer = Observer::new(self.new_instrument(
+            name,
+            InstrumentKind::Observer,
+            NumberKind::F64,
+            opts,
+        ));
+        self.register_callback(observer.clone(), callback);
+
+        observer
+    }
+
+    /// Creates a new batch observer, recording into the accumulator of this
+    /// `Meter`.
+    fn new_batch_observer(&self) -> Self::BatchObserver {
+        BatchObserver::new(self.clone())
     }
 
     /// Records a batch of measurements.
@@ -204,7 +474,321 @@ impl api::Meter for Meter {
     ) {
         for measure in measurements.into_iter() {
             let instrument = measure.instrument();
-            instrument.record_one(measure.into_value(), &label_set);
+            instrument.record_one(measure.into_value(), label_set);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::aggregators::DEFAULT_BOUNDARIES;
+    use super::{Aggregation, Meter, MetricsError, Number};
+    use crate::api::{
+        linear_boundaries, BatchObserver, Counter, CounterHandle, Gauge, Key, Measure, Meter as _,
+        MetricOptions, Observer, UpDownCounter, UpDownCounterHandle,
+    };
+    use crate::global;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn aggregates_per_instrument_and_label_set() {
+        let meter = Meter::new("test");
+        let counter = meter.new_i64_counter("requests", MetricOptions::default());
+        let gauge = meter.new_f64_gauge("temperature", MetricOptions::default());
+        let measure = meter.new_f64_measure("latency", MetricOptions::default());
+        let get = meter.labels(vec![Key::new("method").string("GET")]);
+        let post = meter.labels(vec![Key::new("method").string("POST")]);
+
+        counter.add(1, &get);
+        counter.acquire_handle(&get).add(2);
+        counter.add(5, &post);
+        gauge.set(21.5, &get);
+        meter.record_batch(
+            &post,
+            vec![measure.measurement(0.3), gauge.measurement(19.0)],
+        );
+
+        let records = meter.accumulator().collect();
+        let find = |name: &str, method: &str| {
+            records
+                .iter()
+                .find(|record| {
+                    record.descriptor().name() == name
+                        && record.labels().get("method") == Some(method)
+                })
+                .map(|record| record.aggregation().clone())
+        };
+        assert_eq!(
+            find("requests", "GET"),
+            Some(Aggregation::Sum(Number::I64(3)))
+        );
+        assert_eq!(
+            find("requests", "POST"),
+            Some(Aggregation::Sum(Number::I64(5)))
+        );
+        match find("temperature", "POST") {
+            Some(Aggregation::LastValue { value, .. }) => assert_eq!(value, Number::F64(19.0)),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        match find("latency", "POST") {
+            Some(Aggregation::Histogram { count, sum, .. }) => {
+                assert_eq!(count, 1);
+                assert_eq!(sum, Number::F64(0.3));
+            }
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        assert_eq!(find("latency", "GET"), None);
+        assert!(records
+            .iter()
+            .all(|record| record.descriptor().namespace() == "test"));
+    }
+
+    fn last_value(meter: &Meter, name: &str, queue: &str) -> Option<Number> {
+        meter
+            .accumulator()
+            .collect()
+            .into_iter()
+            .find(|record| {
+                record.descriptor().name() == name && record.labels().get("queue") == Some(queue)
+            })
+            .map(|record| match record.aggregation() {
+                Aggregation::LastValue { value, .. } => *value,
+                other => panic!("unexpected aggregation {:?}", other),
+            })
+    }
+
+    #[test]
+    fn runs_observer_callbacks_at_collection() {
+        let meter = Meter::new("test");
+        let depth = Arc::new(AtomicI64::new(3));
+        let observed = depth.clone();
+        let high = meter.labels(vec![Key::new("queue").string("high")]);
+        let low = meter.labels(vec![Key::new("queue").string("low")]);
+        meter.register_i64_observer("depth", MetricOptions::default(), move |result| {
+            let depth = observed.load(Ordering::SeqCst);
+            result.observe(depth, &high);
+            if depth > 0 {
+                result.observe(depth * 2, &low);
+            }
+        });
+
+        assert_eq!(last_value(&meter, "depth", "high"), Some(Number::I64(3)));
+        assert_eq!(last_value(&meter, "depth", "low"), Some(Number::I64(6)));
+
+        // Label sets that are no longer observed are not exported
+        depth.store(0, Ordering::SeqCst);
+        assert_eq!(last_value(&meter, "depth", "high"), Some(Number::I64(0)));
+        assert_eq!(last_value(&meter, "depth", "low"), None);
+    }
+
+    #[test]
+    fn registering_an_observer_again_replaces_its_callback() {
+        let meter = Meter::new("test");
+        let runs = Arc::new(AtomicI64::new(0));
+        for depth in 1..=2 {
+            let runs = runs.clone();
+            let labels = meter.labels(vec![Key::new("queue").string("high")]);
+            meter.register_i64_observer("depth", MetricOptions::default(), move |result| {
+                runs.fetch_add(1, Ordering::SeqCst);
+                result.observe(depth, &labels);
+            });
+        }
+
+        assert_eq!(last_value(&meter, "depth", "high"), Some(Number::I64(2)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn batch_observer_updates_several_instruments() {
+        let meter = Meter::new("test");
+        let labels = meter.labels(vec![Key::new("queue").string("high")]);
+        let batch = meter.new_batch_observer();
+        let depth = batch.new_i64_observer("depth", MetricOptions::default());
+        let ratio = batch.new_f64_observer("ratio", MetricOptions::default());
+        batch.register_callback(move |result| {
+            result.observe(&labels, vec![depth.observation(7), ratio.observation(0.5)]);
+        });
+
+        assert_eq!(last_value(&meter, "depth", "high"), Some(Number::I64(7)));
+        assert_eq!(last_value(&meter, "ratio", "high"), Some(Number::F64(0.5)));
+    }
+
+    #[test]
+    fn returns_existing_instruments_and_reports_conflicts() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let handler_reported = reported.clone();
+        let handler = move |err: &(dyn std::error::Error + 'static)| {
+            let err = err.to_string();
+            if err.contains("namespace `conflicts`") {
+                handler_reported.lock().unwrap().push(err);
+            }
+        };
+        let meter = Meter::new(String::from("conflicts"));
+        let labels = meter.labels(Vec::new());
+        let first = meter
+            .try_new_i64_counter("requests", MetricOptions::default())
+            .unwrap();
+        let second = meter
+            .try_new_i64_counter("requests", MetricOptions::default())
+            .unwrap();
+        first.add(1, &labels);
+        second.add(2, &labels);
+
+        match meter.try_new_i64_counter(
+            "requests",
+            MetricOptions::default().with_description("Requests served"),
+        ) {
+            Err(MetricsError::InstrumentConflict {
+                existing,
+                requested,
+            }) => {
+                assert_eq!(existing.description(), "");
+                assert_eq!(requested.description(), "Requests served");
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        assert!(meter
+            .try_new_f64_gauge("requests", MetricOptions::default())
+            .is_err());
+
+        // Conflicting instruments of the infallible API are reported and not
+        // exported
+        global::with_error_handler(handler, || {
+            meter
+                .new_f64_gauge("requests", MetricOptions::default())
+                .set(5.0, &labels)
+        });
+        assert_eq!(reported.lock().unwrap().len(), 1);
+
+        let records = meter.accumulator().collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].aggregation(), &Aggregation::Sum(Number::I64(3)));
+    }
+
+    #[test]
+    fn aggregates_measures_over_explicit_boundaries() {
+        let meter = Meter::new("test");
+        let labels = meter.labels(Vec::new());
+        let measure = meter
+            .try_new_f64_measure(
+                "latency",
+                MetricOptions::default().with_boundaries(linear_boundaries(1.0, 1.0, 3)),
+            )
+            .unwrap();
+        measure.record(2.5, &labels);
+
+        let invalid = [vec![1.0, f64::NAN], vec![2.0, 1.0], vec![1.0, 1.0]];
+        for boundaries in invalid.iter().cloned() {
+            let opts = MetricOptions::default().with_boundaries(boundaries);
+            match meter.try_new_f64_measure("size", opts) {
+                Err(MetricsError::InvalidBoundaries { requested }) => {
+                    assert_eq!(requested.name(), "size")
+                }
+                other => panic!("unexpected result {:?}", other.map(|_| ())),
+            }
+        }
+
+        // Invalid boundaries of the infallible API fall back to the defaults
+        meter
+            .new_f64_measure(
+                "size",
+                MetricOptions::default().with_boundaries(vec![2.0, 1.0]),
+            )
+            .record(2.5, &labels);
+
+        let records = meter.accumulator().collect();
+        let find = |name: &str| {
+            records
+                .iter()
+                .find(|record| record.descriptor().name() == name)
+                .map(|record| record.aggregation().clone())
+        };
+        match find("latency") {
+            Some(Aggregation::Histogram {
+                boundaries, counts, ..
+            }) => {
+                assert_eq!(boundaries, vec![1.0, 2.0, 3.0]);
+                assert_eq!(counts, vec![0, 0, 1, 0]);
+            }
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        match find("size") {
+            Some(Aggregation::Histogram { boundaries, .. }) => {
+                assert_eq!(boundaries, DEFAULT_BOUNDARIES.to_vec())
+            }
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_negative_values_of_monotonic_instruments() {
+        let meter = Meter::new("test");
+        let labels = meter.labels(Vec::new());
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let handler_reported = reported.clone();
+        let handler = move |err: &(dyn std::error::Error + 'static)| {
+            let err = err.to_string();
+            if err.starts_with("negative value") {
+                handler_reported.lock().unwrap().push(err);
+            }
+        };
+        let requests = meter.new_i64_counter("requests", MetricOptions::default());
+        let latency = meter.new_f64_measure("latency", MetricOptions::default());
+        requests.add(2, &labels);
+        // Negative values of the infallible API are reported and dropped
+        global::with_error_handler(handler, || {
+            requests.add(-1, &labels);
+            requests.acquire_handle(&labels).add(-1);
+            latency.record(-0.5, &labels);
+        });
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![
+                "negative value -1 recorded by instrument `requests` is invalid",
+                "negative value -1 recorded by instrument `requests` is invalid",
+                "negative value -0.5 recorded by instrument `latency` is invalid",
+            ]
+        );
+        match requests.try_add(-1, &labels) {
+            Err(MetricsError::NegativeValue { instrument, value }) => {
+                assert_eq!(instrument.name(), "requests");
+                assert_eq!(value, Number::I64(-1));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(requests.acquire_handle(&labels).try_add(-1).is_err());
+
+        assert!(latency.try_record(-0.5, &labels).is_err());
+        assert!(latency.acquire_handle(&labels).try_record(-0.5).is_err());
+
+        // Non-monotonic instruments accept negative values
+        let balance =
+            meter.new_f64_counter("balance", MetricOptions::default().with_monotonic(false));
+        balance.add(-1.5, &labels);
+        let queued = meter.new_i64_up_down_counter("queued", MetricOptions::default());
+        queued.add(3, &labels);
+        queued.acquire_handle(&labels).add(-1);
+        let offset = meter.new_i64_measure("offset", MetricOptions::default().with_absolute(false));
+        assert_eq!(offset.try_record(-4, &labels), Ok(()));
+
+        let records = meter.accumulator().collect();
+        let find = |name: &str| {
+            records
+                .iter()
+                .find(|record| record.descriptor().name() == name)
+                .map(|record| record.aggregation().clone())
+        };
+        assert_eq!(find("requests"), Some(Aggregation::Sum(Number::I64(2))));
+        match find("latency") {
+            Some(Aggregation::Histogram { count, .. }) => assert_eq!(count, 0),
+            other => panic!("unexpected aggregation {:?}", other),
+        }
+        assert_eq!(find("balance"), Some(Aggregation::Sum(Number::F64(-1.5))));
+        assert_eq!(find("queued"), Some(Aggregation::Sum(Number::I64(2))));
+        match find("offset") {
+            Some(Aggregation::Histogram { sum, .. }) => assert_eq!(sum, Number::I64(-4)),
+            other => panic!("unexpected aggregation {:?}", other),
         }
     }
 }
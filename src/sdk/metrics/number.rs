@@ -0,0 +1,135 @@
+//! # Metric Numbers
+//!
+//! Instruments record either `i64` or `f64` values. A [`Number`] keeps the
+//! kind of the instrument, so that integer sums stay exact.
+//!
+//! [`Number`]: enum.Number.html
+use crate::api;
+use std::fmt;
+
+/// The kind of numbers recorded by an instrument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NumberKind {
+    /// `i64` values
+    I64,
+    /// `f64` values
+    F64,
+}
+
+/// A value recorded by an instrument or computed by an aggregator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    /// An `i64` value
+    I64(i64),
+    /// An `f64` value
+    F64(f64),
+}
+
+impl Number {
+    /// Zero of the given kind.
+    pub fn zero(kind: NumberKind) -> Self {
+        match kind {
+            NumberKind::I64 => Number::I64(0),
+            NumberKind::F64 => Number::F64(0.0),
+        }
+    }
+
+    /// Interpret a measurement value recorded by an instrument of `kind`.
+    pub(crate) fn from_measurement(value: api::MeasurementValue, kind: NumberKind) -> Self {
+        match kind {
+            NumberKind::I64 => Number::I64(value.into_i64()),
+            NumberKind::F64 => Number::F64(value.into_f64()),
+        }
+    }
+
+    /// The kind of this number.
+    pub fn kind(&self) -> NumberKind {
+        match self {
+            Number::I64(_) => NumberKind::I64,
+            Number::F64(_) => NumberKind::F64,
+        }
+    }
+
+    /// Convert to `f64`, which may lose precision for large integers.
+    pub fn to_f64(&self) -> f64 {
+        match *self {
+            Number::I64(value) => value as f64,
+            Number::F64(value) => value,
+        }
+    }
+
+    /// Convert to `i64`, truncating floating point values.
+    pub fn to_i64(&self) -> i64 {
+        match *self {
+            Number::I64(value) => value,
+            Number::F64(value) => value as i64,
+        }
+    }
+
+    /// Returns `true` if the number is less than zero.
+    pub fn is_negative(&self) -> bool {
+        match *self {
+            Number::I64(value) => value < 0,
+            Number::F64(value) => value < 0.0,
+        }
+    }
+
+    /// Add `other` to this number, keeping the kind of this number. Integer
+    /// sums saturate instead of overflowing.
+    pub(crate) fn add(self, other: Number) -> Number {
+        match self {
+            Number::I64(value) => Number::I64(value.saturating_add(other.to_i64())),
+            Number::F64(value) => Number::F64(value + other.to_f64()),
+        }
+    }
+
+    /// The smaller of two numbers of the same kind.
+    pub(crate) fn min(self, other: Number) -> Number {
+        if other.to_f64() < self.to_f64() {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// The larger of two numbers of the same kind.
+    pub(crate) fn max(self, other: Number) -> Number {
+        if other.to_f64() > self.to_f64() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::I64(value) => value.fmt(f),
+            Number::F64(value) => value.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Number, NumberKind};
+    use crate::api::MeasurementValue;
+
+    #[test]
+    fn keeps_kind() {
+        let number = Number::from_measurement(MeasurementValue::from(-3), NumberKind::I64);
+        assert_eq!(number, Number::I64(-3));
+        assert!(number.is_negative());
+        assert_eq!(number.add(Number::F64(1.5)), Number::I64(-2));
+        assert_eq!(
+            Number::I64(i64::MAX).add(Number::I64(1)),
+            Number::I64(i64::MAX)
+        );
+
+        let number = Number::from_measurement(MeasurementValue::from(2.5), NumberKind::F64);
+        assert_eq!(number.add(Number::F64(0.25)), Number::F64(2.75));
+        assert_eq!(number.min(Number::F64(1.0)), Number::F64(1.0));
+        assert_eq!(number.max(Number::F64(1.0)), Number::F64(2.5));
+    }
+}
@@ -0,0 +1,36 @@
+//! # Meter Provider SDK
+//!
+//! All `Meter`s created by a `MeterProvider` record into the same
+//! [`Accumulator`], so a single exporter collects the instruments of every
+//! instrumentation library of the application.
+//!
+//! [`Accumulator`]: ../accumulator/struct.Accumulator.html
+use crate::api;
+use crate::sdk::metrics::{Accumulator, Meter};
+
+/// Creator of named `Meter` instances sharing one `Accumulator`.
+#[derive(Clone, Debug, Default)]
+pub struct MeterProvider {
+    accumulator: Accumulator,
+}
+
+impl MeterProvider {
+    /// Create a new provider whose meters record into `accumulator`.
+    pub fn new(accumulator: Accumulator) -> Self {
+        MeterProvider { accumulator }
+    }
+
+    /// The accumulator aggregating the measurements of all meters.
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+}
+
+impl api::MeterProvider for MeterProvider {
+    type Meter = Meter;
+
+    /// Create a new meter, using `name` as the namespace of its instruments.
+    fn versioned_meter(&self, name: &'static str, version: Option<&'static str>) -> Self::Meter {
+        Meter::with_accumulator(name, self.accumulator.clone()).with_version(version)
+    }
+}
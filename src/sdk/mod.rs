@@ -13,17 +13,18 @@ pub mod resource;
 pub mod trace;
 
 #[cfg(feature = "metrics")]
-pub use metrics::{LabelSet, Meter};
+pub use metrics::{LabelSet, Meter, MeterProvider};
 pub use resource::Resource;
 #[cfg(feature = "trace")]
 pub use trace::{
-    config::Config,
+    config::{Config, SpanLimits},
     evicted_hash_map::EvictedHashMap,
     evicted_queue::EvictedQueue,
-    id_generator::IdGenerator,
+    id_generator::{IdGenerator, Legacy64BitIdGenerator, SeededIdGenerator},
     provider::Provider,
     sampler::Sampler,
     span::Span,
     span_processor::{BatchSpanProcessor, SimpleSpanProcessor},
+    thread_span_processor::ThreadBatchSpanProcessor,
     tracer::Tracer,
 };
@@ -18,6 +18,14 @@ pub struct Config {
     pub max_attributes_per_span: u32,
     /// The max links that can be added to a `Span`.
     pub max_links_per_span: u32,
+    /// The max attributes that can be added to an `Event`.
+    pub max_attributes_per_event: u32,
+    /// The max attributes that can be added to a `Link`.
+    pub max_attributes_per_link: u32,
+    /// The max length of string and byte array attribute values, longer
+    /// values are truncated. Strings are truncated to this number of
+    /// characters. `None` means values are never truncated.
+    pub max_attribute_value_length: Option<u32>,
     /// Contains attributes representing an entity that produces telemetry.
     pub resource: Arc<sdk::Resource>,
 }
@@ -31,7 +39,185 @@ impl Default for Config {
             max_events_per_span: 128,
             max_attributes_per_span: 32,
             max_links_per_span: 32,
+            max_attributes_per_event: 128,
+            max_attributes_per_link: 128,
+            max_attribute_value_length: None,
             resource: Arc::new(sdk::Resource::default()),
         }
     }
 }
+
+impl Config {
+    /// The span limits of this config.
+    pub fn span_limits(&self) -> SpanLimits {
+        SpanLimits {
+            max_events_per_span: self.max_events_per_span,
+            max_attributes_per_span: self.max_attributes_per_span,
+            max_links_per_span: self.max_links_per_span,
+            max_attributes_per_event: self.max_attributes_per_event,
+            max_attributes_per_link: self.max_attributes_per_link,
+            max_attribute_value_length: self.max_attribute_value_length,
+        }
+    }
+
+    /// Replace the span limits of this config.
+    pub fn set_span_limits(&mut self, limits: SpanLimits) {
+        self.max_events_per_span = limits.max_events_per_span;
+        self.max_attributes_per_span = limits.max_attributes_per_span;
+        self.max_links_per_span = limits.max_links_per_span;
+        self.max_attributes_per_event = limits.max_attributes_per_event;
+        self.max_attributes_per_link = limits.max_attributes_per_link;
+        self.max_attribute_value_length = limits.max_attribute_value_length;
+    }
+}
+
+/// The limits of a `Config` applied to spans, see the `Config` fields of the
+/// same name.
+///
+/// Limits are copied from the `Config` when a span starts, so that they can
+/// be applied without locking the configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpanLimits {
+    /// The max events that can be added to a `Span`.
+    pub max_events_per_span: u32,
+    /// The max attributes that can be added to a `Span`.
+    pub max_attributes_per_span: u32,
+    /// The max links that can be added to a `Span`.
+    pub max_links_per_span: u32,
+    /// The max attributes that can be added to an `Event`.
+    pub max_attributes_per_event: u32,
+    /// The max attributes that can be added to a `Link`.
+    pub max_attributes_per_link: u32,
+    /// The max length of string and byte array attribute values.
+    pub max_attribute_value_length: Option<u32>,
+}
+
+impl Default for SpanLimits {
+    /// The limits of the default `Config`.
+    fn default() -> Self {
+        SpanLimits {
+            max_events_per_span: 128,
+            max_attributes_per_span: 32,
+            max_links_per_span: 32,
+            max_attributes_per_event: 128,
+            max_attributes_per_link: 128,
+            max_attribute_value_length: None,
+        }
+    }
+}
+
+impl SpanLimits {
+    /// Truncate the attribute value if it is too long.
+    pub(crate) fn attribute(&self, mut attribute: api::KeyValue) -> api::KeyValue {
+        if let Some(max_length) = self.max_attribute_value_length {
+            truncate_value(&mut attribute.value, max_length as usize);
+        }
+        attribute
+    }
+
+    /// Apply the per event attribute limits.
+    pub(crate) fn event(&self, mut event: api::Event) -> api::Event {
+        event.dropped_attributes_count +=
+            self.attributes(&mut event.attributes, self.max_attributes_per_event);
+        event
+    }
+
+    /// Apply the per link attribute limits.
+    pub(crate) fn link(&self, mut link: api::Link) -> api::Link {
+        link.dropped_attributes_count +=
+            self.attributes(&mut link.attributes, self.max_attributes_per_link);
+        link
+    }
+
+    /// Keep the first `max` attributes, truncating their values, and return
+    /// the number of dropped attributes.
+    fn attributes(&self, attributes: &mut Vec<api::KeyValue>, max: u32) -> u32 {
+        let dropped = attributes.len().saturating_sub(max as usize);
+        attributes.truncate(max as usize);
+        if let Some(max_length) = self.max_attribute_value_length {
+            for attribute in attributes.iter_mut() {
+                truncate_value(&mut attribute.value, max_length as usize);
+            }
+        }
+        dropped as u32
+    }
+}
+
+/// Truncate strings to `max_length` characters and byte arrays to
+/// `max_length` bytes.
+fn truncate_value(value: &mut api::Value, max_length: usize) {
+    match value {
+        api::Value::String(string) => {
+            if let Some((index, _)) = string.char_indices().nth(max_length) {
+                string.truncate(index);
+            }
+        }
+        api::Value::Bytes(bytes) => bytes.truncate(max_length),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::api::{self, Key};
+
+    #[test]
+    fn truncates_attribute_values() {
+        let limits = Config {
+            max_attribute_value_length: Some(3),
+            ..Default::default()
+        }
+        .span_limits();
+
+        assert_eq!(
+            limits.attribute(Key::new("string").string("héllo")),
+            Key::new("string").string("hél")
+        );
+        assert_eq!(
+            limits.attribute(Key::new("bytes").bytes(vec![1, 2, 3, 4])),
+            Key::new("bytes").bytes(vec![1, 2, 3])
+        );
+        assert_eq!(
+            limits.attribute(Key::new("short").string("hi")),
+            Key::new("short").string("hi")
+        );
+        assert_eq!(
+            limits.attribute(Key::new("number").i64(12345)),
+            Key::new("number").i64(12345)
+        );
+    }
+
+    #[test]
+    fn limits_event_and_link_attributes() {
+        let limits = Config {
+            max_attributes_per_event: 1,
+            max_attributes_per_link: 2,
+            ..Default::default()
+        }
+        .span_limits();
+        let attributes = vec![
+            Key::new("a").i64(1),
+            Key::new("b").i64(2),
+            Key::new("c").i64(3),
+        ];
+
+        let event = limits.event(api::Event::with_name("event".to_string()));
+        assert_eq!(event.dropped_attributes_count, 0);
+
+        let event = limits.event(api::Event::new(
+            "event".to_string(),
+            std::time::SystemTime::now(),
+            attributes.clone(),
+        ));
+        assert_eq!(event.attributes, vec![Key::new("a").i64(1)]);
+        assert_eq!(event.dropped_attributes_count, 2);
+
+        let link = limits.link(api::Link::new(
+            api::SpanContext::empty_context(),
+            attributes,
+        ));
+        assert_eq!(link.attributes().len(), 2);
+        assert_eq!(link.dropped_attributes_count(), 1);
+    }
+}
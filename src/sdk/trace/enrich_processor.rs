@@ -0,0 +1,99 @@
+//! # Enrich Span Processor
+//!
+//! The [`EnrichSpanProcessor`] calls a function with the data of every span
+//! when it starts, which can add attributes that are only known at that
+//! point, e.g. the current thread or a request id kept in a thread-local.
+//! Changes are visible to all processors receiving the span in `on_end`.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::api::Key;
+//! use opentelemetry::sdk;
+//! use opentelemetry::sdk::trace::enrich_processor::EnrichSpanProcessor;
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_span_processor(EnrichSpanProcessor::new(|span| {
+//!         if let Some(name) = std::thread::current().name() {
+//!             span.attributes.insert(Key::new("thread.name").string(name));
+//!         }
+//!     }))
+//!     .build();
+//! ```
+//!
+//! [`EnrichSpanProcessor`]: struct.EnrichSpanProcessor.html
+use crate::{api, exporter};
+use std::fmt;
+use std::sync::Arc;
+
+type Enrich = Box<dyn Fn(&mut exporter::trace::SpanData) + Send + Sync>;
+
+/// A [`SpanProcessor`] that modifies spans when they start.
+///
+/// It does not export spans, so it is used alongside an exporting processor.
+///
+/// [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+pub struct EnrichSpanProcessor {
+    enrich: Enrich,
+}
+
+impl EnrichSpanProcessor {
+    /// Create a new enrich processor calling `enrich` for every started span.
+    pub fn new<F>(enrich: F) -> Self
+    where
+        F: Fn(&mut exporter::trace::SpanData) + Send + Sync + 'static,
+    {
+        EnrichSpanProcessor {
+            enrich: Box::new(enrich),
+        }
+    }
+}
+
+impl fmt::Debug for EnrichSpanProcessor {
+    /// Formats the `EnrichSpanProcessor` omitting its function.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnrichSpanProcessor").finish()
+    }
+}
+
+impl api::SpanProcessor for EnrichSpanProcessor {
+    fn on_start(&self, span: &mut exporter::trace::SpanData) {
+        (self.enrich)(span)
+    }
+
+    fn on_end(&self, _span: Arc<exporter::trace::SpanData>) {
+        // Ignored
+    }
+
+    fn shutdown(&self) -> api::FlushResult {
+        api::FlushResult::Success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnrichSpanProcessor;
+    use crate::api::{Key, Provider, Tracer};
+    use crate::exporter::trace::in_memory::InMemorySpanExporter;
+    use crate::sdk;
+
+    #[test]
+    fn enriches_exported_span() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_span_processor(EnrichSpanProcessor::new(|span| {
+                span.attributes.insert(Key::new("build").string("1.2.3"))
+            }))
+            .build();
+        drop(provider.get_tracer("test").start("enriched", None));
+
+        let spans = exporter.get_finished_spans();
+        let attributes = spans[0]
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str().to_string(), value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(attributes, vec![("build".to_string(), "1.2.3".into())]);
+    }
+}
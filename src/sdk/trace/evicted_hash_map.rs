@@ -1,27 +1,50 @@
 //! # Evicted Map
+//!
+//! Entries are kept in a doubly linked list stored in a slab, ordered from
+//! the least to the most recently set entry, with a hash index from keys to
+//! slots. Inserting, updating, removing and evicting entries are constant
+//! time operations, and iteration order is deterministic.
 
 use crate::api;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, LinkedList};
+use std::collections::HashMap;
 
 /// A hash map with a capped number of attributes that retains the most
 /// recently set entries.
+///
+/// Iteration yields entries in the order they were last set, starting with
+/// the oldest one.
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct EvictedHashMap {
-    map: HashMap<api::Key, api::Value>,
-    evict_list: LinkedList<api::Key>,
+    index: HashMap<api::Key, usize>,
+    slots: Vec<Option<Node>>,
+    free: Vec<usize>,
+    head: Option<usize>,
+    tail: Option<usize>,
     capacity: u32,
     dropped_count: u32,
 }
 
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug)]
+struct Node {
+    key: api::Key,
+    value: api::Value,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
 impl EvictedHashMap {
     /// Create a new `EvictedHashMap` with a given capacity.
     pub fn new(capacity: u32) -> Self {
         EvictedHashMap {
-            map: HashMap::new(),
-            evict_list: Default::default(),
+            index: HashMap::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            tail: None,
             capacity,
             dropped_count: 0,
         }
@@ -30,100 +53,230 @@ impl EvictedHashMap {
     /// Inserts a key-value pair into the map.
     pub fn insert(&mut self, item: api::KeyValue) {
         // Check for existing item
-        if let Some(value) = self.map.get_mut(&item.key) {
-            *value = item.value;
-            self.move_key_to_front(item.key);
+        if let Some(&slot) = self.index.get(&item.key) {
+            self.node_mut(slot).value = item.value;
+            self.unlink(slot);
+            self.push_back(slot);
             return;
         }
 
-        // Add new item
-        self.evict_list.push_front(item.key.clone());
-        self.map.insert(item.key, item.value);
-
         // Verify size not exceeded
-        if self.evict_list.len() as u32 > self.capacity {
-            self.remove_oldest();
+        if self.index.len() as u32 >= self.capacity {
             self.dropped_count += 1;
+            match self.head {
+                Some(oldest) => {
+                    let key = self.node(oldest).key.clone();
+                    self.remove(&key);
+                }
+                // A map without capacity drops every item
+                None => return,
+            }
         }
+
+        // Add new item
+        let node = Node {
+            key: item.key.clone(),
+            value: item.value,
+            prev: None,
+            next: None,
+        };
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.slots.push(Some(node));
+                self.slots.len() - 1
+            }
+        };
+        self.index.insert(item.key, slot);
+        self.push_back(slot);
+    }
+
+    /// Returns a reference to the value of the given key.
+    pub fn get(&self, key: &api::Key) -> Option<&api::Value> {
+        self.index.get(key).map(|&slot| &self.node(slot).value)
+    }
+
+    /// Removes a key from the map, returning its value if it was present.
+    pub fn remove(&mut self, key: &api::Key) -> Option<api::Value> {
+        let slot = self.index.remove(key)?;
+        self.unlink(slot);
+        self.free.push(slot);
+
+        self.slots[slot].take().map(|node| node.value)
     }
 
     /// Returns the number of elements in the map.
     pub fn len(&self) -> usize {
-        self.map.len()
+        self.index.len()
     }
 
     /// Returns `true` if the map is empty.
     pub fn is_empty(&self) -> bool {
-        self.map.is_empty()
-    }
-
-    /// Returns a front-to-back iterator.
-    pub fn iter(&self) -> std::collections::hash_map::Iter<api::Key, api::Value> {
-        self.map.iter()
-    }
-
-    fn move_key_to_front(&mut self, key: api::Key) {
-        if self.evict_list.is_empty() {
-            // If empty, push front
-            self.evict_list.push_front(key);
-        } else if self.evict_list.front() == Some(&key) {
-            // Already the front, ignore
-        } else {
-            // Else split linked lists around key and combine
-            let key_idx = self
-                .evict_list
-                .iter()
-                .position(|k| k == &key)
-                .expect("key must exist in evicted hash map, this is a bug");
-            let mut tail = self.evict_list.split_off(key_idx);
-            let item = tail.pop_front().unwrap();
-            self.evict_list.push_front(item);
-            self.evict_list.append(&mut tail);
+        self.index.is_empty()
+    }
+
+    /// Returns the number of entries evicted because the map was full.
+    pub fn dropped_count(&self) -> u32 {
+        self.dropped_count
+    }
+
+    /// Returns an iterator from the least to the most recently set entry.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            map: self,
+            next: self.head,
+            remaining: self.len(),
         }
     }
 
-    fn remove_oldest(&mut self) {
-        if let Some(oldest_item) = self.evict_list.pop_back() {
-            self.map.remove(&oldest_item);
+    fn node(&self, slot: usize) -> &Node {
+        self.slots[slot]
+            .as_ref()
+            .expect("slot must be occupied in evicted hash map, this is a bug")
+    }
+
+    fn node_mut(&mut self, slot: usize) -> &mut Node {
+        self.slots[slot]
+            .as_mut()
+            .expect("slot must be occupied in evicted hash map, this is a bug")
+    }
+
+    /// Detach the node in `slot` from the list.
+    fn unlink(&mut self, slot: usize) {
+        let (prev, next) = {
+            let node = self.node_mut(slot);
+            (node.prev.take(), node.next.take())
+        };
+        match prev {
+            Some(prev) => self.node_mut(prev).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    /// Attach the detached node in `slot` as the most recent entry.
+    fn push_back(&mut self, slot: usize) {
+        let tail = self.tail;
+        self.node_mut(slot).prev = tail;
+        match tail {
+            Some(tail) => self.node_mut(tail).next = Some(slot),
+            None => self.head = Some(slot),
         }
+        self.tail = Some(slot);
     }
+
+    /// Slots of all entries in iteration order.
+    fn ordered_slots(&self) -> Vec<usize> {
+        let mut slots = Vec::with_capacity(self.len());
+        let mut next = self.head;
+        while let Some(slot) = next {
+            slots.push(slot);
+            next = self.node(slot).next;
+        }
+        slots
+    }
+}
+
+impl PartialEq for EvictedHashMap {
+    /// Maps are equal if they have the same entries in the same order, the
+    /// same capacity and the same dropped count.
+    fn eq(&self, other: &Self) -> bool {
+        self.capacity == other.capacity
+            && self.dropped_count == other.dropped_count
+            && self.len() == other.len()
+            && self.iter().eq(other.iter())
+    }
+}
+
+/// An iterator over the entries of an `EvictedHashMap`, from the least to the
+/// most recently set entry.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    map: &'a EvictedHashMap,
+    next: Option<usize>,
+    remaining: usize,
 }
 
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a api::Key, &'a api::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.map.node(self.next?);
+        self.next = node.next;
+        self.remaining -= 1;
+
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
 impl IntoIterator for EvictedHashMap {
     type Item = (api::Key, api::Value);
-    type IntoIter = std::collections::hash_map::IntoIter<api::Key, api::Value>;
+    type IntoIter = std::vec::IntoIter<(api::Key, api::Value)>;
 
     /// Creates a consuming iterator, that is, one that moves each key-value
-    /// pair out of the map in arbitrary order. The map cannot be used after
-    /// calling this.
-    fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+    /// pair out of the map from the least to the most recently set entry. The
+    /// map cannot be used after calling this.
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.ordered_slots()
+            .into_iter()
+            .filter_map(|slot| self.slots[slot].take())
+            .map(|node| (node.key, node.value))
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
 impl<'a> IntoIterator for &'a EvictedHashMap {
     type Item = (&'a api::Key, &'a api::Value);
-    type IntoIter = std::collections::hash_map::Iter<'a, api::Key, api::Value>;
+    type IntoIter = Iter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter()
+        self.iter()
     }
 }
 
 impl<'a> IntoIterator for &'a mut EvictedHashMap {
     type Item = (&'a api::Key, &'a mut api::Value);
-    type IntoIter = std::collections::hash_map::IterMut<'a, api::Key, api::Value>;
+    type IntoIter = std::vec::IntoIter<(&'a api::Key, &'a mut api::Value)>;
 
+    /// Creates an iterator over mutable values, from the least to the most
+    /// recently set entry.
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter_mut()
+        let order = self.ordered_slots();
+        let mut nodes = self
+            .slots
+            .iter_mut()
+            .map(|slot| slot.as_mut())
+            .collect::<Vec<_>>();
+        order
+            .into_iter()
+            .filter_map(|slot| nodes[slot].take())
+            .map(|node| (&node.key, &mut node.value))
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::EvictedHashMap;
-    use crate::api::Key;
-    use std::collections::HashSet;
+    use crate::api::{Key, Value};
+
+    fn keys(map: &EvictedHashMap) -> Vec<&str> {
+        map.iter().map(|(key, _)| key.as_str()).collect()
+    }
 
     #[test]
     fn insert_over_capacity_test() {
@@ -134,13 +287,79 @@ mod tests {
             map.insert(Key::new(i.to_string()).bool(true))
         }
 
-        assert_eq!(map.dropped_count, 1);
+        assert_eq!(map.dropped_count(), 1);
         assert_eq!(map.len(), capacity as usize);
         assert_eq!(
-            map.map.keys().cloned().collect::<HashSet<_>>(),
+            map.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(),
             (1..=capacity)
                 .map(|i| Key::new(i.to_string()))
-                .collect::<HashSet<_>>()
+                .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn update_moves_entry_to_back() {
+        let mut map = EvictedHashMap::new(3);
+        map.insert(Key::new("a").i64(1));
+        map.insert(Key::new("b").i64(2));
+        map.insert(Key::new("c").i64(3));
+        map.insert(Key::new("a").i64(4));
+        assert_eq!(keys(&map), vec!["b", "c", "a"]);
+
+        // The least recently set entry is evicted
+        map.insert(Key::new("d").i64(5));
+        assert_eq!(keys(&map), vec!["c", "a", "d"]);
+        assert_eq!(map.get(&Key::new("a")), Some(&Value::I64(4)));
+        assert_eq!(map.dropped_count(), 1);
+    }
+
+    #[test]
+    fn remove_and_reuse_slots() {
+        let mut map = EvictedHashMap::new(3);
+        map.insert(Key::new("a").i64(1));
+        map.insert(Key::new("b").i64(2));
+        map.insert(Key::new("c").i64(3));
+        assert_eq!(map.remove(&Key::new("b")), Some(Value::I64(2)));
+        assert_eq!(map.remove(&Key::new("b")), None);
+        map.insert(Key::new("d").i64(4));
+        assert_eq!(keys(&map), vec!["a", "c", "d"]);
+        assert_eq!(map.dropped_count(), 0);
+
+        for (_, value) in &mut map {
+            *value = Value::I64(0);
+        }
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![
+                (Key::new("a"), Value::I64(0)),
+                (Key::new("c"), Value::I64(0)),
+                (Key::new("d"), Value::I64(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_capacity_drops_everything() {
+        let mut map = EvictedHashMap::new(0);
+        map.insert(Key::new("a").i64(1));
+        assert!(map.is_empty());
+        assert_eq!(map.dropped_count(), 1);
+    }
+
+    #[test]
+    fn equality_ignores_slot_layout() {
+        let mut left = EvictedHashMap::new(3);
+        left.insert(Key::new("x").i64(0));
+        left.insert(Key::new("a").i64(1));
+        left.remove(&Key::new("x"));
+        left.insert(Key::new("b").i64(2));
+
+        let mut right = EvictedHashMap::new(3);
+        right.insert(Key::new("a").i64(1));
+        right.insert(Key::new("b").i64(2));
+        assert_eq!(left, right);
+
+        right.insert(Key::new("a").i64(1));
+        assert_ne!(left, right);
+    }
 }
@@ -0,0 +1,128 @@
+//! # Filter Span Processor
+//!
+//! The [`FilterSpanProcessor`] wraps another [`SpanProcessor`] and drops
+//! finished spans matching a predicate before they reach it. This is useful
+//! to avoid exporting noisy spans like health checks.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::{api, sdk};
+//! use opentelemetry::sdk::trace::filter_processor::FilterSpanProcessor;
+//!
+//! let exporter = api::NoopSpanExporter {};
+//! let processor = FilterSpanProcessor::new(
+//!     sdk::SimpleSpanProcessor::new(Box::new(exporter)),
+//!     |span| span.name == "GET /healthz",
+//! );
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_span_processor(processor)
+//!     .build();
+//! ```
+//!
+//! [`FilterSpanProcessor`]: struct.FilterSpanProcessor.html
+//! [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+use crate::{api, exporter};
+use std::fmt;
+use std::sync::Arc;
+
+type Predicate = Box<dyn Fn(&exporter::trace::SpanData) -> bool + Send + Sync>;
+
+/// A [`SpanProcessor`] that drops spans for which a predicate returns `true`
+/// and forwards all other spans to an inner processor.
+///
+/// The predicate is evaluated when the span ends, so it can match on the
+/// span's name, kind, attributes, status or resource.
+///
+/// [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+pub struct FilterSpanProcessor<P> {
+    inner: P,
+    predicate: Predicate,
+}
+
+impl<P: api::SpanProcessor> FilterSpanProcessor<P> {
+    /// Create a new filter processor. Spans for which `predicate` returns
+    /// `true` are dropped, all other spans are passed to `inner`.
+    pub fn new<F>(inner: P, predicate: F) -> Self
+    where
+        F: Fn(&exporter::trace::SpanData) -> bool + Send + Sync + 'static,
+    {
+        FilterSpanProcessor {
+            inner,
+            predicate: Box::new(predicate),
+        }
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for FilterSpanProcessor<P> {
+    /// Formats the `FilterSpanProcessor` omitting its predicate.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterSpanProcessor")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<P: api::SpanProcessor> api::SpanProcessor for FilterSpanProcessor<P> {
+    fn on_start(&self, span: &mut exporter::trace::SpanData) {
+        self.inner.on_start(span)
+    }
+
+    fn on_end(&self, span: Arc<exporter::trace::SpanData>) {
+        if !(self.predicate)(&span) {
+            self.inner.on_end(span)
+        }
+    }
+
+    fn force_flush(&self) -> api::FlushResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown(&self) -> api::FlushResult {
+        self.inner.shutdown()
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterSpanProcessor;
+    use crate::api::{Provider, Span, Tracer};
+    use crate::exporter::trace::in_memory::InMemorySpanExporter;
+    use crate::{api, sdk};
+
+    #[test]
+    fn drops_matching_spans() {
+        let exporter = InMemorySpanExporter::default();
+        let collect = sdk::SimpleSpanProcessor::new(Box::new(exporter.clone()));
+        let provider = sdk::Provider::builder()
+            .with_span_processor(FilterSpanProcessor::new(collect, |span| {
+                span.name == "GET /healthz"
+                    || span.span_kind == api::SpanKind::Internal
+                        && span
+                            .attributes
+                            .iter()
+                            .any(|(key, _)| key.as_str() == "noisy")
+            }))
+            .build();
+        let tracer = provider.get_tracer("test");
+
+        tracer.start("GET /healthz", None).end();
+        tracer.start("GET /users", None).end();
+        let noisy = tracer.start("poll", None);
+        noisy.set_attribute(api::Key::new("noisy").bool(true));
+        noisy.end();
+        drop(noisy);
+
+        let names = exporter
+            .get_finished_spans()
+            .iter()
+            .map(|span| span.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["GET /users".to_string()]);
+    }
+}
@@ -1,7 +1,33 @@
 //! Id Generator
+//!
+//! The default [`IdGenerator`] draws ids from a thread local random number
+//! generator. [`SeededIdGenerator`] produces the same sequence of ids for the
+//! same seed on a single thread, which makes snapshot tests and replayed load
+//! tests reproducible, and [`Legacy64BitIdGenerator`] limits trace ids to 64 bits
+//! for systems that cannot propagate 128-bit trace ids.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::sdk;
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_config(sdk::Config {
+//!         id_generator: Box::new(sdk::Legacy64BitIdGenerator::new(
+//!             sdk::SeededIdGenerator::new(42),
+//!         )),
+//!         ..Default::default()
+//!     })
+//!     .build();
+//! ```
+//!
+//! [`IdGenerator`]: struct.IdGenerator.html
+//! [`SeededIdGenerator`]: struct.SeededIdGenerator.html
+//! [`Legacy64BitIdGenerator`]: struct.Legacy64BitIdGenerator.html
 use crate::api;
 use rand::{rngs, Rng};
 use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Generates Trace and Span ids
 #[derive(Clone, Debug, Default)]
@@ -25,3 +51,128 @@ thread_local! {
     /// Store random number generator for each thread
     static CURRENT_RNG: RefCell<rngs::ThreadRng> = RefCell::new(rngs::ThreadRng::default());
 }
+
+/// Generates Trace and Span ids from a seed.
+///
+/// Ids are drawn from a [SplitMix64] sequence shared by all threads, so ids
+/// generated on a single thread are the same on every run. When multiple
+/// threads generate ids, the draws interleave depending on scheduling and the
+/// generated ids differ between runs. The ids are not suitable where they
+/// must be unpredictable.
+///
+/// [SplitMix64]: http://prng.di.unimi.it/splitmix64.c
+#[derive(Debug)]
+pub struct SeededIdGenerator {
+    state: AtomicU64,
+}
+
+impl SeededIdGenerator {
+    /// Create a new generator producing the sequence of ids for `seed`.
+    pub fn new(seed: u64) -> Self {
+        SeededIdGenerator {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Next non-zero value of the sequence, zero is an invalid id.
+    fn next_u64(&self) -> u64 {
+        loop {
+            let mut z = self
+                .state
+                .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+                .wrapping_add(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+            if z != 0 {
+                return z;
+            }
+        }
+    }
+}
+
+impl api::IdGenerator for SeededIdGenerator {
+    /// Generate new `TraceId` from the next two values of the sequence
+    fn new_trace_id(&self) -> api::TraceId {
+        let high = u128::from(self.next_u64());
+        api::TraceId::from_u128(high << 64 | u128::from(self.next_u64()))
+    }
+
+    /// Generate new `SpanId` from the next value of the sequence
+    fn new_span_id(&self) -> api::SpanId {
+        api::SpanId::from_u64(self.next_u64())
+    }
+}
+
+/// Wraps an `IdGenerator` to only produce trace ids that fit in 64 bits.
+///
+/// The upper 64 bits of every trace id are zero, for interoperability with
+/// systems that only support 64-bit trace ids. Span ids are unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct Legacy64BitIdGenerator<G> {
+    inner: G,
+}
+
+impl<G: api::IdGenerator> Legacy64BitIdGenerator<G> {
+    /// Create a new generator truncating the trace ids of `inner`.
+    pub fn new(inner: G) -> Self {
+        Legacy64BitIdGenerator { inner }
+    }
+}
+
+impl<G: api::IdGenerator> api::IdGenerator for Legacy64BitIdGenerator<G> {
+    /// Generate new `TraceId` from the lower 64 bits of the inner generator's
+    /// trace ids, skipping ids that would be invalid
+    fn new_trace_id(&self) -> api::TraceId {
+        loop {
+            let trace_id = self.inner.new_trace_id().to_u128() & u128::from(u64::MAX);
+            if trace_id != 0 {
+                return api::TraceId::from_u128(trace_id);
+            }
+        }
+    }
+
+    /// Generate new `SpanId` using the inner generator
+    fn new_span_id(&self) -> api::SpanId {
+        self.inner.new_span_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdGenerator, Legacy64BitIdGenerator, SeededIdGenerator};
+    use crate::api::IdGenerator as _;
+
+    #[test]
+    fn seeded_generator_is_reproducible() {
+        let ids = |seed| {
+            let generator = SeededIdGenerator::new(seed);
+            (0..10)
+                .map(|_| {
+                    (
+                        generator.new_trace_id().to_u128(),
+                        generator.new_span_id().to_u64(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(42), ids(42));
+        assert_ne!(ids(42), ids(43));
+        // First output of SplitMix64 seeded with 0
+        assert_eq!(
+            SeededIdGenerator::new(0).new_span_id().to_u64(),
+            0xe220_a839_7b1d_cdaf
+        );
+    }
+
+    #[test]
+    fn legacy_generator_produces_64_bit_trace_ids() {
+        let generator = Legacy64BitIdGenerator::new(IdGenerator::default());
+        for _ in 0..100 {
+            let trace_id = generator.new_trace_id().to_u128();
+            assert_ne!(trace_id, 0);
+            assert_eq!(trace_id >> 64, 0);
+        }
+    }
+}
@@ -7,11 +7,17 @@
 //! current operation execution.
 //! * The `Provider` struct which configures and produces `Tracer`s.
 pub mod config;
+pub mod enrich_processor;
 pub mod evicted_hash_map;
 pub mod evicted_queue;
+pub mod filter_processor;
 pub mod id_generator;
 pub mod provider;
+#[cfg(feature = "redaction")]
+pub mod redaction_processor;
 pub mod sampler;
 pub mod span;
 pub mod span_processor;
+pub mod span_processor_stats;
+pub mod thread_span_processor;
 pub mod tracer;
@@ -8,10 +8,24 @@
 //! propagators) are provided by the `Provider`. `Tracer` instances do
 //! not duplicate this data to avoid that different `Tracer` instances
 //! of the `Provider` have different versions of these data.
+//!
+//! ## Runtime Updates
+//!
+//! The sampler, span limits and span processors of a running `Provider` can
+//! be replaced through an [`UpdateHandle`] returned by
+//! [`Builder::build_with_handle`]. Existing `Tracer` instances observe the
+//! change for all spans started afterwards, and spans that are already in
+//! flight are reported to the processors configured when they end.
+//!
+//! [`UpdateHandle`]: struct.UpdateHandle.html
+//! [`Builder::build_with_handle`]: struct.Builder.html#method.build_with_handle
 use crate::exporter::trace::SpanExporter;
 use crate::{api, sdk};
+use arc_swap::ArcSwap;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, Weak};
 
 /// Default tracer name if empty string is provided.
 const DEFAULT_COMPONENT_NAME: &str = "rust.opentelemetry.io/sdk/tracer";
@@ -20,14 +34,106 @@ const DEFAULT_COMPONENT_NAME: &str = "rust.opentelemetry.io/sdk/tracer";
 #[derive(Debug)]
 struct ProviderInner {
     named_tracers: RwLock<HashMap<&'static str, sdk::Tracer>>,
-    processors: Vec<Box<dyn api::SpanProcessor>>,
-    config: sdk::Config,
+    processors: RwLock<Arc<Vec<Box<dyn api::SpanProcessor>>>>,
+    // Read without locking whenever a span starts
+    config: ArcSwap<sdk::Config>,
+    // Serializes updates of the config
+    shared: Mutex<SharedConfig>,
+}
+
+/// The sampler and id generator of the published config, which are shared
+/// with the copies made by updates.
+#[derive(Debug)]
+struct SharedConfig {
+    sampler: Arc<dyn api::Sampler>,
+    id_generator: Arc<dyn api::IdGenerator>,
+}
+
+impl SharedConfig {
+    /// Take the sampler and id generator out of `config` to share them, and
+    /// return the config to publish.
+    fn new(mut config: sdk::Config) -> (Self, Arc<sdk::Config>) {
+        let shared = SharedConfig {
+            sampler: Arc::from(std::mem::replace(
+                &mut config.default_sampler,
+                Box::new(sdk::Sampler::Never),
+            )),
+            id_generator: Arc::from(std::mem::replace(
+                &mut config.id_generator,
+                Box::new(sdk::IdGenerator::default()),
+            )),
+        };
+        let config = shared.publish(config.span_limits(), config.resource);
+
+        (shared, config)
+    }
+
+    /// A config using the shared sampler and id generator.
+    fn publish(&self, limits: sdk::SpanLimits, resource: Arc<sdk::Resource>) -> Arc<sdk::Config> {
+        let mut config = sdk::Config {
+            default_sampler: Box::new(Shared(self.sampler.clone())),
+            id_generator: Box::new(Shared(self.id_generator.clone())),
+            resource,
+            ..Default::default()
+        };
+        config.set_span_limits(limits);
+
+        Arc::new(config)
+    }
+}
+
+/// A sampler or id generator shared between configs.
+struct Shared<T: ?Sized>(Arc<T>);
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl api::Sampler for Shared<dyn api::Sampler> {
+    fn should_sample(
+        &self,
+        parent_context: Option<&api::SpanContext>,
+        trace_id: api::TraceId,
+        span_id: api::SpanId,
+        name: &str,
+        span_kind: &api::SpanKind,
+        attributes: &[api::KeyValue],
+        links: &[api::Link],
+    ) -> api::SamplingResult {
+        self.0.should_sample(
+            parent_context,
+            trace_id,
+            span_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+impl api::IdGenerator for Shared<dyn api::IdGenerator> {
+    fn new_trace_id(&self) -> api::TraceId {
+        self.0.new_trace_id()
+    }
+
+    fn new_span_id(&self) -> api::SpanId {
+        self.0.new_span_id()
+    }
 }
 
 impl Drop for ProviderInner {
+    /// Starts shutting down the span processors without waiting for them,
+    /// `Provider::shutdown` waits for pending spans to be exported.
     fn drop(&mut self) {
-        for processor in &self.processors {
-            processor.shutdown();
+        let processors = self
+            .processors
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for processor in processors.iter() {
+            processor.begin_shutdown();
         }
     }
 }
@@ -50,14 +156,61 @@ impl Provider {
         Builder::default()
     }
 
-    /// Span processors associated with this provider
-    pub fn span_processors(&self) -> &Vec<Box<dyn api::SpanProcessor>> {
-        &self.inner.processors
+    /// Span processors associated with this provider.
+    ///
+    /// The returned list is a snapshot, processors swapped in through an
+    /// `UpdateHandle` are only visible to subsequent calls.
+    pub fn span_processors(&self) -> Arc<Vec<Box<dyn api::SpanProcessor>>> {
+        self.inner
+            .processors
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Flush all span processors of this provider, see
+    /// [`SpanProcessor::force_flush`].
+    ///
+    /// Returns the result of each processor in the order they were registered.
+    ///
+    /// [`SpanProcessor::force_flush`]: ../../../api/trace/span_processor/trait.SpanProcessor.html#tymethod.force_flush
+    pub fn force_flush(&self) -> Vec<api::FlushResult> {
+        self.span_processors()
+            .iter()
+            .map(|processor| processor.force_flush())
+            .collect()
     }
 
-    /// Config associated with this tracer
-    pub fn config(&self) -> &sdk::Config {
-        &self.inner.config
+    /// Shut down all span processors of this provider, see
+    /// [`SpanProcessor::shutdown`].
+    ///
+    /// Returns the result of each processor in the order they were registered.
+    /// Dropping the provider only starts shutting down the processors and
+    /// does not wait for them to export their pending spans, call this before
+    /// exiting to make sure they are exported.
+    /// Like `force_flush`, this blocks until the processors are done, and must
+    /// not be called from the runtime a batch processor's worker runs on if
+    /// that runtime is single threaded.
+    ///
+    /// [`SpanProcessor::shutdown`]: ../../../api/trace/span_processor/trait.SpanProcessor.html#tymethod.shutdown
+    pub fn shutdown(&self) -> Vec<api::FlushResult> {
+        self.span_processors()
+            .iter()
+            .map(|processor| processor.shutdown())
+            .collect()
+    }
+
+    /// Config associated with this tracer.
+    ///
+    /// The returned config is a snapshot, updates made through an
+    /// `UpdateHandle` are only visible to subsequent calls.
+    pub fn config(&self) -> Arc<sdk::Config> {
+        self.inner.config.load_full()
+    }
+
+    /// The current config, loaded without taking a lock or cloning its `Arc`.
+    pub(crate) fn load_config(&self) -> impl Deref<Target = Arc<sdk::Config>> {
+        self.inner.config.load()
     }
 }
 
@@ -79,14 +232,18 @@ impl api::Provider for Provider {
             .inner
             .named_tracers
             .read()
-            .expect("RwLock poisoned")
+            .unwrap_or_else(PoisonError::into_inner)
             .get(&component_name)
         {
             return tracer.clone();
         };
 
         // Else construct new named tracer
-        let mut tracers = self.inner.named_tracers.write().expect("RwLock poisoned");
+        let mut tracers = self
+            .inner
+            .named_tracers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
         let new_tracer = sdk::Tracer::new(name, self.clone());
         tracers.insert(component_name, new_tracer.clone());
 
@@ -118,6 +275,15 @@ impl Builder {
         Builder { processors, ..self }
     }
 
+    /// Add a custom `SpanProcessor` to this provider, e.g. a processor that
+    /// wraps an exporting processor to filter or modify spans.
+    pub fn with_span_processor<T: api::SpanProcessor + 'static>(self, processor: T) -> Self {
+        let mut processors = self.processors;
+        processors.push(Box::new(processor));
+
+        Builder { processors, ..self }
+    }
+
     /// The sdk `Config` that this provider will use.
     pub fn with_config(self, config: sdk::Config) -> Self {
         Builder { config, ..self }
@@ -125,12 +291,178 @@ impl Builder {
 
     /// Create a new provider from this configuration.
     pub fn build(self) -> Provider {
+        let (shared, config) = SharedConfig::new(self.config);
         Provider {
             inner: Arc::new(ProviderInner {
                 named_tracers: Default::default(),
-                processors: self.processors,
-                config: self.config,
+                processors: RwLock::new(Arc::new(self.processors)),
+                config: ArcSwap::new(config),
+                shared: Mutex::new(shared),
             }),
         }
     }
+
+    /// Create a new provider from this configuration along with an
+    /// `UpdateHandle` that can change its configuration while it is running.
+    pub fn build_with_handle(self) -> (Provider, UpdateHandle) {
+        let provider = self.build();
+        let handle = UpdateHandle {
+            inner: Arc::downgrade(&provider.inner),
+        };
+
+        (provider, handle)
+    }
+}
+
+/// Handle to atomically update the sampler, span limits and span processors
+/// of a running `Provider`.
+///
+/// The handle does not keep the provider alive, updates made after the
+/// provider has been dropped are ignored.
+#[derive(Clone, Debug)]
+pub struct UpdateHandle {
+    inner: Weak<ProviderInner>,
+}
+
+impl UpdateHandle {
+    /// Replace the sampler used for all spans started after this call.
+    pub fn set_sampler(&self, sampler: Box<dyn api::Sampler>) {
+        self.update_config(|shared, _| shared.sampler = Arc::from(sampler))
+    }
+
+    /// Replace the span limits used for all spans started after this call.
+    ///
+    /// All limits are replaced at once, use `Provider::config` to read the
+    /// current limits and change only some of them.
+    pub fn set_span_limits(&self, limits: sdk::SpanLimits) {
+        self.update_config(|_, current| *current = limits)
+    }
+
+    /// Publish a copy of the current config, after `f` updated the shared
+    /// sampler or the span limits.
+    fn update_config<F>(&self, f: F)
+    where
+        F: FnOnce(&mut SharedConfig, &mut sdk::SpanLimits),
+    {
+        if let Some(inner) = self.inner.upgrade() {
+            let mut shared = inner.shared.lock().unwrap_or_else(PoisonError::into_inner);
+            let current = inner.config.load_full();
+            let mut limits = current.span_limits();
+            f(&mut shared, &mut limits);
+            inner
+                .config
+                .store(shared.publish(limits, current.resource.clone()));
+        }
+    }
+
+    /// Replace the span processors of the provider.
+    ///
+    /// Spans starting after this call are reported to the new processors.
+    /// Spans that already started are still ended on the processors that saw
+    /// them start, so each processor sees both ends of its spans. Returns the
+    /// previous processors, which are not shut down by this call.
+    /// Call `shutdown` on them to wait for their pending spans to be exported,
+    /// or drop them to let them finish in the background. Returns `None` if
+    /// the provider has been dropped.
+    pub fn set_span_processors(
+        &self,
+        processors: Vec<Box<dyn api::SpanProcessor>>,
+    ) -> Option<Arc<Vec<Box<dyn api::SpanProcessor>>>> {
+        let inner = self.inner.upgrade()?;
+        let mut current = inner
+            .processors
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        Some(std::mem::replace(&mut *current, Arc::new(processors)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{self, Provider as _, Span, Tracer};
+    use crate::{exporter, sdk};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone, Debug, Default)]
+    struct CountShutdowns(Arc<AtomicUsize>);
+
+    impl api::SpanProcessor for CountShutdowns {
+        fn on_start(&self, _span: &mut exporter::trace::SpanData) {}
+
+        fn on_end(&self, _span: Arc<exporter::trace::SpanData>) {}
+
+        fn shutdown(&self) -> api::FlushResult {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            api::FlushResult::Success
+        }
+    }
+
+    #[test]
+    fn update_sampler_for_existing_tracer() {
+        let (provider, handle) = sdk::Provider::builder()
+            .with_config(sdk::Config {
+                default_sampler: Box::new(sdk::Sampler::Never),
+                ..Default::default()
+            })
+            .build_with_handle();
+        let tracer = provider.get_tracer("test");
+
+        let in_flight = tracer.start("in-flight", None);
+        assert!(!in_flight.is_recording());
+
+        handle.set_sampler(Box::new(sdk::Sampler::Always));
+        assert!(tracer.start("after-update", None).is_recording());
+
+        let before = provider.config();
+        handle.set_span_limits(sdk::SpanLimits {
+            max_attributes_per_span: 1,
+            ..before.span_limits()
+        });
+        assert_eq!(provider.config().max_attributes_per_span, 1);
+        assert_eq!(before.max_attributes_per_span, 32);
+        assert!(tracer.start("after-limits", None).is_recording());
+    }
+
+    #[test]
+    fn update_after_drop_is_ignored() {
+        let (provider, handle) = sdk::Provider::builder().build_with_handle();
+        drop(provider);
+
+        handle.set_sampler(Box::new(sdk::Sampler::Never));
+        assert!(handle.set_span_processors(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn set_span_processors_returns_previous_processors() {
+        let exporter = crate::exporter::trace::in_memory::InMemorySpanExporter::default();
+        let (provider, handle) = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build_with_handle();
+        let tracer = provider.get_tracer("test");
+        let started = tracer.start("before-update", None);
+
+        let previous = handle.set_span_processors(Vec::new()).unwrap();
+        tracer.start("after-update", None).end();
+        assert_eq!(previous.len(), 1);
+        assert!(exporter.get_finished_spans().is_empty());
+
+        // Spans end on the processors they started on
+        drop(started);
+        let finished = exporter.get_finished_spans();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "before-update");
+        assert_eq!(provider.shutdown(), Vec::new());
+    }
+
+    #[test]
+    fn drop_shuts_down_processors() {
+        let processor = CountShutdowns::default();
+        let provider = sdk::Provider::builder()
+            .with_span_processor(processor.clone())
+            .build();
+        drop(provider);
+
+        assert_eq!(processor.0.load(Ordering::SeqCst), 1);
+    }
 }
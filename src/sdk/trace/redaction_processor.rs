@@ -0,0 +1,320 @@
+//! # Redaction Span Processor
+//!
+//! The [`RedactionSpanProcessor`] wraps another [`SpanProcessor`] and rewrites
+//! or removes sensitive data from finished spans before they reach it.
+//! Rules apply to span attributes, event attributes and the span's
+//! `status_message`.
+//!
+//! Attributes can be matched by key, in which case they are either removed or
+//! have their whole value replaced, and string values can be matched by a
+//! regular expression, in which case only the matching parts are replaced.
+//! A set of built-in [`Scrubber`]s covers common secrets.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::{api, sdk};
+//! use opentelemetry::sdk::trace::redaction_processor::{RedactionSpanProcessor, Scrubber};
+//!
+//! # fn main() -> Result<(), regex::Error> {
+//! let exporter = api::NoopSpanExporter {};
+//! let processor = RedactionSpanProcessor::new(sdk::SimpleSpanProcessor::new(Box::new(exporter)))
+//!     .with_scrubber(Scrubber::Email)
+//!     .with_scrubber(Scrubber::UrlQuery)
+//!     .remove_key("^http\\.request\\.header\\.cookie$")?
+//!     .replace_value("card=\\d+", "card=[REDACTED]")?;
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_span_processor(processor)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RedactionSpanProcessor`]: struct.RedactionSpanProcessor.html
+//! [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+//! [`Scrubber`]: enum.Scrubber.html
+use crate::{api, exporter};
+use regex::Regex;
+use std::sync::Arc;
+
+/// Replacement used by the built-in scrubbers.
+const REDACTED: &str = "[REDACTED]";
+
+/// Built-in rules for commonly leaked secrets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scrubber {
+    /// Replace email addresses found in string values.
+    Email,
+    /// Replace `Bearer` and `Basic` credentials found in string values.
+    AuthToken,
+    /// Strip query strings from URLs found in string values.
+    UrlQuery,
+    /// Replace the values of attributes whose key suggests a secret, e.g.
+    /// `password`, `secret`, `token`, `api_key` or `authorization`.
+    SensitiveKeys,
+}
+
+/// What to do with an attribute whose key matches a rule.
+#[derive(Clone, Debug)]
+enum KeyAction {
+    Remove,
+    Replace(String),
+}
+
+#[derive(Clone, Debug)]
+struct KeyRule {
+    pattern: Regex,
+    action: KeyAction,
+}
+
+#[derive(Clone, Debug)]
+struct ValueRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+/// A [`SpanProcessor`] that redacts span data before passing finished spans to
+/// an inner processor.
+///
+/// Spans passed to `on_start` are forwarded unchanged.
+///
+/// [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+#[derive(Debug)]
+pub struct RedactionSpanProcessor<P> {
+    inner: P,
+    key_rules: Vec<KeyRule>,
+    value_rules: Vec<ValueRule>,
+}
+
+impl<P: api::SpanProcessor> RedactionSpanProcessor<P> {
+    /// Create a new redaction processor without any rules.
+    pub fn new(inner: P) -> Self {
+        RedactionSpanProcessor {
+            inner,
+            key_rules: Vec::new(),
+            value_rules: Vec::new(),
+        }
+    }
+
+    /// Remove attributes whose key matches the `pattern` regular expression.
+    pub fn remove_key(self, pattern: &str) -> Result<Self, regex::Error> {
+        self.with_key_rule(pattern, KeyAction::Remove)
+    }
+
+    /// Replace the value of attributes whose key matches the `pattern`
+    /// regular expression with `replacement`.
+    pub fn replace_key<S: Into<String>>(
+        self,
+        pattern: &str,
+        replacement: S,
+    ) -> Result<Self, regex::Error> {
+        self.with_key_rule(pattern, KeyAction::Replace(replacement.into()))
+    }
+
+    /// Replace all matches of the `pattern` regular expression in string
+    /// values with `replacement`. The replacement can refer to capture groups,
+    /// e.g. `$1`.
+    pub fn replace_value<S: Into<String>>(
+        mut self,
+        pattern: &str,
+        replacement: S,
+    ) -> Result<Self, regex::Error> {
+        self.value_rules.push(ValueRule {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.into(),
+        });
+
+        Ok(self)
+    }
+
+    /// Add one of the built-in scrubbers.
+    pub fn with_scrubber(self, scrubber: Scrubber) -> Self {
+        let result = match scrubber {
+            Scrubber::Email => self.replace_value(
+                r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}",
+                REDACTED,
+            ),
+            Scrubber::AuthToken => self.replace_value(
+                r"(?i)\b(bearer|basic)\s+[A-Za-z0-9\-._~+/]+=*",
+                format!("$1 {}", REDACTED),
+            ),
+            Scrubber::UrlQuery => {
+                self.replace_value(r"([A-Za-z][A-Za-z0-9+.\-]*://[^\s?#]*)\?[^\s#]*", "$1")
+            }
+            Scrubber::SensitiveKeys => self.replace_key(
+                r"(?i)(password|passwd|secret|token|api[_\-.]?key|authorization|cookie)",
+                REDACTED,
+            ),
+        };
+
+        result.expect("built-in scrubber patterns must be valid")
+    }
+
+    fn with_key_rule(mut self, pattern: &str, action: KeyAction) -> Result<Self, regex::Error> {
+        self.key_rules.push(KeyRule {
+            pattern: Regex::new(pattern)?,
+            action,
+        });
+
+        Ok(self)
+    }
+
+    /// Apply all rules to the given span data.
+    fn redact(&self, span: &mut exporter::trace::SpanData) {
+        let removed = span
+            .attributes
+            .iter()
+            .filter(|(key, _)| matches!(self.key_action(key), Some(KeyAction::Remove)))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in removed {
+            span.attributes.remove(&key);
+        }
+        for (key, value) in &mut span.attributes {
+            self.redact_value(key, value);
+        }
+
+        for event in &mut span.message_events {
+            event
+                .attributes
+                .retain(|kv| !matches!(self.key_action(&kv.key), Some(KeyAction::Remove)));
+            for api::KeyValue { key, value } in event.attributes.iter_mut() {
+                self.redact_value(key, value);
+            }
+        }
+
+        if let Some(message) = self.redact_str(&span.status_message) {
+            span.status_message = message;
+        }
+    }
+
+    fn key_action(&self, key: &api::Key) -> Option<&KeyAction> {
+        self.key_rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(key.as_str()))
+            .map(|rule| &rule.action)
+    }
+
+    fn redact_value(&self, key: &api::Key, value: &mut api::Value) {
+        if let Some(KeyAction::Replace(replacement)) = self.key_action(key) {
+            *value = api::Value::String(replacement.clone());
+        } else if let api::Value::String(s) = value {
+            if let Some(redacted) = self.redact_str(s) {
+                *s = redacted;
+            }
+        }
+    }
+
+    /// Returns the redacted string, or `None` if no value rule matched.
+    fn redact_str(&self, s: &str) -> Option<String> {
+        let mut result: Option<String> = None;
+        for rule in &self.value_rules {
+            let current = result.as_deref().unwrap_or(s);
+            if rule.pattern.is_match(current) {
+                result = Some(
+                    rule.pattern
+                        .replace_all(current, rule.replacement.as_str())
+                        .into_owned(),
+                );
+            }
+        }
+
+        result
+    }
+}
+
+impl<P: api::SpanProcessor> api::SpanProcessor for RedactionSpanProcessor<P> {
+    fn on_start(&self, span: &mut exporter::trace::SpanData) {
+        self.inner.on_start(span)
+    }
+
+    fn on_end(&self, mut span: Arc<exporter::trace::SpanData>) {
+        self.redact(Arc::make_mut(&mut span));
+        self.inner.on_end(span)
+    }
+
+    fn force_flush(&self) -> api::FlushResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown(&self) -> api::FlushResult {
+        self.inner.shutdown()
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RedactionSpanProcessor, Scrubber};
+    use crate::api::{Key, Provider, Span, Tracer};
+    use crate::exporter::trace::in_memory::InMemorySpanExporter;
+    use crate::{api, exporter, sdk};
+
+    fn collect(exporter: &InMemorySpanExporter) -> sdk::SimpleSpanProcessor {
+        sdk::SimpleSpanProcessor::new(Box::new(exporter.clone()))
+    }
+
+    fn attribute(span: &exporter::trace::SpanData, key: &str) -> Option<api::Value> {
+        span.attributes
+            .iter()
+            .find(|(k, _)| k.as_str() == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn redacts_attributes_events_and_status() {
+        let exporter = InMemorySpanExporter::default();
+        let processor = RedactionSpanProcessor::new(collect(&exporter))
+            .with_scrubber(Scrubber::Email)
+            .with_scrubber(Scrubber::AuthToken)
+            .with_scrubber(Scrubber::UrlQuery)
+            .with_scrubber(Scrubber::SensitiveKeys)
+            .remove_key("^internal\\.")
+            .unwrap();
+        let provider = sdk::Provider::builder()
+            .with_span_processor(processor)
+            .build();
+        let span = provider.get_tracer("test").start("checkout", None);
+        span.set_attribute(Key::new("user").string("jane.doe@example.com"));
+        span.set_attribute(Key::new("http.url").string("https://example.com/a/b?token=abc#top"));
+        span.set_attribute(Key::new("db.password").string("hunter2"));
+        span.set_attribute(Key::new("internal.debug").bool(true));
+        span.add_event(
+            "request".to_string(),
+            vec![
+                Key::new("header").string("Bearer abc.def-ghi"),
+                Key::new("internal.id").i64(1),
+            ],
+        );
+        span.set_status(
+            api::StatusCode::Unauthenticated,
+            "rejected jane.doe@example.com".to_string(),
+        );
+        drop(span);
+
+        let spans = exporter.get_finished_spans();
+        let span = &spans[0];
+        assert_eq!(attribute(span, "user"), Some("[REDACTED]".into()));
+        assert_eq!(
+            attribute(span, "http.url"),
+            Some("https://example.com/a/b#top".into())
+        );
+        assert_eq!(attribute(span, "db.password"), Some("[REDACTED]".into()));
+        assert_eq!(attribute(span, "internal.debug"), None);
+        assert_eq!(
+            span.message_events.iter().next().unwrap().attributes,
+            vec![Key::new("header").string("Bearer [REDACTED]")]
+        );
+        assert_eq!(span.status_message, "rejected [REDACTED]");
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let processor = RedactionSpanProcessor::new(collect(&InMemorySpanExporter::default()));
+        assert!(processor.replace_value("(", "").is_err());
+    }
+}
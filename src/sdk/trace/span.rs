@@ -8,41 +8,59 @@
 //! start time is set to the current time on span creation. After the `Span` is created, it
 //! is possible to change its name, set its `Attributes`, and add `Links` and `Events`.
 //! These cannot be changed after the `Span`'s end time has been set.
-use crate::api::Tracer;
+use crate::sdk::trace::config::SpanLimits;
 use crate::{api, exporter, sdk};
 use std::any::Any;
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
 /// Single operation within a trace.
+///
+/// Spans that are not recording only hold their id, creating, cloning and
+/// dropping them neither allocates nor locks.
 #[derive(Clone, Debug)]
 pub struct Span {
     id: api::SpanId,
-    inner: Arc<SpanInner>,
+    inner: Option<Arc<SpanInner>>,
 }
 
+/// The span processors a span started on.
+type Processors = Arc<Vec<Box<dyn api::SpanProcessor>>>;
+
 /// Inner data, processed and exported on drop
 #[derive(Debug)]
 struct SpanInner {
     data: Option<Mutex<exporter::trace::SpanData>>,
-    tracer: sdk::Tracer,
+    // The processors the span started on
+    processors: Processors,
+    limits: SpanLimits,
 }
 
 impl Span {
+    /// Create a recording span if there is data. The span ends on the
+    /// processors it started on.
     pub(crate) fn new(
         id: api::SpanId,
-        data: Option<exporter::trace::SpanData>,
-        tracer: sdk::Tracer,
+        data: Option<(exporter::trace::SpanData, Processors)>,
+        limits: SpanLimits,
     ) -> Self {
         Span {
             id,
-            inner: Arc::new(SpanInner {
-                data: data.map(Mutex::new),
-                tracer,
+            inner: data.map(|(data, processors)| {
+                Arc::new(SpanInner {
+                    data: Some(Mutex::new(data)),
+                    processors,
+                    limits,
+                })
             }),
         }
     }
 
+    /// Create a span that is not recording.
+    pub(crate) fn non_recording(id: api::SpanId) -> Self {
+        Span { id, inner: None }
+    }
+
     /// Return span id
     pub(crate) fn id(&self) -> api::SpanId {
         self.id
@@ -54,9 +72,9 @@ impl Span {
         F: FnOnce(&exporter::trace::SpanData) -> T,
     {
         self.inner
-            .data
             .as_ref()
-            .and_then(|inner| inner.lock().ok().map(|span_data| f(&span_data)))
+            .and_then(|inner| inner.data.as_ref())
+            .and_then(|data| data.lock().ok().map(|span_data| f(&span_data)))
     }
 
     /// Operate on mutable reference to span inner
@@ -65,9 +83,18 @@ impl Span {
         F: FnOnce(&mut exporter::trace::SpanData) -> T,
     {
         self.inner
-            .data
             .as_ref()
-            .and_then(|inner| inner.lock().ok().map(|mut span_data| f(&mut span_data)))
+            .and_then(|inner| inner.data.as_ref())
+            .and_then(|data| data.lock().ok().map(|mut span_data| f(&mut span_data)))
+    }
+
+    /// Operate on mutable reference to span inner with the span limits
+    fn with_limited_data_mut<T, F>(&self, f: F) -> Option<T>
+    where
+        F: FnOnce(&mut exporter::trace::SpanData, &SpanLimits) -> T,
+    {
+        let limits = &self.inner.as_ref()?.limits;
+        self.with_data_mut(|data| f(data, limits))
     }
 }
 
@@ -83,9 +110,9 @@ impl api::Span for Span {
         timestamp: SystemTime,
         attributes: Vec<api::KeyValue>,
     ) {
-        self.with_data_mut(|data| {
+        self.with_limited_data_mut(|data, limits| {
             data.message_events
-                .push_back(api::Event::new(name, timestamp, attributes))
+                .push_back(limits.event(api::Event::new(name, timestamp, attributes)))
         });
     }
 
@@ -100,7 +127,7 @@ impl api::Span for Span {
     /// Returns true if this `Span` is recording information like events with the `add_event`
     /// operation, attributes using `set_attributes`, status with `set_status`, etc.
     fn is_recording(&self) -> bool {
-        self.inner.data.is_some()
+        self.inner.is_some()
     }
 
     /// Sets a single `Attribute` where the attribute properties are passed as arguments.
@@ -109,8 +136,8 @@ impl api::Span for Span {
     /// attributes"](https://github.com/open-telemetry/opentelemetry-specification/blob/master/specification/data-semantic-conventions.md)
     /// that have prescribed semantic meanings.
     fn set_attribute(&self, attribute: api::KeyValue) {
-        self.with_data_mut(|data| {
-            data.attributes.insert(attribute);
+        self.with_limited_data_mut(|data, limits| {
+            data.attributes.insert(limits.attribute(attribute));
         });
     }
 
@@ -149,7 +176,7 @@ impl api::Span for Span {
     ///
     /// [`instrument`]: ../../api/trace/futures/trait.Instrument.html#method.instrument
     fn mark_as_active(&self) {
-        self.inner.tracer.mark_span_as_active(&self);
+        sdk::trace::tracer::mark_span_as_active(self);
     }
 
     /// Mark span as inactive
@@ -159,22 +186,22 @@ impl api::Span for Span {
     ///
     /// [`instrument`]: ../futures/trait.Instrument.html#method.instrument
     fn mark_as_inactive(&self) {
-        self.inner.tracer.mark_span_as_inactive(self.id);
+        sdk::trace::tracer::mark_span_as_inactive(self.id);
     }
 }
 
 impl Drop for SpanInner {
     /// Report span on inner drop
     fn drop(&mut self) {
-        if let Some(data) = self.data.take() {
-            if let Ok(mut inner) = data.lock() {
-                if inner.end_time == inner.start_time {
-                    inner.end_time = SystemTime::now();
-                }
-                let exportable_span = Arc::new(inner.clone());
-                for processor in self.tracer.provider().span_processors() {
-                    processor.on_end(exportable_span.clone())
-                }
+        // The span data is moved out of the mutex, no other reference to it
+        // can exist once the inner span is dropped.
+        if let Some(Ok(mut data)) = self.data.take().map(Mutex::into_inner) {
+            if data.end_time == data.start_time {
+                data.end_time = SystemTime::now();
+            }
+            let exportable_span = Arc::new(data);
+            for processor in self.processors.iter() {
+                processor.on_end(exportable_span.clone())
             }
         }
     }
@@ -81,6 +81,15 @@
 //! }
 //! ```
 //!
+//! With the `rt-tokio` or `rt-async-std` feature enabled, the spawn and interval functions of the
+//! respective runtime are provided by [`tokio_builder`] and [`async_std_builder`]:
+//!
+//! ```ignore
+//! let batch = sdk::BatchSpanProcessor::tokio_builder(exporter)
+//!     .with_max_queue_size(4096)
+//!     .build();
+//! ```
+//!
 //! [`is_recording`]: ../../../api/trace/span/trait.Span.html#tymethod.is_recording
 //! [`Provider`]: ../../../api/trace/provider/trait.Provider.html
 //! [`Tracer`]: ../../../api/trace/tracer/trait.Tracer.html
@@ -88,16 +97,22 @@
 //! [`SimpleSpanProcessor`]: struct.SimpleSpanProcessor.html
 //! [`BatchSpanProcessor`]: struct.BatchSpanProcessor.html
 //! [`executor`]: https://docs.rs/futures/0.3.4/futures/executor/index.html
+//! [`tokio_builder`]: struct.BatchSpanProcessor.html#method.tokio_builder
+//! [`async_std_builder`]: struct.BatchSpanProcessor.html#method.async_std_builder
 //! [`tokio`]: https://tokio.rs
 //! [`async-std`]: https://async.rs
+use crate::sdk::trace::span_processor_stats::{DropReason, SpanProcessorStats};
 use crate::{api, exporter};
 use futures::{
     channel::mpsc,
+    future,
     task::{Context, Poll},
-    Future, Stream, StreamExt,
+    Future, FutureExt, Stream, StreamExt,
 };
+use rand::Rng;
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc as sync_mpsc, Arc, Mutex};
 use std::time;
 
 /// A [`SpanProcessor`] that exports synchronously when spans are finished.
@@ -106,54 +121,102 @@ use std::time;
 #[derive(Debug)]
 pub struct SimpleSpanProcessor {
     exporter: Box<dyn exporter::trace::SpanExporter>,
+    stats: SpanProcessorStats,
+    is_shutdown: AtomicBool,
 }
 
 impl SimpleSpanProcessor {
-    pub(crate) fn new(exporter: Box<dyn exporter::trace::SpanExporter>) -> Self {
-        SimpleSpanProcessor { exporter }
+    /// Create a new simple processor exporting to the given exporter.
+    pub fn new(exporter: Box<dyn exporter::trace::SpanExporter>) -> Self {
+        SimpleSpanProcessor {
+            exporter,
+            stats: SpanProcessorStats::default(),
+            is_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// A handle to the counters of this processor.
+    pub fn stats(&self) -> SpanProcessorStats {
+        self.stats.clone()
     }
 }
 
 impl api::SpanProcessor for SimpleSpanProcessor {
-    fn on_start(&self, _span: Arc<exporter::trace::SpanData>) {
-        // Ignored
+    fn on_start(&self, _span: &mut exporter::trace::SpanData) {
+        self.stats.span_started();
     }
 
     fn on_end(&self, span: Arc<exporter::trace::SpanData>) {
+        self.stats.span_ended();
         if span.context.is_sampled() {
-            self.exporter.export(vec![span]);
+            let start = time::Instant::now();
+            let result = self.exporter.export(vec![span]);
+            self.stats.batch_exported(
+                1,
+                result == exporter::trace::ExportResult::Success,
+                start.elapsed(),
+            );
         }
     }
 
-    fn shutdown(&self) {
+    fn shutdown(&self) -> api::FlushResult {
+        if self.is_shutdown.swap(true, Ordering::SeqCst) {
+            return api::FlushResult::AlreadyShutdown;
+        }
         self.exporter.shutdown();
+        api::FlushResult::Success
     }
 }
 
 /// A [`SpanProcessor`] that asynchronously buffers finished spans and reports
 /// them at a preconfigured interval.
 ///
+/// `force_flush` and `shutdown` block the calling thread until the worker has
+/// exported all pending spans, or the configured flush timeout elapsed. They
+/// should not be called from the thread the worker is running on, e.g. from
+/// within a single threaded runtime, as the worker cannot make progress then.
+/// Dropping the processor does not block, the worker exports the remaining
+/// spans and shuts down the exporter in the background.
+///
 /// [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
 #[derive(Debug)]
 pub struct BatchSpanProcessor {
     message_sender: Mutex<mpsc::Sender<BatchMessage>>,
+    flush_timeout: time::Duration,
+    stats: SpanProcessorStats,
 }
 
 impl api::SpanProcessor for BatchSpanProcessor {
-    fn on_start(&self, _span: Arc<exporter::trace::SpanData>) {
-        // Ignored
+    fn on_start(&self, _span: &mut exporter::trace::SpanData) {
+        self.stats.span_started();
     }
 
     fn on_end(&self, span: Arc<exporter::trace::SpanData>) {
-        if let Ok(mut sender) = self.message_sender.try_lock() {
-            let _ = sender.try_send(BatchMessage::ExportSpan(span));
+        self.stats.span_ended();
+        let mut sender = match self.message_sender.try_lock() {
+            Ok(sender) => sender,
+            Err(_) => return self.stats.span_dropped(DropReason::LockContention),
+        };
+        match sender.try_send(BatchMessage::ExportSpan(span)) {
+            Ok(()) => self.stats.span_queued(),
+            Err(err) if err.is_full() => self.stats.span_dropped(DropReason::QueueFull),
+            Err(_) => self.stats.span_dropped(DropReason::AfterShutdown),
         }
     }
 
-    fn shutdown(&self) {
-        if let Ok(mut sender) = self.message_sender.try_lock() {
-            let _ = sender.try_send(BatchMessage::Shutdown);
-        }
+    fn force_flush(&self) -> api::FlushResult {
+        self.send_and_wait(BatchMessage::Flush)
+    }
+
+    fn shutdown(&self) -> api::FlushResult {
+        self.send_and_wait(BatchMessage::Shutdown)
+    }
+
+    /// Asks the worker to export the remaining spans and shut down the
+    /// exporter without waiting for it.
+    fn begin_shutdown(&self) {
+        let (ack, _) = sync_mpsc::channel();
+        self.send(BatchMessage::Shutdown(ack));
     }
 }
 
@@ -162,54 +225,266 @@ impl api::SpanProcessor for BatchSpanProcessor {
 /// This process is implemented as a [`Future`] that returns when the accompanying
 /// [`BatchSpanProcessor`] is shut down, and allows systems like [`tokio`] and [`async-std`] to
 /// process the work in the background without requiring dedicated system threads.
+///
+/// Exports are raced against a timer of the runtime, an export that does not
+/// complete within the configured export timeout is dropped.
 #[allow(missing_debug_implementations)]
 pub struct BatchSpanProcessorWorker {
-    exporter: Box<dyn exporter::trace::SpanExporter>,
-    messages: Pin<Box<dyn Stream<Item = BatchMessage> + Send>>,
-    config: BatchConfig,
-    buffer: Vec<Arc<exporter::trace::SpanData>>,
+    worker: Pin<Box<dyn Future<Output = ()> + Send>>,
 }
 
 impl Future for BatchSpanProcessorWorker {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.worker.as_mut().poll(cx)
+    }
+}
+
+type Ticker = Pin<Box<dyn Stream<Item = ()> + Send>>;
+
+type Delay = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The state of a worker, driven by `Worker::run`.
+struct Worker {
+    exporter: Box<dyn exporter::trace::SpanExporter>,
+    messages: mpsc::Receiver<BatchMessage>,
+    ticker: Ticker,
+    interval: Box<dyn Fn(time::Duration) -> Ticker + Send>,
+    config: BatchConfig,
+    buffer: Vec<Arc<exporter::trace::SpanData>>,
+    retry: Option<PendingRetry>,
+    stats: SpanProcessorStats,
+}
+
+/// A batch that failed with a retryable error and waits for its next attempt.
+struct PendingRetry {
+    batch: Vec<Arc<exporter::trace::SpanData>>,
+    attempts: u32,
+    timer: Delay,
+}
+
+/// What the worker has to do next.
+enum Event {
+    Tick,
+    Retry,
+    Message(Option<BatchMessage>),
+}
+
+impl Worker {
+    async fn run(mut self) {
         loop {
-            match futures::ready!(self.messages.poll_next_unpin(cx)) {
-                // Span has finished, add to buffer of pending spans.
-                Some(BatchMessage::ExportSpan(span)) => {
-                    if self.buffer.len() < self.config.max_queue_size {
-                        self.buffer.push(span);
+            match future::poll_fn(|cx| self.poll_event(cx)).await {
+                // Span batch interval time reached, export current spans
+                // unless a failed batch is waiting to be retried.
+                Event::Tick => {
+                    if self.retry.is_none() {
+                        self.export_buffer(true).await;
                     }
                 }
-                // Span batch interval time reached, export current spans.
-                Some(BatchMessage::Tick) => {
-                    if !self.buffer.is_empty() {
-                        let mut spans = std::mem::replace(&mut self.buffer, Vec::new());
-                        while !spans.is_empty() {
-                            let batch_idx = spans
-                                .len()
-                                .saturating_sub(self.config.max_export_batch_size);
-                            let batch = spans.split_off(batch_idx);
-                            self.exporter.export(batch);
-                        }
-                    }
+                // Backoff of the failed batch elapsed, export it again.
+                Event::Retry => self.export_retry().await,
+                // Span has finished, add to buffer of pending spans.
+                Event::Message(Some(BatchMessage::ExportSpan(span))) => self.push(span),
+                // Flush requested, export current spans and acknowledge.
+                Event::Message(Some(BatchMessage::Flush(ack))) => {
+                    let result = self.export_buffer(true).await;
+                    let _ = ack.send(result);
                 }
-                // Stream has terminated or processor is shutdown, return to finish execution.
-                None | Some(BatchMessage::Shutdown) => {
-                    self.exporter.shutdown();
-                    return Poll::Ready(());
+                // Processor is shutdown, drain pending spans and return to finish execution.
+                Event::Message(Some(BatchMessage::Shutdown(ack))) => {
+                    let result = self.shutdown().await;
+                    let _ = ack.send(result);
+                    return;
+                }
+                // All senders are gone, export what is left and finish execution.
+                Event::Message(None) => {
+                    self.shutdown().await;
+                    return;
                 }
             }
         }
     }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Event> {
+        if let Poll::Ready(Some(())) = self.ticker.poll_next_unpin(cx) {
+            return Poll::Ready(Event::Tick);
+        }
+        if let Some(retry) = self.retry.as_mut() {
+            if retry.timer.poll_unpin(cx).is_ready() {
+                return Poll::Ready(Event::Retry);
+            }
+        }
+        self.messages.poll_next_unpin(cx).map(Event::Message)
+    }
+
+    /// A future completing once `duration` elapsed, driven by the runtime's
+    /// interval.
+    fn delay(&self, duration: time::Duration) -> Delay {
+        if duration == time::Duration::from_secs(0) {
+            return Box::pin(future::ready(()));
+        }
+        let deadline = time::Instant::now() + duration;
+        let mut ticks = (self.interval)(duration);
+        Box::pin(async move {
+            // Intervals of some runtimes tick immediately, wait for the
+            // deadline rather than for a number of ticks.
+            while time::Instant::now() < deadline {
+                if ticks.next().await.is_none() {
+                    future::pending::<()>().await;
+                }
+            }
+        })
+    }
+
+    /// Add a finished span to the buffer of pending spans.
+    fn push(&mut self, span: Arc<exporter::trace::SpanData>) {
+        let retrying = self.retry.as_ref().map_or(0, |retry| retry.batch.len());
+        if self.buffer.len() + retrying < self.config.max_queue_size {
+            self.buffer.push(span);
+        } else {
+            self.stats.span_dropped(DropReason::BufferFull);
+        }
+    }
+
+    /// Export all buffered spans, including a batch waiting for a retry, in
+    /// batches of at most `max_export_batch_size`.
+    ///
+    /// If a batch fails with a retryable error and `schedule_retries` is set,
+    /// the batch is scheduled for a retry and the remaining spans are kept in
+    /// the buffer until the retry is done.
+    async fn export_buffer(&mut self, schedule_retries: bool) -> api::FlushResult {
+        let mut result = api::FlushResult::Success;
+        let mut spans = std::mem::take(&mut self.buffer);
+        let mut attempts = 0;
+        if let Some(retry) = self.retry.take() {
+            attempts = retry.attempts;
+            spans.extend(retry.batch);
+        }
+        while !spans.is_empty() {
+            let batch_idx = spans
+                .len()
+                .saturating_sub(self.config.max_export_batch_size);
+            let batch = spans.split_off(batch_idx);
+            if !self
+                .export_batch(batch, attempts + 1, schedule_retries)
+                .await
+            {
+                result = api::FlushResult::ExportFailed;
+            }
+            attempts = 0;
+            if self.retry.is_some() {
+                self.buffer = spans;
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Export the batch waiting for a retry.
+    async fn export_retry(&mut self) {
+        if let Some(retry) = self.retry.take() {
+            self.export_batch(retry.batch, retry.attempts + 1, true)
+                .await;
+        }
+    }
+
+    /// Export a single batch, this is attempt number `attempts`. Returns
+    /// `true` if the batch was exported.
+    async fn export_batch(
+        &mut self,
+        batch: Vec<Arc<exporter::trace::SpanData>>,
+        attempts: u32,
+        schedule_retry: bool,
+    ) -> bool {
+        let batch_len = batch.len();
+        let start = time::Instant::now();
+        let result = self.export(batch.clone()).await;
+        let elapsed = start.elapsed();
+
+        match result {
+            exporter::trace::ExportResult::Success => {
+                self.stats.batch_exported(batch_len, true, elapsed);
+                self.stats.dequeue(batch_len);
+                return true;
+            }
+            exporter::trace::ExportResult::FailedRetryable
+                if schedule_retry && attempts <= self.config.max_export_retries =>
+            {
+                let timer = self.delay(self.backoff(attempts));
+                self.retry = Some(PendingRetry {
+                    batch,
+                    attempts,
+                    timer,
+                });
+            }
+            _ => {
+                self.stats.batch_exported(batch_len, false, elapsed);
+                self.stats.dequeue(batch_len);
+            }
+        }
+
+        false
+    }
+
+    /// Run the exporter for at most `export_timeout`. Exports that time out
+    /// are dropped, which cancels them, and are not retried.
+    async fn export(
+        &mut self,
+        batch: Vec<Arc<exporter::trace::SpanData>>,
+    ) -> exporter::trace::ExportResult {
+        let timeout = self.delay(self.config.export_timeout);
+        match future::select(self.exporter.export_async(batch), timeout).await {
+            future::Either::Left((result, _)) => result,
+            future::Either::Right(((), _)) => exporter::trace::ExportResult::FailedNotRetryable,
+        }
+    }
+
+    /// Exponential backoff with jitter before attempt number `attempts + 1`.
+    fn backoff(&self, attempts: u32) -> time::Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        let backoff = self
+            .config
+            .initial_retry_backoff
+            .checked_mul(factor)
+            .map_or(self.config.max_retry_backoff, |backoff| {
+                backoff.min(self.config.max_retry_backoff)
+            });
+        let nanos = backoff.as_nanos() as u64;
+
+        time::Duration::from_nanos(rand::thread_rng().gen_range(nanos / 2, nanos + 1))
+    }
+
+    /// Export everything still queued in the buffer or the channel, then shut
+    /// down the exporter. Retryable failures are not retried.
+    ///
+    /// Exports complete or time out before the exporter is shut down, so no
+    /// export is running concurrently with the shutdown.
+    async fn shutdown(&mut self) -> api::FlushResult {
+        self.messages.close();
+        let mut acks = Vec::new();
+        while let Some(Some(message)) = self.messages.next().now_or_never() {
+            match message {
+                BatchMessage::ExportSpan(span) => self.push(span),
+                BatchMessage::Flush(ack) | BatchMessage::Shutdown(ack) => acks.push(ack),
+            }
+        }
+        let result = self.export_buffer(false).await;
+        self.exporter.shutdown();
+        for ack in acks {
+            let _ = ack.send(result);
+        }
+
+        result
+    }
 }
 
 #[derive(Debug)]
 enum BatchMessage {
     ExportSpan(Arc<exporter::trace::SpanData>),
-    Tick,
-    Shutdown,
+    Flush(sync_mpsc::Sender<api::FlushResult>),
+    Shutdown(sync_mpsc::Sender<api::FlushResult>),
 }
 
 impl BatchSpanProcessor {
@@ -221,23 +496,70 @@ impl BatchSpanProcessor {
     ) -> Self
     where
         S: Fn(BatchSpanProcessorWorker) -> SO,
-        I: Fn(time::Duration) -> IS,
+        I: Fn(time::Duration) -> IS + Send + 'static,
         IS: Stream<Item = ISI> + Send + 'static,
     {
         let (message_sender, message_receiver) = mpsc::channel(config.max_queue_size);
-        let ticker = interval(config.scheduled_delay).map(|_| BatchMessage::Tick);
-
-        // Spawn worker process via user-defined spawn function.
-        spawn(BatchSpanProcessorWorker {
+        let interval: Box<dyn Fn(time::Duration) -> Ticker + Send> =
+            Box::new(move |delay| Box::pin(interval(delay).map(|_| ()).fuse()));
+        let ticker = interval(config.scheduled_delay);
+        let flush_timeout = config.flush_timeout;
+        let stats = SpanProcessorStats::default();
+        let worker = Worker {
             exporter,
-            messages: Box::pin(futures::stream::select(message_receiver, ticker)),
+            messages: message_receiver,
+            ticker,
+            interval,
             config,
             buffer: Vec::new(),
+            retry: None,
+            stats: stats.clone(),
+        };
+
+        // Spawn worker process via user-defined spawn function.
+        spawn(BatchSpanProcessorWorker {
+            worker: Box::pin(worker.run()),
         });
 
         // Return batch processor with link to worker
         BatchSpanProcessor {
             message_sender: Mutex::new(message_sender),
+            flush_timeout,
+            stats,
+        }
+    }
+
+    /// A handle to the counters of this processor and its worker.
+    pub fn stats(&self) -> SpanProcessorStats {
+        self.stats.clone()
+    }
+
+    /// Send a control message to the worker, returns `false` if the worker
+    /// has already finished.
+    fn send(&self, message: BatchMessage) -> bool {
+        // A fresh sender always has a guaranteed slot in the channel, so
+        // control messages are not lost if the channel is full.
+        match self.message_sender.lock() {
+            Ok(sender) => sender.clone().try_send(message).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Send a control message to the worker and wait for its acknowledgement.
+    fn send_and_wait<F>(&self, message: F) -> api::FlushResult
+    where
+        F: FnOnce(sync_mpsc::Sender<api::FlushResult>) -> BatchMessage,
+    {
+        let (ack, result) = sync_mpsc::channel();
+        if !self.send(message(ack)) {
+            // The worker has already finished.
+            return api::FlushResult::AlreadyShutdown;
+        }
+
+        match result.recv_timeout(self.flush_timeout) {
+            Ok(result) => result,
+            Err(sync_mpsc::RecvTimeoutError::Timeout) => api::FlushResult::Timeout,
+            Err(sync_mpsc::RecvTimeoutError::Disconnected) => api::FlushResult::ExportFailed,
         }
     }
 
@@ -259,24 +581,93 @@ impl BatchSpanProcessor {
             config: Default::default(),
         }
     }
+
+    /// Create a new batch processor builder running the worker on the tokio
+    /// runtime. The processor must be built from within a runtime.
+    #[cfg(feature = "rt-tokio")]
+    pub fn tokio_builder<E>(exporter: E) -> TokioBatchSpanProcessorBuilder<E>
+    where
+        E: exporter::trace::SpanExporter,
+    {
+        BatchSpanProcessor::builder(exporter, tokio::spawn, tokio::time::interval)
+    }
+
+    /// Create a new batch processor builder running the worker on the
+    /// async-std runtime.
+    #[cfg(feature = "rt-async-std")]
+    pub fn async_std_builder<E>(exporter: E) -> AsyncStdBatchSpanProcessorBuilder<E>
+    where
+        E: exporter::trace::SpanExporter,
+    {
+        BatchSpanProcessor::builder(
+            exporter,
+            async_std::task::spawn,
+            async_std::stream::interval,
+        )
+    }
 }
 
+/// A [`BatchSpanProcessorBuilder`] spawning its worker on the tokio runtime.
+///
+/// [`BatchSpanProcessorBuilder`]: struct.BatchSpanProcessorBuilder.html
+#[cfg(feature = "rt-tokio")]
+pub type TokioBatchSpanProcessorBuilder<E> = BatchSpanProcessorBuilder<
+    E,
+    fn(BatchSpanProcessorWorker) -> tokio::task::JoinHandle<()>,
+    fn(time::Duration) -> tokio::time::Interval,
+>;
+
+/// A [`BatchSpanProcessorBuilder`] spawning its worker on the async-std
+/// runtime.
+///
+/// [`BatchSpanProcessorBuilder`]: struct.BatchSpanProcessorBuilder.html
+#[cfg(feature = "rt-async-std")]
+pub type AsyncStdBatchSpanProcessorBuilder<E> = BatchSpanProcessorBuilder<
+    E,
+    fn(BatchSpanProcessorWorker) -> async_std::task::JoinHandle<()>,
+    fn(time::Duration) -> async_std::stream::Interval,
+>;
+
 /// Batch span processor configuration
 #[derive(Debug)]
 pub struct BatchConfig {
     /// The maximum queue size to buffer spans for delayed processing. If the
     /// queue gets full it drops the spans. The default value of is 2048.
-    max_queue_size: usize,
+    pub(crate) max_queue_size: usize,
 
     /// The delay interval in milliseconds between two consecutive processing
     /// of batches. The default value is 5 seconds.
-    scheduled_delay: time::Duration,
+    pub(crate) scheduled_delay: time::Duration,
 
     /// The maximum number of spans to process in a single batch. If there are
     /// more than one batch worth of spans then it processes multiple batches
     /// of spans one batch after the other without any delay. The default value
     /// is 512.
-    max_export_batch_size: usize,
+    pub(crate) max_export_batch_size: usize,
+
+    /// The maximum duration `force_flush` and `shutdown` wait for pending
+    /// spans to be exported. The default value is 30 seconds.
+    pub(crate) flush_timeout: time::Duration,
+
+    /// The maximum duration a single export may take. Exports that time out
+    /// are dropped, counted as failed and not retried. Only exporters
+    /// implementing `SpanExporter::export_async` can be timed out. The default
+    /// value is 30 seconds.
+    export_timeout: time::Duration,
+
+    /// The maximum number of times a batch is retried after the exporter
+    /// returned `FailedRetryable`. While a batch waits for a retry, new spans
+    /// are buffered up to `max_queue_size`. The default value is 5.
+    max_export_retries: u32,
+
+    /// The backoff before the first retry. It doubles with every further
+    /// retry, and a random jitter of up to half the backoff is subtracted.
+    /// The default value is 1 second.
+    initial_retry_backoff: time::Duration,
+
+    /// The maximum backoff between two retries. The default value is 30
+    /// seconds.
+    max_retry_backoff: time::Duration,
 }
 
 impl Default for BatchConfig {
@@ -285,6 +676,11 @@ impl Default for BatchConfig {
             max_queue_size: 2048,
             scheduled_delay: time::Duration::from_secs(5),
             max_export_batch_size: 512,
+            flush_timeout: time::Duration::from_secs(30),
+            export_timeout: time::Duration::from_secs(30),
+            max_export_retries: 5,
+            initial_retry_backoff: time::Duration::from_secs(1),
+            max_retry_backoff: time::Duration::from_secs(30),
         }
     }
 }
@@ -304,7 +700,7 @@ impl<E, S, SO, I, IS, ISI> BatchSpanProcessorBuilder<E, S, I>
 where
     E: exporter::trace::SpanExporter + 'static,
     S: Fn(BatchSpanProcessorWorker) -> SO,
-    I: Fn(time::Duration) -> IS,
+    I: Fn(time::Duration) -> IS + Send + 'static,
     IS: Stream<Item = ISI> + Send + 'static,
 {
     /// Set max queue size for batches
@@ -331,6 +727,41 @@ where
         BatchSpanProcessorBuilder { config, ..self }
     }
 
+    /// Set the maximum duration `force_flush` and `shutdown` wait for pending
+    /// spans to be exported
+    pub fn with_flush_timeout(self, timeout: time::Duration) -> Self {
+        let mut config = self.config;
+        config.flush_timeout = timeout;
+
+        BatchSpanProcessorBuilder { config, ..self }
+    }
+
+    /// Set the maximum duration of a single export
+    pub fn with_export_timeout(self, timeout: time::Duration) -> Self {
+        let mut config = self.config;
+        config.export_timeout = timeout;
+
+        BatchSpanProcessorBuilder { config, ..self }
+    }
+
+    /// Set the maximum number of retries for batches failing with a retryable
+    /// error
+    pub fn with_max_export_retries(self, retries: u32) -> Self {
+        let mut config = self.config;
+        config.max_export_retries = retries;
+
+        BatchSpanProcessorBuilder { config, ..self }
+    }
+
+    /// Set the initial and maximum backoff between retries
+    pub fn with_retry_backoff(self, initial: time::Duration, max: time::Duration) -> Self {
+        let mut config = self.config;
+        config.initial_retry_backoff = initial;
+        config.max_retry_backoff = max;
+
+        BatchSpanProcessorBuilder { config, ..self }
+    }
+
     /// Build a batch processor
     pub fn build(self) -> BatchSpanProcessor {
         BatchSpanProcessor::new(
@@ -341,3 +772,342 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{Provider, Span, Tracer};
+    use crate::sdk::trace::span_processor_stats::DropReason;
+    use crate::{api, exporter, sdk};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::time;
+
+    #[derive(Debug, Default)]
+    struct Exported {
+        spans: Mutex<Vec<Arc<exporter::trace::SpanData>>>,
+        shutdown: Mutex<bool>,
+        // Results of the next exports, exports succeed once this is empty.
+        results: Mutex<VecDeque<exporter::trace::ExportResult>>,
+        // Whether the next asynchronous export never completes.
+        hang: Mutex<bool>,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct TestExporter(Arc<Exported>);
+
+    impl exporter::trace::SpanExporter for TestExporter {
+        fn export(
+            &self,
+            batch: Vec<Arc<exporter::trace::SpanData>>,
+        ) -> exporter::trace::ExportResult {
+            let result = self.0.results.lock().unwrap().pop_front();
+            let result = result.unwrap_or(exporter::trace::ExportResult::Success);
+            if result == exporter::trace::ExportResult::Success {
+                self.0.spans.lock().unwrap().extend(batch);
+            }
+            result
+        }
+
+        fn export_async(
+            &self,
+            batch: Vec<Arc<exporter::trace::SpanData>>,
+        ) -> exporter::trace::ExportFuture<'_> {
+            if std::mem::replace(&mut *self.0.hang.lock().unwrap(), false) {
+                return Box::pin(futures::future::pending());
+            }
+            Box::pin(futures::future::ready(self.export(batch)))
+        }
+
+        fn shutdown(&self) {
+            *self.0.shutdown.lock().unwrap() = true;
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    /// A synchronous exporter whose exports block until it is released.
+    #[derive(Clone, Debug, Default)]
+    struct BlockingExporter(Arc<(Mutex<bool>, Condvar)>);
+
+    impl BlockingExporter {
+        fn release(&self) {
+            let (released, condvar) = &*self.0;
+            *released.lock().unwrap() = true;
+            condvar.notify_all();
+        }
+    }
+
+    impl exporter::trace::SpanExporter for BlockingExporter {
+        fn export(
+            &self,
+            _batch: Vec<Arc<exporter::trace::SpanData>>,
+        ) -> exporter::trace::ExportResult {
+            let (released, condvar) = &*self.0;
+            let mut released = released.lock().unwrap();
+            while !*released {
+                released = condvar.wait(released).unwrap();
+            }
+            exporter::trace::ExportResult::Success
+        }
+
+        fn export_async(
+            &self,
+            batch: Vec<Arc<exporter::trace::SpanData>>,
+        ) -> exporter::trace::ExportFuture<'_> {
+            let exporter = self.clone();
+            exporter::trace::spawn_export(move || exporter.export(batch))
+        }
+
+        fn shutdown(&self) {}
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    fn batch_processor(exporter: TestExporter) -> sdk::BatchSpanProcessor {
+        sdk::BatchSpanProcessor::builder(
+            exporter,
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            |_| futures::stream::pending::<()>(),
+        )
+        .build()
+    }
+
+    /// An interval ticking from a separate thread, so that timers fire while
+    /// the worker is blocked on an executor.
+    fn thread_interval(period: time::Duration) -> impl futures::Stream<Item = ()> {
+        let (mut ticks, receiver) = futures::channel::mpsc::channel(1);
+        std::thread::spawn(move || {
+            while !ticks.is_closed() {
+                std::thread::sleep(period);
+                let _ = ticks.try_send(());
+            }
+        });
+        receiver
+    }
+
+    #[test]
+    fn batch_force_flush_exports_pending_spans() {
+        let exporter = TestExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(batch_processor(exporter.clone()))
+            .build();
+        provider.get_tracer("test").start("flushed", None).end();
+
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::Success]);
+        assert_eq!(exporter.0.spans.lock().unwrap().len(), 1);
+        assert!(!*exporter.0.shutdown.lock().unwrap());
+    }
+
+    #[test]
+    fn batch_shutdown_drains_and_shuts_down_exporter() {
+        let exporter = TestExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(batch_processor(exporter.clone()))
+            .build();
+        provider.get_tracer("test").start("drained", None).end();
+        let processors = provider.span_processors();
+
+        assert_eq!(processors[0].shutdown(), api::FlushResult::Success);
+        assert_eq!(exporter.0.spans.lock().unwrap().len(), 1);
+        assert!(*exporter.0.shutdown.lock().unwrap());
+        // The worker is gone, further calls return immediately.
+        assert_eq!(
+            processors[0].force_flush(),
+            api::FlushResult::AlreadyShutdown
+        );
+    }
+
+    #[test]
+    fn batch_stats_count_exported_and_dropped_spans() {
+        let exporter = TestExporter::default();
+        let processor = batch_processor(exporter);
+        let stats = processor.stats();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        let tracer = provider.get_tracer("test");
+        tracer.start("exported", None).end();
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::Success]);
+        provider.span_processors()[0].shutdown();
+        tracer.start("dropped", None).end();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.started, 2);
+        assert_eq!(snapshot.ended, 2);
+        assert_eq!(snapshot.queued, 1);
+        assert_eq!(snapshot.exported, 1);
+        assert_eq!(snapshot.dropped(DropReason::AfterShutdown), 1);
+        assert_eq!(snapshot.queue_depth, 0);
+    }
+
+    #[test]
+    fn batch_retries_are_bounded() {
+        let exporter = TestExporter::default();
+        exporter.0.results.lock().unwrap().extend(vec![
+            exporter::trace::ExportResult::FailedRetryable,
+            exporter::trace::ExportResult::FailedRetryable,
+            exporter::trace::ExportResult::FailedRetryable,
+        ]);
+        let processor = sdk::BatchSpanProcessor::builder(
+            exporter.clone(),
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            |_| futures::stream::pending::<()>(),
+        )
+        .with_max_export_retries(1)
+        .build();
+        let stats = processor.stats();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        let tracer = provider.get_tracer("test");
+
+        // The first attempt fails and schedules a retry, flushing retries
+        // immediately.
+        tracer.start("retried", None).end();
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::ExportFailed]);
+        assert_eq!(stats.snapshot().queue_depth, 1);
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::ExportFailed]);
+        assert_eq!(stats.snapshot().export_failed, 1);
+        assert_eq!(stats.snapshot().queue_depth, 0);
+
+        // The next batch starts over with a fresh retry budget.
+        tracer.start("exported", None).end();
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::ExportFailed]);
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::Success]);
+        let spans = exporter.0.spans.lock().unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].name, "exported");
+    }
+
+    #[test]
+    fn batch_export_timeout_cancels_export() {
+        let exporter = TestExporter::default();
+        *exporter.0.hang.lock().unwrap() = true;
+        let processor = sdk::BatchSpanProcessor::builder(
+            exporter.clone(),
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            thread_interval,
+        )
+        .with_scheduled_delay(time::Duration::from_secs(60))
+        .with_export_timeout(time::Duration::from_millis(10))
+        .build();
+        let stats = processor.stats();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        let tracer = provider.get_tracer("test");
+        tracer.start("hung", None).end();
+
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::ExportFailed]);
+        assert_eq!(stats.snapshot().export_failed, 1);
+
+        // The hung export was dropped, later exports and the shutdown proceed.
+        tracer.start("exported", None).end();
+        assert_eq!(provider.shutdown(), vec![api::FlushResult::Success]);
+        assert!(*exporter.0.shutdown.lock().unwrap());
+        let spans = exporter.0.spans.lock().unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].name, "exported");
+    }
+
+    #[test]
+    fn batch_export_timeout_cancels_blocking_export() {
+        let exporter = BlockingExporter::default();
+        let processor = sdk::BatchSpanProcessor::builder(
+            exporter.clone(),
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            thread_interval,
+        )
+        .with_scheduled_delay(time::Duration::from_secs(60))
+        .with_export_timeout(time::Duration::from_millis(10))
+        .build();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        provider.get_tracer("test").start("blocked", None).end();
+
+        // The export blocks on its own thread, the worker still times it out.
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::ExportFailed]);
+
+        exporter.release();
+        provider.get_tracer("test").start("exported", None).end();
+        assert_eq!(provider.shutdown(), vec![api::FlushResult::Success]);
+    }
+
+    #[test]
+    fn batch_retries_after_backoff() {
+        let exporter = TestExporter::default();
+        exporter
+            .0
+            .results
+            .lock()
+            .unwrap()
+            .push_back(exporter::trace::ExportResult::FailedRetryable);
+        let processor = sdk::BatchSpanProcessor::builder(
+            exporter.clone(),
+            |worker| std::thread::spawn(move || futures::executor::block_on(worker)),
+            thread_interval,
+        )
+        .with_scheduled_delay(time::Duration::from_millis(10))
+        .with_retry_backoff(
+            time::Duration::from_millis(10),
+            time::Duration::from_millis(10),
+        )
+        .build();
+        let provider = sdk::Provider::builder()
+            .with_batch_exporter(processor)
+            .build();
+        provider.get_tracer("test").start("retried", None).end();
+
+        let start = time::Instant::now();
+        while exporter.0.spans.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < time::Duration::from_secs(5));
+            std::thread::sleep(time::Duration::from_millis(5));
+        }
+    }
+
+    #[cfg(feature = "rt-tokio")]
+    #[test]
+    fn tokio_builder_exports_on_runtime() {
+        let exporter = TestExporter::default();
+        let mut runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let processor = sdk::BatchSpanProcessor::tokio_builder(exporter.clone())
+                .with_scheduled_delay(time::Duration::from_millis(10))
+                .build();
+            let provider = sdk::Provider::builder()
+                .with_batch_exporter(processor)
+                .build();
+            provider.get_tracer("test").start("tokio", None).end();
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        });
+
+        assert_eq!(exporter.0.spans.lock().unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    #[test]
+    fn async_std_builder_exports_on_runtime() {
+        let exporter = TestExporter::default();
+        async_std::task::block_on(async {
+            let processor = sdk::BatchSpanProcessor::async_std_builder(exporter.clone())
+                .with_scheduled_delay(time::Duration::from_millis(10))
+                .build();
+            let provider = sdk::Provider::builder()
+                .with_batch_exporter(processor)
+                .build();
+            provider.get_tracer("test").start("async-std", None).end();
+            async_std::task::sleep(time::Duration::from_millis(50)).await;
+        });
+
+        assert_eq!(exporter.0.spans.lock().unwrap().len(), 1);
+    }
+}
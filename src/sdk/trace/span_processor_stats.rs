@@ -0,0 +1,381 @@
+//! # Span Processor Stats
+//!
+//! Span processors keep counters about the spans passing through them, which
+//! makes it possible to see how many spans are dropped and why. The counters
+//! are shared between a processor and its background worker and can be read
+//! at any time through a [`SpanProcessorStats`] handle.
+//!
+//! With the `metrics` feature enabled, a [`StatsRecorder`] can be used to
+//! report the counters as instruments on an [`api::Meter`].
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::{api, sdk};
+//!
+//! let processor = sdk::SimpleSpanProcessor::new(Box::new(api::NoopSpanExporter {}));
+//! let stats = processor.stats();
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_span_processor(processor)
+//!     .build();
+//!
+//! let snapshot = stats.snapshot();
+//! assert_eq!(snapshot.dropped_total(), 0);
+//! ```
+//!
+//! [`SpanProcessorStats`]: struct.SpanProcessorStats.html
+//! [`StatsRecorder`]: struct.StatsRecorder.html
+//! [`api::Meter`]: ../../../api/metrics/trait.Meter.html
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time;
+
+/// Reasons for a span processor to drop a finished span.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropReason {
+    /// The channel to the background worker was full.
+    QueueFull,
+    /// The channel to the background worker was locked by another thread.
+    LockContention,
+    /// The worker's buffer already held `max_queue_size` spans.
+    BufferFull,
+    /// The span ended after the processor was shut down.
+    AfterShutdown,
+}
+
+impl DropReason {
+    /// All drop reasons, in the order used by [`StatsSnapshot`].
+    ///
+    /// [`StatsSnapshot`]: struct.StatsSnapshot.html
+    pub const ALL: [DropReason; 4] = [
+        DropReason::QueueFull,
+        DropReason::LockContention,
+        DropReason::BufferFull,
+        DropReason::AfterShutdown,
+    ];
+
+    /// The reason as a lowercase string, suitable as a label value.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DropReason::QueueFull => "queue_full",
+            DropReason::LockContention => "lock_contention",
+            DropReason::BufferFull => "buffer_full",
+            DropReason::AfterShutdown => "after_shutdown",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    started: AtomicU64,
+    ended: AtomicU64,
+    queued: AtomicU64,
+    dropped: [AtomicU64; 4],
+    exported: AtomicU64,
+    export_failed: AtomicU64,
+    export_batches: AtomicU64,
+    export_latency_nanos: AtomicU64,
+    queue_depth: AtomicU64,
+}
+
+/// A cloneable handle to the counters of a span processor.
+#[derive(Clone, Debug, Default)]
+pub struct SpanProcessorStats {
+    counters: Arc<Counters>,
+}
+
+impl SpanProcessorStats {
+    /// Read the current value of all counters.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let c = &self.counters;
+        let mut dropped = [0; 4];
+        for (count, counter) in dropped.iter_mut().zip(c.dropped.iter()) {
+            *count = counter.load(Ordering::Relaxed);
+        }
+
+        StatsSnapshot {
+            started: c.started.load(Ordering::Relaxed),
+            ended: c.ended.load(Ordering::Relaxed),
+            queued: c.queued.load(Ordering::Relaxed),
+            dropped,
+            exported: c.exported.load(Ordering::Relaxed),
+            export_failed: c.export_failed.load(Ordering::Relaxed),
+            export_batches: c.export_batches.load(Ordering::Relaxed),
+            export_latency: time::Duration::from_nanos(
+                c.export_latency_nanos.load(Ordering::Relaxed),
+            ),
+            queue_depth: c.queue_depth.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn span_started(&self) {
+        self.counters.started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn span_ended(&self) {
+        self.counters.ended.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn span_queued(&self) {
+        self.counters.queued.fetch_add(1, Ordering::Relaxed);
+        self.counters.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a dropped span. Spans dropped by the worker were queued before
+    /// and no longer count towards the queue depth.
+    pub(crate) fn span_dropped(&self, reason: DropReason) {
+        self.counters.dropped[reason.index()].fetch_add(1, Ordering::Relaxed);
+        if reason == DropReason::BufferFull {
+            self.dequeue(1);
+        }
+    }
+
+    /// Record the result of exporting a batch of `spans` spans that took
+    /// `latency`.
+    pub(crate) fn batch_exported(&self, spans: usize, success: bool, latency: time::Duration) {
+        let c = &self.counters;
+        let counter = if success {
+            &c.exported
+        } else {
+            &c.export_failed
+        };
+        counter.fetch_add(spans as u64, Ordering::Relaxed);
+        c.export_batches.fetch_add(1, Ordering::Relaxed);
+        c.export_latency_nanos
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Remove `spans` exported spans from the queue depth.
+    pub(crate) fn dequeue(&self, spans: usize) {
+        let _ =
+            self.counters
+                .queue_depth
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| {
+                    Some(depth.saturating_sub(spans as u64))
+                });
+    }
+}
+
+/// The values of a processor's counters at a point in time.
+///
+/// All values except `queue_depth` are totals since the processor was
+/// created.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StatsSnapshot {
+    /// Spans passed to `on_start`.
+    pub started: u64,
+    /// Spans passed to `on_end`.
+    pub ended: u64,
+    /// Spans handed over to the background worker.
+    pub queued: u64,
+    dropped: [u64; 4],
+    /// Spans successfully exported.
+    pub exported: u64,
+    /// Spans the exporter failed to export.
+    pub export_failed: u64,
+    /// Calls to the exporter.
+    pub export_batches: u64,
+    /// Time spent in the exporter.
+    pub export_latency: time::Duration,
+    /// Spans queued or buffered but not exported yet.
+    pub queue_depth: u64,
+}
+
+impl StatsSnapshot {
+    /// Spans dropped for the given reason.
+    pub fn dropped(&self, reason: DropReason) -> u64 {
+        self.dropped[reason.index()]
+    }
+
+    /// Spans dropped for any reason.
+    pub fn dropped_total(&self) -> u64 {
+        self.dropped.iter().sum()
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub use recorder::StatsRecorder;
+
+#[cfg(feature = "metrics")]
+mod recorder {
+    use super::{DropReason, SpanProcessorStats, StatsSnapshot};
+    use crate::api::{self, Counter, Gauge, Measure};
+    use std::fmt;
+    use std::sync::Mutex;
+
+    /// Reports the counters of a span processor as metric instruments.
+    ///
+    /// Instruments are created once in `new`, each call to `record` adds the
+    /// change since the previous call. Call it periodically, e.g. before
+    /// metrics are collected.
+    pub struct StatsRecorder<M: api::Meter> {
+        stats: SpanProcessorStats,
+        last: Mutex<StatsSnapshot>,
+        no_labels: M::LabelSet,
+        reason_labels: Vec<M::LabelSet>,
+        started: M::I64Counter,
+        ended: M::I64Counter,
+        queued: M::I64Counter,
+        dropped: M::I64Counter,
+        exported: M::I64Counter,
+        export_failed: M::I64Counter,
+        export_latency: M::F64Measure,
+        queue_depth: M::I64Gauge,
+    }
+
+    impl<M: api::Meter> StatsRecorder<M> {
+        /// Create the instruments on `meter` for the processor owning `stats`.
+        pub fn new(meter: &M, stats: SpanProcessorStats) -> Self {
+            let reason = api::Key::new("reason");
+            let counter = |name: &str, description: &str| {
+                meter.new_i64_counter(
+                    name,
+                    api::MetricOptions::default().with_description(description),
+                )
+            };
+
+            StatsRecorder {
+                last: Mutex::new(StatsSnapshot::default()),
+                no_labels: meter.labels(Vec::new()),
+                reason_labels: DropReason::ALL
+                    .iter()
+                    .map(|r| meter.labels(vec![reason.string(r.as_str())]))
+                    .collect(),
+                started: counter("spans_started", "Spans started"),
+                ended: counter("spans_ended", "Spans ended"),
+                queued: counter("spans_queued", "Spans queued for export"),
+                dropped: meter.new_i64_counter(
+                    "spans_dropped",
+                    api::MetricOptions::default()
+                        .with_description("Spans dropped before export")
+                        .with_keys(vec![reason.clone()]),
+                ),
+                exported: counter("spans_exported", "Spans exported"),
+                export_failed: counter("spans_export_failed", "Spans that failed to export"),
+                export_latency: meter.new_f64_measure(
+                    "export_latency",
+                    api::MetricOptions::default()
+                        .with_description("Average export call latency in seconds"),
+                ),
+                queue_depth: meter.new_i64_gauge(
+                    "queue_depth",
+                    api::MetricOptions::default().with_description("Spans waiting to be exported"),
+                ),
+                stats,
+            }
+        }
+
+        /// Record the change of all counters since the last call.
+        pub fn record(&self) {
+            let current = self.stats.snapshot();
+            let mut last = match self.last.lock() {
+                Ok(last) => last,
+                Err(_) => return,
+            };
+            let delta = |now: u64, then: u64| now.saturating_sub(then) as i64;
+
+            self.started
+                .add(delta(current.started, last.started), &self.no_labels);
+            self.ended
+                .add(delta(current.ended, last.ended), &self.no_labels);
+            self.queued
+                .add(delta(current.queued, last.queued), &self.no_labels);
+            for (reason, labels) in DropReason::ALL.iter().zip(self.reason_labels.iter()) {
+                self.dropped.add(
+                    delta(current.dropped(*reason), last.dropped(*reason)),
+                    labels,
+                );
+            }
+            self.exported
+                .add(delta(current.exported, last.exported), &self.no_labels);
+            self.export_failed.add(
+                delta(current.export_failed, last.export_failed),
+                &self.no_labels,
+            );
+            let batches = current.export_batches.saturating_sub(last.export_batches);
+            if batches > 0 {
+                let latency = current
+                    .export_latency
+                    .checked_sub(last.export_latency)
+                    .unwrap_or_default();
+                self.export_latency
+                    .record(latency.as_secs_f64() / batches as f64, &self.no_labels);
+            }
+            self.queue_depth
+                .set(current.queue_depth as i64, &self.no_labels);
+
+            *last = current;
+        }
+    }
+
+    impl<M: api::Meter> fmt::Debug for StatsRecorder<M> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("StatsRecorder")
+                .field("stats", &self.stats)
+                .finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropReason, SpanProcessorStats};
+    use std::time;
+
+    #[test]
+    fn snapshot_tracks_queue_depth() {
+        let stats = SpanProcessorStats::default();
+        for _ in 0..3 {
+            stats.span_ended();
+            stats.span_queued();
+        }
+        stats.span_dropped(DropReason::BufferFull);
+        stats.span_dropped(DropReason::QueueFull);
+        stats.batch_exported(2, true, time::Duration::from_millis(5));
+        stats.dequeue(2);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.ended, 3);
+        assert_eq!(snapshot.queued, 3);
+        assert_eq!(snapshot.dropped(DropReason::BufferFull), 1);
+        assert_eq!(snapshot.dropped_total(), 2);
+        assert_eq!(snapshot.exported, 2);
+        assert_eq!(snapshot.export_batches, 1);
+        assert_eq!(snapshot.export_latency, time::Duration::from_millis(5));
+        assert_eq!(snapshot.queue_depth, 0);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn recorder_reports_deltas() {
+        use super::StatsRecorder;
+        use crate::sdk;
+        use crate::sdk::metrics::{Aggregation, Number};
+
+        let stats = SpanProcessorStats::default();
+        let meter = sdk::Meter::new("stats_recorder_test");
+        let recorder = StatsRecorder::new(&meter, stats.clone());
+        let started = || {
+            meter
+                .accumulator()
+                .collect()
+                .into_iter()
+                .find(|record| record.descriptor().name() == "spans_started")
+                .map(|record| record.aggregation().clone())
+        };
+
+        stats.span_started();
+        stats.span_started();
+        recorder.record();
+        assert_eq!(started(), Some(Aggregation::Sum(Number::I64(2))));
+
+        stats.span_started();
+        recorder.record();
+        assert_eq!(started(), Some(Aggregation::Sum(Number::I64(3))));
+    }
+}
@@ -0,0 +1,454 @@
+//! # Thread Batch Span Processor
+//!
+//! The [`ThreadBatchSpanProcessor`] batches finished spans like the
+//! [`BatchSpanProcessor`], but runs its worker on a dedicated OS thread
+//! instead of an async runtime. This allows applications without an executor
+//! to export spans in the background.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::{api, sdk};
+//! use opentelemetry::sdk::trace::thread_span_processor::ThreadBatchSpanProcessor;
+//! use std::time::Duration;
+//!
+//! let exporter = api::NoopSpanExporter {};
+//! let processor = ThreadBatchSpanProcessor::builder(exporter)
+//!     .with_scheduled_delay(Duration::from_secs(1))
+//!     .build();
+//!
+//! let provider = sdk::Provider::builder()
+//!     .with_span_processor(processor)
+//!     .build();
+//! ```
+//!
+//! [`ThreadBatchSpanProcessor`]: struct.ThreadBatchSpanProcessor.html
+//! [`BatchSpanProcessor`]: ../span_processor/struct.BatchSpanProcessor.html
+use crate::sdk::trace::span_processor::BatchConfig;
+use crate::sdk::trace::span_processor_stats::{DropReason, SpanProcessorStats};
+use crate::{api, exporter};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time;
+
+/// A [`SpanProcessor`] that buffers finished spans and exports them in
+/// batches from a dedicated thread.
+///
+/// Spans are exported every `scheduled_delay`, or as soon as
+/// `max_export_batch_size` spans are queued. Spans ending while
+/// `max_queue_size` spans are queued are dropped. Export timeouts and retries
+/// are not supported, the exporter is called directly on the worker thread.
+///
+/// If the worker thread cannot be spawned, spans are exported synchronously
+/// when they end, like the [`SimpleSpanProcessor`] does.
+///
+/// [`SpanProcessor`]: ../../../api/trace/span_processor/trait.SpanProcessor.html
+/// [`SimpleSpanProcessor`]: ../span_processor/struct.SimpleSpanProcessor.html
+#[derive(Debug)]
+pub struct ThreadBatchSpanProcessor {
+    shared: Arc<Shared>,
+    worker: Mutex<Option<thread::JoinHandle<()>>>,
+    // Exports on the calling thread if the worker thread could not be spawned
+    fallback: Option<Worker>,
+    max_queue_size: usize,
+    max_export_batch_size: usize,
+    flush_timeout: time::Duration,
+    stats: SpanProcessorStats,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: Vec<Arc<exporter::trace::SpanData>>,
+    acks: Vec<mpsc::Sender<api::FlushResult>>,
+    shutdown: bool,
+}
+
+impl ThreadBatchSpanProcessor {
+    /// Create a new thread batch processor builder
+    pub fn builder<E>(exporter: E) -> ThreadBatchSpanProcessorBuilder<E>
+    where
+        E: exporter::trace::SpanExporter + 'static,
+    {
+        ThreadBatchSpanProcessorBuilder {
+            exporter,
+            config: Default::default(),
+        }
+    }
+
+    fn new(exporter: Box<dyn exporter::trace::SpanExporter>, config: BatchConfig) -> Self {
+        let shared = Arc::new(Shared::default());
+        let stats = SpanProcessorStats::default();
+        let max_queue_size = config.max_queue_size;
+        let max_export_batch_size = config.max_export_batch_size;
+        let flush_timeout = config.flush_timeout;
+        let worker = Worker {
+            shared: shared.clone(),
+            exporter,
+            config,
+            stats: stats.clone(),
+        };
+        // The worker is handed over through a slot, so that it can be taken
+        // back if the thread cannot be spawned.
+        let slot = Arc::new(Mutex::new(Some(worker)));
+        let thread_slot = slot.clone();
+        let spawned = thread::Builder::new()
+            .name("opentelemetry-batch-span-processor".to_string())
+            .spawn(move || {
+                if let Some(worker) = thread_slot.lock().ok().and_then(|mut w| w.take()) {
+                    worker.run()
+                }
+            });
+        let (worker, fallback) = match spawned {
+            Ok(worker) => (Some(worker), None),
+            Err(_) => (None, slot.lock().ok().and_then(|mut w| w.take())),
+        };
+
+        ThreadBatchSpanProcessor {
+            shared,
+            worker: Mutex::new(worker),
+            fallback,
+            max_queue_size,
+            max_export_batch_size,
+            flush_timeout,
+            stats,
+        }
+    }
+
+    /// A handle to the counters of this processor and its worker.
+    pub fn stats(&self) -> SpanProcessorStats {
+        self.stats.clone()
+    }
+
+    /// Ask the worker to export all queued spans and wait for its
+    /// acknowledgement.
+    fn request_export(&self, shutdown: bool) -> api::FlushResult {
+        if let Some(worker) = &self.fallback {
+            return self.export_inline(worker, shutdown);
+        }
+        let (ack, result) = mpsc::channel();
+        match self.shared.state.lock() {
+            Ok(mut state) if !state.shutdown => {
+                state.acks.push(ack);
+                state.shutdown = shutdown;
+            }
+            // The worker has already finished.
+            _ => return api::FlushResult::AlreadyShutdown,
+        }
+        self.shared.condvar.notify_one();
+
+        match result.recv_timeout(self.flush_timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => api::FlushResult::Timeout,
+            Err(mpsc::RecvTimeoutError::Disconnected) => api::FlushResult::ExportFailed,
+        }
+    }
+
+    /// Export all queued spans on the calling thread, used when the worker
+    /// thread could not be spawned.
+    fn export_inline(&self, worker: &Worker, shutdown: bool) -> api::FlushResult {
+        let spans = match self.shared.state.lock() {
+            Ok(mut state) if !state.shutdown => {
+                state.shutdown = shutdown;
+                std::mem::take(&mut state.queue)
+            }
+            _ => return api::FlushResult::AlreadyShutdown,
+        };
+        let result = worker.export(spans);
+        if shutdown {
+            worker.exporter.shutdown();
+        }
+
+        result
+    }
+}
+
+impl api::SpanProcessor for ThreadBatchSpanProcessor {
+    fn on_start(&self, _span: &mut exporter::trace::SpanData) {
+        self.stats.span_started();
+    }
+
+    fn on_end(&self, span: Arc<exporter::trace::SpanData>) {
+        self.stats.span_ended();
+        let mut state = match self.shared.state.lock() {
+            Ok(state) => state,
+            Err(_) => return self.stats.span_dropped(DropReason::LockContention),
+        };
+        if state.shutdown {
+            self.stats.span_dropped(DropReason::AfterShutdown);
+        } else if state.queue.len() >= self.max_queue_size {
+            self.stats.span_dropped(DropReason::QueueFull);
+        } else {
+            state.queue.push(span);
+            self.stats.span_queued();
+            if let Some(worker) = &self.fallback {
+                drop(state);
+                self.export_inline(worker, false);
+            } else if state.queue.len() == self.max_export_batch_size {
+                // Wake the worker early once a full batch is available.
+                self.shared.condvar.notify_one();
+            }
+        }
+    }
+
+    fn force_flush(&self) -> api::FlushResult {
+        self.request_export(false)
+    }
+
+    /// Export all queued spans, shut down the exporter and join the worker
+    /// thread. The thread is not joined if it does not finish within the
+    /// flush timeout.
+    fn shutdown(&self) -> api::FlushResult {
+        let result = self.request_export(true);
+        if result != api::FlushResult::Timeout {
+            if let Some(worker) = self.worker.lock().ok().and_then(|mut w| w.take()) {
+                let _ = worker.join();
+            }
+        }
+
+        result
+    }
+
+    /// Lets the worker export the remaining spans and stop without waiting
+    /// for it.
+    fn begin_shutdown(&self) {
+        if let Some(worker) = &self.fallback {
+            self.export_inline(worker, true);
+            return;
+        }
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.shutdown = true;
+        }
+        self.shared.condvar.notify_one();
+    }
+}
+
+impl Drop for ThreadBatchSpanProcessor {
+    fn drop(&mut self) {
+        api::SpanProcessor::begin_shutdown(self);
+    }
+}
+
+/// The worker exporting spans on the processor's thread.
+#[derive(Debug)]
+struct Worker {
+    shared: Arc<Shared>,
+    exporter: Box<dyn exporter::trace::SpanExporter>,
+    config: BatchConfig,
+    stats: SpanProcessorStats,
+}
+
+impl Worker {
+    fn run(self) {
+        let mut deadline = time::Instant::now() + self.config.scheduled_delay;
+        loop {
+            let (spans, acks, shutdown) = match self.wait(deadline) {
+                Some(work) => work,
+                None => return,
+            };
+            let now = time::Instant::now();
+            if now >= deadline {
+                deadline = now + self.config.scheduled_delay;
+            }
+
+            let result = self.export(spans);
+            if shutdown {
+                self.exporter.shutdown();
+            }
+            for ack in acks {
+                let _ = ack.send(result);
+            }
+            if shutdown {
+                return;
+            }
+        }
+    }
+
+    /// Wait until the deadline passed, a full batch is queued or an export
+    /// was requested, then take the queued spans and pending requests.
+    #[allow(clippy::type_complexity)]
+    fn wait(
+        &self,
+        deadline: time::Instant,
+    ) -> Option<(
+        Vec<Arc<exporter::trace::SpanData>>,
+        Vec<mpsc::Sender<api::FlushResult>>,
+        bool,
+    )> {
+        let mut state = self.shared.state.lock().ok()?;
+        loop {
+            let now = time::Instant::now();
+            if state.shutdown
+                || !state.acks.is_empty()
+                || state.queue.len() >= self.config.max_export_batch_size
+                || now >= deadline
+            {
+                break;
+            }
+            state = self
+                .shared
+                .condvar
+                .wait_timeout(state, deadline - now)
+                .ok()?
+                .0;
+        }
+
+        Some((
+            std::mem::take(&mut state.queue),
+            std::mem::take(&mut state.acks),
+            state.shutdown,
+        ))
+    }
+
+    /// Export spans in batches of at most `max_export_batch_size`.
+    fn export(&self, mut spans: Vec<Arc<exporter::trace::SpanData>>) -> api::FlushResult {
+        let mut result = api::FlushResult::Success;
+        while !spans.is_empty() {
+            let batch_idx = spans
+                .len()
+                .saturating_sub(self.config.max_export_batch_size);
+            let batch = spans.split_off(batch_idx);
+            let batch_len = batch.len();
+            let start = time::Instant::now();
+            let success = self.exporter.export(batch) == exporter::trace::ExportResult::Success;
+            self.stats
+                .batch_exported(batch_len, success, start.elapsed());
+            self.stats.dequeue(batch_len);
+            if !success {
+                result = api::FlushResult::ExportFailed;
+            }
+        }
+
+        result
+    }
+}
+
+/// A builder for creating [`ThreadBatchSpanProcessor`] instances.
+///
+/// [`ThreadBatchSpanProcessor`]: struct.ThreadBatchSpanProcessor.html
+#[derive(Debug)]
+pub struct ThreadBatchSpanProcessorBuilder<E> {
+    exporter: E,
+    config: BatchConfig,
+}
+
+impl<E> ThreadBatchSpanProcessorBuilder<E>
+where
+    E: exporter::trace::SpanExporter + 'static,
+{
+    /// Set max queue size for batches
+    pub fn with_max_queue_size(mut self, size: usize) -> Self {
+        self.config.max_queue_size = size;
+        self
+    }
+
+    /// Set scheduled delay for batches
+    pub fn with_scheduled_delay(mut self, delay: time::Duration) -> Self {
+        self.config.scheduled_delay = delay;
+        self
+    }
+
+    /// Set max export size for batches
+    pub fn with_max_export_batch_size(mut self, size: usize) -> Self {
+        self.config.max_export_batch_size = size;
+        self
+    }
+
+    /// Set the maximum duration `force_flush` and `shutdown` wait for pending
+    /// spans to be exported
+    pub fn with_flush_timeout(mut self, timeout: time::Duration) -> Self {
+        self.config.flush_timeout = timeout;
+        self
+    }
+
+    /// Build a thread batch processor and spawn its worker thread
+    pub fn build(self) -> ThreadBatchSpanProcessor {
+        ThreadBatchSpanProcessor::new(Box::new(self.exporter), self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThreadBatchSpanProcessor;
+    use crate::api::{Provider, Span, Tracer};
+    use crate::sdk::trace::span_processor_stats::DropReason;
+    use crate::{api, exporter, sdk};
+    use std::sync::{Arc, Mutex};
+    use std::time;
+
+    #[derive(Clone, Debug, Default)]
+    struct TestExporter {
+        batches: Arc<Mutex<Vec<usize>>>,
+        shutdown: Arc<Mutex<bool>>,
+    }
+
+    impl exporter::trace::SpanExporter for TestExporter {
+        fn export(
+            &self,
+            batch: Vec<Arc<exporter::trace::SpanData>>,
+        ) -> exporter::trace::ExportResult {
+            self.batches.lock().unwrap().push(batch.len());
+            exporter::trace::ExportResult::Success
+        }
+
+        fn shutdown(&self) {
+            *self.shutdown.lock().unwrap() = true;
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn exports_full_batches_flushes_and_shuts_down() {
+        let exporter = TestExporter::default();
+        let processor = ThreadBatchSpanProcessor::builder(exporter.clone())
+            .with_scheduled_delay(time::Duration::from_secs(3600))
+            .with_max_export_batch_size(2)
+            .build();
+        let stats = processor.stats();
+        let provider = sdk::Provider::builder()
+            .with_span_processor(processor)
+            .build();
+        let tracer = provider.get_tracer("test");
+
+        tracer.start("first", None).end();
+        tracer.start("second", None).end();
+        tracer.start("third", None).end();
+        assert_eq!(provider.force_flush(), vec![api::FlushResult::Success]);
+        let batches = exporter.batches.lock().unwrap().clone();
+        assert_eq!(batches.iter().sum::<usize>(), 3);
+        assert!(batches.iter().all(|len| *len <= 2));
+
+        let processors = provider.span_processors();
+        assert_eq!(processors[0].shutdown(), api::FlushResult::Success);
+        assert!(*exporter.shutdown.lock().unwrap());
+        tracer.start("late", None).end();
+        assert_eq!(stats.snapshot().dropped(DropReason::AfterShutdown), 1);
+        assert_eq!(processors[0].shutdown(), api::FlushResult::AlreadyShutdown);
+    }
+
+    #[test]
+    fn exports_after_scheduled_delay() {
+        let exporter = TestExporter::default();
+        let processor = ThreadBatchSpanProcessor::builder(exporter.clone())
+            .with_scheduled_delay(time::Duration::from_millis(10))
+            .build();
+        let provider = sdk::Provider::builder()
+            .with_span_processor(processor)
+            .build();
+        provider.get_tracer("test").start("delayed", None).end();
+
+        let start = time::Instant::now();
+        while exporter.batches.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < time::Duration::from_secs(5));
+            std::thread::sleep(time::Duration::from_millis(5));
+        }
+    }
+}
@@ -10,10 +10,10 @@
 use crate::api::trace::span::Span;
 use crate::sdk;
 use crate::{api, exporter};
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashSet;
 use std::fmt;
-use std::sync::Arc;
 use std::time::SystemTime;
 
 /// `Tracer` implementation to create and manage spans
@@ -46,6 +46,7 @@ impl Tracer {
     #[allow(clippy::too_many_arguments)]
     fn make_sampling_decision(
         &self,
+        sampler: &dyn api::Sampler,
         parent_context: Option<&api::SpanContext>,
         trace_id: api::TraceId,
         span_id: api::SpanId,
@@ -54,7 +55,6 @@ impl Tracer {
         attributes: &[api::KeyValue],
         links: &[api::Link],
     ) -> Option<(u8, Vec<api::KeyValue>)> {
-        let sampler = &self.provider.config().default_sampler;
         match sampler.should_sample(
             parent_context,
             trace_id,
@@ -84,67 +84,25 @@ impl Tracer {
             }
         }
     }
-}
-
-thread_local! {
-    /// Track currently active `Span` per thread via a `SpanStack`.
-    static CURRENT_SPANS: RefCell<SpanStack> = RefCell::new(SpanStack::new());
-}
-
-impl api::Tracer for Tracer {
-    /// This implementation of `api::Tracer` produces `sdk::Span` instances.
-    type Span = sdk::Span;
-
-    /// Returns a span with an inactive `SpanContext`. Used by functions that
-    /// need to return a default span like `get_active_span` if no span is present.
-    fn invalid(&self) -> Self::Span {
-        sdk::Span::new(api::SpanId::invalid(), None, self.clone())
-    }
-
-    /// Starts a new `Span`.
-    ///
-    /// Each span has zero or one parent spans and zero or more child spans, which
-    /// represent causally related operations. A tree of related spans comprises a
-    /// trace. A span is said to be a _root span_ if it does not have a parent. Each
-    /// trace includes a single root span, which is the shared ancestor of all other
-    /// spans in the trace.
-    fn start(&self, name: &str, parent_span: Option<api::SpanContext>) -> Self::Span {
-        let mut builder = self.span_builder(name);
-        builder.parent_context = parent_span;
-
-        self.build(builder)
-    }
-
-    /// Creates a span builder
-    ///
-    /// An ergonomic way for attributes to be configured before the `Span` is started.
-    fn span_builder(&self, name: &str) -> api::SpanBuilder {
-        api::SpanBuilder::from_name(name.to_string())
-    }
 
-    /// Starts a span from a `SpanBuilder`.
-    ///
-    /// Each span has zero or one parent spans and zero or more child spans, which
-    /// represent causally related operations. A tree of related spans comprises a
-    /// trace. A span is said to be a _root span_ if it does not have a parent. Each
-    /// trace includes a single root span, which is the shared ancestor of all other
-    /// spans in the trace.
-    fn build(&self, mut builder: api::SpanBuilder) -> Self::Span {
-        let config = self.provider.config();
+    /// Starts a span from a builder and a separate name, which is only
+    /// turned into an owned `String` if the span is recording.
+    fn build_span(&self, mut builder: api::SpanBuilder, name: Cow<'_, str>) -> sdk::Span {
+        let config = self.provider.load_config();
         let span_id = builder
             .span_id
             .take()
-            .unwrap_or_else(|| self.provider().config().id_generator.new_span_id());
+            .unwrap_or_else(|| config.id_generator.new_span_id());
 
         let span_kind = builder.span_kind.take().unwrap_or(api::SpanKind::Internal);
-        let mut attribute_options = builder.attributes.take().unwrap_or_else(Vec::new);
-        let mut link_options = builder.links.take().unwrap_or_else(Vec::new);
+        let mut attribute_options = builder.attributes.take().unwrap_or_default();
+        let link_options = builder.links.take().unwrap_or_default();
 
         // Build context for sampling decision
         let (no_parent, trace_id, parent_span_id, remote_parent, parent_trace_flags) = builder
             .parent_context
             .clone()
-            .or_else(|| Some(self.get_active_span().get_context()))
+            .or_else(|| Some(active_span_context()))
             .filter(|ctx| ctx.is_valid())
             .map(|ctx| {
                 (
@@ -159,7 +117,7 @@ impl api::Tracer for Tracer {
                 true,
                 builder
                     .trace_id
-                    .unwrap_or_else(|| self.provider().config().id_generator.new_trace_id()),
+                    .unwrap_or_else(|| config.id_generator.new_trace_id()),
                 api::SpanId::invalid(),
                 false,
                 0,
@@ -168,10 +126,11 @@ impl api::Tracer for Tracer {
         // Make new sampling decision or use parent sampling decision
         let sampling_decision = if no_parent || remote_parent {
             self.make_sampling_decision(
+                config.default_sampler.as_ref(),
                 builder.parent_context.as_ref(),
                 trace_id,
                 span_id,
-                &builder.name,
+                &name,
                 &span_kind,
                 &attribute_options,
                 &link_options,
@@ -180,30 +139,32 @@ impl api::Tracer for Tracer {
             Some((parent_trace_flags, Vec::new()))
         };
 
-        // Build optional inner context, `None` if not recording.
+        // Build optional inner context, `None` if not recording, in which case
+        // nothing is allocated and no builder field is cloned.
+        let limits = config.span_limits();
         let inner = sampling_decision.map(move |(trace_flags, mut extra_attrs)| {
             attribute_options.append(&mut extra_attrs);
             let mut attributes = sdk::EvictedHashMap::new(config.max_attributes_per_span);
             for attribute in attribute_options {
-                attributes.insert(attribute);
+                attributes.insert(limits.attribute(attribute));
             }
             let mut links = sdk::EvictedQueue::new(config.max_links_per_span);
-            links.append_vec(&mut link_options);
+            links.extend(link_options.into_iter().map(|link| limits.link(link)));
             let start_time = builder.start_time.unwrap_or_else(SystemTime::now);
             let end_time = builder.end_time.unwrap_or(start_time);
             let mut message_events = sdk::EvictedQueue::new(config.max_events_per_span);
-            if let Some(mut events) = builder.message_events {
-                message_events.append_vec(&mut events);
+            if let Some(events) = builder.message_events {
+                message_events.extend(events.into_iter().map(|event| limits.event(event)));
             }
             let status_code = builder.status_code.unwrap_or(api::StatusCode::OK);
-            let status_message = builder.status_message.unwrap_or_else(String::new);
+            let status_message = builder.status_message.unwrap_or_default();
             let resource = config.resource.clone();
 
             exporter::trace::SpanData {
                 context: api::SpanContext::new(trace_id, span_id, trace_flags, false),
                 parent_span_id,
                 span_kind,
-                name: builder.name,
+                name: name.into_owned(),
                 start_time,
                 end_time,
                 attributes,
@@ -215,15 +176,87 @@ impl api::Tracer for Tracer {
             }
         });
 
-        // Call `on_start` for all processors
-        if let Some(inner) = inner.as_ref().cloned() {
-            let inner_data = Arc::new(inner);
-            for processor in self.provider.span_processors() {
-                processor.on_start(inner_data.clone())
+        // Call `on_start` for all processors, they may modify the span data.
+        // The span ends on the same processors.
+        let inner = inner.map(|mut data| {
+            let processors = self.provider.span_processors();
+            for processor in processors.iter() {
+                processor.on_start(&mut data)
             }
-        }
+            (data, processors)
+        });
+
+        sdk::Span::new(span_id, inner, limits)
+    }
+}
+
+thread_local! {
+    /// Track currently active `Span` per thread via a `SpanStack`.
+    static CURRENT_SPANS: RefCell<SpanStack> = RefCell::new(SpanStack::new());
+}
+
+/// Context of the current active span, without cloning the span.
+fn active_span_context() -> api::SpanContext {
+    CURRENT_SPANS
+        .with(|spans| spans.borrow().current_context())
+        .unwrap_or_else(api::SpanContext::empty_context)
+}
+
+/// Mark a given `Span` as active on the current thread.
+pub(crate) fn mark_span_as_active(span: &sdk::Span) {
+    CURRENT_SPANS.with(|spans| {
+        spans.borrow_mut().push(span.clone());
+    })
+}
+
+/// Mark a given `Span` as inactive on the current thread.
+pub(crate) fn mark_span_as_inactive(span_id: api::SpanId) {
+    CURRENT_SPANS.with(|spans| {
+        spans.borrow_mut().pop(span_id);
+    })
+}
 
-        sdk::Span::new(span_id, inner, self.clone())
+impl api::Tracer for Tracer {
+    /// This implementation of `api::Tracer` produces `sdk::Span` instances.
+    type Span = sdk::Span;
+
+    /// Returns a span with an inactive `SpanContext`. Used by functions that
+    /// need to return a default span like `get_active_span` if no span is present.
+    fn invalid(&self) -> Self::Span {
+        sdk::Span::non_recording(api::SpanId::invalid())
+    }
+
+    /// Starts a new `Span`.
+    ///
+    /// Each span has zero or one parent spans and zero or more child spans, which
+    /// represent causally related operations. A tree of related spans comprises a
+    /// trace. A span is said to be a _root span_ if it does not have a parent. Each
+    /// trace includes a single root span, which is the shared ancestor of all other
+    /// spans in the trace.
+    fn start(&self, name: &str, parent_span: Option<api::SpanContext>) -> Self::Span {
+        let mut builder = api::SpanBuilder::from_name(String::new());
+        builder.parent_context = parent_span;
+
+        self.build_span(builder, Cow::Borrowed(name))
+    }
+
+    /// Creates a span builder
+    ///
+    /// An ergonomic way for attributes to be configured before the `Span` is started.
+    fn span_builder(&self, name: &str) -> api::SpanBuilder {
+        api::SpanBuilder::from_name(name.to_string())
+    }
+
+    /// Starts a span from a `SpanBuilder`.
+    ///
+    /// Each span has zero or one parent spans and zero or more child spans, which
+    /// represent causally related operations. A tree of related spans comprises a
+    /// trace. A span is said to be a _root span_ if it does not have a parent. Each
+    /// trace includes a single root span, which is the shared ancestor of all other
+    /// spans in the trace.
+    fn build(&self, mut builder: api::SpanBuilder) -> Self::Span {
+        let name = std::mem::take(&mut builder.name);
+        self.build_span(builder, Cow::Owned(name))
     }
 
     /// Returns the current active span.
@@ -238,16 +271,12 @@ impl api::Tracer for Tracer {
 
     /// Mark a given `Span` as active.
     fn mark_span_as_active(&self, span: &Self::Span) {
-        CURRENT_SPANS.with(|spans| {
-            spans.borrow_mut().push(span.clone());
-        })
+        mark_span_as_active(span)
     }
 
     /// Mark a given `Span` as inactive.
     fn mark_span_as_inactive(&self, span_id: api::SpanId) {
-        CURRENT_SPANS.with(|spans| {
-            spans.borrow_mut().pop(span_id);
-        })
+        mark_span_as_inactive(span_id)
     }
 
     /// Clone span
@@ -308,4 +337,105 @@ impl SpanStack {
             .find(|context_id| !context_id.duplicate)
             .map(|context_id| context_id.span.clone())
     }
+
+    /// Context of the span returned by `current`, without cloning it
+    #[inline]
+    fn current_context(&self) -> Option<api::SpanContext> {
+        self.stack
+            .iter()
+            .rev()
+            .find(|context_id| !context_id.duplicate)
+            .map(|context_id| context_id.span.get_context())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{Key, Provider, Span, Tracer};
+    use crate::exporter::trace::in_memory::InMemorySpanExporter;
+    use crate::{api, sdk};
+
+    #[test]
+    fn non_recording_spans_are_not_exported() {
+        let exporter = InMemorySpanExporter::default();
+        let tracer = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(sdk::Config {
+                default_sampler: Box::new(sdk::Sampler::Never),
+                ..Default::default()
+            })
+            .build()
+            .get_tracer("test");
+
+        let span = tracer.start("unsampled", None);
+        assert!(!span.is_recording());
+        assert!(!span.get_context().is_valid());
+        span.set_attribute(Key::new("ignored").bool(true));
+        drop(span);
+
+        assert!(exporter.get_finished_spans().is_empty());
+    }
+
+    #[test]
+    fn active_non_recording_span() {
+        let tracer = sdk::Provider::default().get_tracer("test");
+        let invalid = tracer.invalid();
+        assert!(!invalid.is_recording());
+
+        let span = tracer.start("active", None);
+        span.mark_as_active();
+        assert_eq!(tracer.get_active_span().get_context(), span.get_context());
+        invalid.mark_as_active();
+        assert!(!tracer.get_active_span().get_context().is_valid());
+        invalid.mark_as_inactive();
+        span.mark_as_inactive();
+        assert!(!tracer.get_active_span().is_recording());
+    }
+
+    #[test]
+    fn applies_span_limits() {
+        let exporter = InMemorySpanExporter::default();
+        let tracer = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .with_config(sdk::Config {
+                max_attributes_per_span: 1,
+                max_events_per_span: 1,
+                max_attributes_per_event: 1,
+                max_attributes_per_link: 1,
+                max_attribute_value_length: Some(2),
+                ..Default::default()
+            })
+            .build()
+            .get_tracer("test");
+
+        let link_attributes = vec![Key::new("a").i64(1), Key::new("b").i64(2)];
+        let span = tracer
+            .span_builder("limited")
+            .with_links(vec![api::Link::new(
+                api::SpanContext::empty_context(),
+                link_attributes.clone(),
+            )])
+            .start(&tracer);
+        span.set_attribute(Key::new("first").string("value"));
+        span.set_attribute(Key::new("second").string("value"));
+        span.add_event("first".to_string(), Vec::new());
+        span.add_event("second".to_string(), link_attributes);
+        drop(span);
+
+        let spans = exporter.get_finished_spans();
+        let span = &spans[0];
+        assert_eq!(span.dropped_attributes_count(), 1);
+        assert_eq!(span.dropped_events_count(), 1);
+        assert_eq!(span.dropped_links_count(), 0);
+        assert_eq!(
+            span.attributes.get(&Key::new("second")),
+            Some(&api::Value::from("va"))
+        );
+        let event = span.message_events.iter().next().unwrap();
+        assert_eq!(event.name, "second");
+        assert_eq!(event.dropped_attributes_count, 1);
+        let link = span.links.iter().next().unwrap();
+        assert_eq!(link.attributes().len(), 1);
+        assert_eq!(link.dropped_attributes_count(), 1);
+    }
 }
@@ -0,0 +1,5 @@
+//! # Testing Utilities
+//!
+//! Helpers for testing code instrumented with OpenTelemetry. Enable them with
+//! the `testing` feature, typically for dev-dependencies only.
+pub mod trace;
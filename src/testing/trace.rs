@@ -0,0 +1,414 @@
+//! # Trace Assertions
+//!
+//! A [`SpanTree`] arranges finished spans by their `parent_span_id`, so that
+//! tests can assert on the shape of a trace instead of individual spans.
+//! Assertions are made through a fluent [`SpanAssert`], which panics with the
+//! expected and actual values and a rendering of the whole tree when an
+//! expectation does not hold.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::api::{Provider, Span, Tracer};
+//! use opentelemetry::exporter::trace::in_memory::InMemorySpanExporter;
+//! use opentelemetry::sdk;
+//! use opentelemetry::testing::trace::SpanTree;
+//!
+//! let exporter = InMemorySpanExporter::default();
+//! let provider = sdk::Provider::builder()
+//!     .with_simple_exporter(exporter.clone())
+//!     .build();
+//! let tracer = provider.get_tracer("test");
+//!
+//! let checkout = tracer.start("checkout", None);
+//! for name in &["db.query", "db.query", "http.client"] {
+//!     tracer.start(name, Some(checkout.get_context())).end();
+//! }
+//! drop(checkout);
+//!
+//! SpanTree::new(exporter.get_finished_spans())
+//!     .assert_span("checkout")
+//!     .is_root()
+//!     .has_status(opentelemetry::api::StatusCode::OK)
+//!     .has_children(&["db.query", "db.query", "http.client"])
+//!     .is_single_trace();
+//! ```
+//!
+//! [`SpanTree`]: struct.SpanTree.html
+//! [`SpanAssert`]: struct.SpanAssert.html
+use crate::api;
+use crate::exporter::trace::SpanData;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// A span and its child spans, ordered by start time.
+#[derive(Clone, Debug)]
+pub struct SpanNode {
+    /// The span at this node
+    pub span: Arc<SpanData>,
+    /// The child spans, ordered by start time
+    pub children: Vec<SpanNode>,
+}
+
+impl SpanNode {
+    fn build(span: Arc<SpanData>, spans: &[Arc<SpanData>]) -> Self {
+        let span_id = span.context.span_id();
+        let mut children = spans
+            .iter()
+            .filter(|child| child.parent_span_id == span_id && child.context.span_id() != span_id)
+            .map(|child| SpanNode::build(child.clone(), spans))
+            .collect::<Vec<_>>();
+        children.sort_by_key(|child| child.span.start_time);
+
+        SpanNode { span, children }
+    }
+
+    /// Names of the child spans, ordered by start time.
+    pub fn child_names(&self) -> Vec<&str> {
+        self.children
+            .iter()
+            .map(|child| child.span.name.as_str())
+            .collect()
+    }
+
+    /// Depth first search for a span with the given name, including this node.
+    pub fn find(&self, name: &str) -> Option<&SpanNode> {
+        if self.span.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(name))
+    }
+
+    fn descendants(&self) -> Vec<&SpanNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.descendants());
+        }
+        nodes
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} ({:?}, {:?})",
+            "",
+            self.span.name,
+            self.span.span_kind,
+            self.span.status_code,
+            indent = depth * 2
+        )?;
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finished spans arranged as trees by their `parent_span_id`.
+///
+/// Spans whose parent is not part of the given spans are roots.
+#[derive(Clone, Debug)]
+pub struct SpanTree {
+    roots: Vec<SpanNode>,
+}
+
+impl SpanTree {
+    /// Build the tree from a set of finished spans.
+    pub fn new<I: IntoIterator<Item = Arc<SpanData>>>(spans: I) -> Self {
+        let spans = spans.into_iter().collect::<Vec<_>>();
+        let ids = spans
+            .iter()
+            .map(|span| span.context.span_id())
+            .collect::<HashSet<_>>();
+        let mut roots = spans
+            .iter()
+            .filter(|span| {
+                span.parent_span_id == span.context.span_id() || !ids.contains(&span.parent_span_id)
+            })
+            .map(|span| SpanNode::build(span.clone(), &spans))
+            .collect::<Vec<_>>();
+        roots.sort_by_key(|root| root.span.start_time);
+
+        SpanTree { roots }
+    }
+
+    /// The root spans, ordered by start time.
+    pub fn roots(&self) -> &[SpanNode] {
+        &self.roots
+    }
+
+    /// Depth first search for a span with the given name.
+    pub fn find(&self, name: &str) -> Option<&SpanNode> {
+        self.roots.iter().find_map(|root| root.find(name))
+    }
+
+    /// Start assertions on the first span with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no span with this name.
+    pub fn assert_span(&self, name: &str) -> SpanAssert<'_> {
+        match self.find(name) {
+            Some(node) => SpanAssert { tree: self, node },
+            None => panic!("no span named `{}` in trace:\n{}", name, self),
+        }
+    }
+}
+
+impl fmt::Display for SpanTree {
+    /// Renders one line per span, indented by depth.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for root in &self.roots {
+            root.fmt_indented(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fluent assertions on a span in a [`SpanTree`].
+///
+/// Every method panics if its expectation does not hold, and returns the
+/// assertion otherwise so that calls can be chained.
+///
+/// [`SpanTree`]: struct.SpanTree.html
+#[derive(Clone, Copy, Debug)]
+pub struct SpanAssert<'a> {
+    tree: &'a SpanTree,
+    node: &'a SpanNode,
+}
+
+impl<'a> SpanAssert<'a> {
+    /// The span under assertion.
+    pub fn span(&self) -> &'a SpanData {
+        &self.node.span
+    }
+
+    /// Assert that the span has no parent in the tree.
+    pub fn is_root(self) -> Self {
+        let is_root = self
+            .tree
+            .roots
+            .iter()
+            .any(|root| std::ptr::eq(root, self.node));
+        self.check(is_root, "is a root span", "true", "false")
+    }
+
+    /// Assert the span kind.
+    pub fn has_kind(self, kind: api::SpanKind) -> Self {
+        let actual = &self.node.span.span_kind;
+        self.check(
+            *actual == kind,
+            "kind",
+            format!("{:?}", kind),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert the span status code.
+    pub fn has_status(self, status: api::StatusCode) -> Self {
+        let actual = &self.node.span.status_code;
+        self.check(
+            *actual == status,
+            "status",
+            format!("{:?}", status),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert that the span has the given attribute.
+    pub fn has_attribute(self, attribute: api::KeyValue) -> Self {
+        let found = self
+            .node
+            .span
+            .attributes
+            .iter()
+            .any(|(key, value)| *key == attribute.key && *value == attribute.value);
+        let actual = self
+            .node
+            .span
+            .attributes
+            .iter()
+            .map(|(key, value)| format!("{}={:?}", key.as_str(), value))
+            .collect::<Vec<_>>();
+        self.check(
+            found,
+            "attribute",
+            format!("{}={:?}", attribute.key.as_str(), attribute.value),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert that the span has events with the given names, in this order.
+    pub fn has_events(self, names: &[&str]) -> Self {
+        let actual = self
+            .node
+            .span
+            .message_events
+            .iter()
+            .map(|event| event.name.as_str())
+            .collect::<Vec<_>>();
+        self.check(
+            actual == names,
+            "events",
+            format!("{:?}", names),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert that the span links to the given span context.
+    pub fn has_link_to(self, span_context: &api::SpanContext) -> Self {
+        let actual = self
+            .node
+            .span
+            .links
+            .iter()
+            .map(|link| link.span_context().span_id())
+            .collect::<Vec<_>>();
+        self.check(
+            actual.contains(&span_context.span_id()),
+            "link",
+            format!("{:?}", span_context.span_id()),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert the names of the child spans, in any order.
+    pub fn has_children(self, names: &[&str]) -> Self {
+        let mut expected = names.to_vec();
+        let mut actual = self.node.child_names();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        self.check(
+            actual == expected,
+            "children",
+            format!("{:?}", expected),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert the names of the child spans, ordered by start time.
+    pub fn has_children_in_order(self, names: &[&str]) -> Self {
+        let actual = self.node.child_names();
+        self.check(
+            actual == names,
+            "children in start order",
+            format!("{:?}", names),
+            format!("{:?}", actual),
+        )
+    }
+
+    /// Assert that the span and all its descendants belong to the same trace.
+    pub fn is_single_trace(self) -> Self {
+        let trace_id = self.node.span.context.trace_id();
+        let others = self
+            .node
+            .descendants()
+            .into_iter()
+            .filter(|node| node.span.context.trace_id() != trace_id)
+            .map(|node| node.span.name.as_str())
+            .collect::<Vec<_>>();
+        self.check(
+            others.is_empty(),
+            "spans in other traces",
+            "[]",
+            format!("{:?}", others),
+        )
+    }
+
+    /// Continue with assertions on the first child with the given name.
+    pub fn child(self, name: &str) -> Self {
+        match self
+            .node
+            .children
+            .iter()
+            .find(|child| child.span.name == name)
+        {
+            Some(node) => SpanAssert {
+                tree: self.tree,
+                node,
+            },
+            None => self.fail(
+                "child",
+                format!("{:?}", name),
+                format!("{:?}", self.node.child_names()),
+            ),
+        }
+    }
+
+    fn check<E: fmt::Display, A: fmt::Display>(
+        self,
+        ok: bool,
+        what: &str,
+        expected: E,
+        actual: A,
+    ) -> Self {
+        if !ok {
+            self.fail(what, expected, actual);
+        }
+        self
+    }
+
+    fn fail<E: fmt::Display, A: fmt::Display>(&self, what: &str, expected: E, actual: A) -> ! {
+        panic!(
+            "span `{}` {} mismatch\n  expected: {}\n    actual: {}\ntrace:\n{}",
+            self.node.span.name, what, expected, actual, self.tree
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanTree;
+    use crate::api::{Key, Provider, Span, Tracer};
+    use crate::exporter::trace::in_memory::InMemorySpanExporter;
+    use crate::{api, sdk};
+
+    fn tree() -> SpanTree {
+        let exporter = InMemorySpanExporter::default();
+        let provider = sdk::Provider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = provider.get_tracer("test");
+
+        let root = tracer.start("checkout", None);
+        root.set_attribute(Key::new("cart.items").i64(3));
+        root.add_event("validated".to_string(), Vec::new());
+        for name in &["db.query", "http.client", "db.query"] {
+            tracer.start(name, Some(root.get_context())).end();
+        }
+        drop(root);
+
+        SpanTree::new(exporter.get_finished_spans())
+    }
+
+    #[test]
+    fn matches_shape() {
+        tree()
+            .assert_span("checkout")
+            .is_root()
+            .has_kind(api::SpanKind::Internal)
+            .has_status(api::StatusCode::OK)
+            .has_attribute(Key::new("cart.items").i64(3))
+            .has_events(&["validated"])
+            .has_children(&["db.query", "db.query", "http.client"])
+            .has_children_in_order(&["db.query", "http.client", "db.query"])
+            .is_single_trace()
+            .child("http.client")
+            .has_children(&[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "span `checkout` children mismatch")]
+    fn reports_mismatch() {
+        tree()
+            .assert_span("checkout")
+            .has_children(&["db.query", "http.client"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no span named `missing`")]
+    fn reports_missing_span() {
+        tree().assert_span("missing");
+    }
+}
@@ -0,0 +1,104 @@
+//! Checks the number of allocations of the span start and end path.
+use opentelemetry::{
+    api::{Key, Provider, Span, Tracer},
+    sdk,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of spans started to measure allocations per span.
+const ITERATIONS: usize = 1000;
+
+/// Counts allocations of all threads of the test binary.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Return the average number of allocations per call of `f` for a tracer
+/// exporting to a noop exporter.
+fn allocations_per_span<F: Fn(&sdk::Tracer)>(sampler: sdk::Sampler, f: F) -> f64 {
+    let tracer = sdk::Provider::builder()
+        .with_simple_exporter(opentelemetry::api::NoopSpanExporter {})
+        .with_config(sdk::Config {
+            default_sampler: Box::new(sampler),
+            ..Default::default()
+        })
+        .build()
+        .get_tracer("allocations");
+
+    // Let one-time allocations, e.g. thread locals, happen before measuring.
+    tracer.start("warm-up", None).end();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        f(&tracer);
+    }
+
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ITERATIONS as f64
+}
+
+fn start_end(tracer: &sdk::Tracer) {
+    tracer.start("foo", None).end()
+}
+
+// The allocator counts the allocations of all threads, so all checks run in a
+// single test rather than in concurrently running tests.
+#[test]
+fn span_allocations() {
+    // Ending a span moves its data into an `Arc` handed to the processors,
+    // the data is not cloned. Cloning it would allocate the name and the
+    // attribute, event and link collections once more.
+    let allocations = allocations_per_span(sdk::Sampler::Always, start_end);
+    assert!(allocations <= 4.0, "{} allocations per span", allocations);
+
+    let allocations = allocations_per_span(sdk::Sampler::Always, |tracer| {
+        let span = tracer.start("foo", None);
+        for i in 0..32 {
+            span.set_attribute(Key::new(format!("key{}", i)).string("hello"));
+        }
+        for _ in 0..16 {
+            span.add_event(
+                "event".to_string(),
+                vec![Key::new("key").string("hello"), Key::new("count").i64(1)],
+            );
+        }
+        span.end();
+    });
+    assert!(allocations <= 192.0, "{} allocations per span", allocations);
+
+    // Spans that are not sampled do not allocate.
+    let allocations = allocations_per_span(sdk::Sampler::Never, start_end);
+    assert_eq!(allocations, 0.0);
+
+    let sampled = Cell::new(0);
+    let allocations = allocations_per_span(sdk::Sampler::Probability(0.01), |tracer| {
+        let span = tracer.start("foo", None);
+        if span.is_recording() {
+            sampled.set(sampled.get() + 1);
+        }
+        span.end()
+    });
+    assert!(allocations * ITERATIONS as f64 <= sampled.get() as f64 * 4.0);
+
+    let allocations = allocations_per_span(sdk::Sampler::Always, |tracer| tracer.invalid().end());
+    assert_eq!(allocations, 0.0);
+}